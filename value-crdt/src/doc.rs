@@ -0,0 +1,510 @@
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use value::{
+    pointer::{parse_index, parse_pointer},
+    Map, Value,
+};
+
+use crate::{CrdtError, Id, NewValue, Op, OpKind, ReplicaId, Version};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Slot {
+    Value(Value),
+    Node(Id),
+}
+
+/// A map entry, `None` once deleted.
+#[derive(Debug, Clone, PartialEq)]
+struct Register {
+    id: Id,
+    slot: Option<Slot>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Item {
+    id: Id,
+    slot: Slot,
+    removed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Map(BTreeMap<String, Register>),
+    List(Vec<Item>),
+    Counter(i64),
+}
+
+/// Every operation of a document, enough to restore it on any replica.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Snapshot {
+    pub ops: Vec<Op>,
+}
+
+/// A replica of a collaboratively edited document, see the
+/// [crate docs](crate).
+#[derive(Debug, Clone)]
+pub struct Doc {
+    replica: ReplicaId,
+    clock: u64,
+    ops: BTreeMap<Id, Op>,
+    nodes: BTreeMap<Id, Node>,
+    /// The smallest operation whose node or item was missing when it was
+    /// integrated. An operation arriving with a smaller id may be what it
+    /// was missing, so the document is rebuilt.
+    first_orphan: Option<Id>,
+}
+
+impl Doc {
+    pub fn new(replica: ReplicaId) -> Doc {
+        Doc {
+            replica,
+            clock: 0,
+            ops: BTreeMap::new(),
+            nodes: BTreeMap::from([(Id::ROOT, Node::Map(BTreeMap::new()))]),
+            first_orphan: None,
+        }
+    }
+
+    /// Restore a document from a snapshot, to continue editing as `replica`.
+    pub fn from_snapshot(replica: ReplicaId, snapshot: Snapshot) -> Doc {
+        let mut doc = Doc::new(replica);
+        doc.apply(snapshot.ops);
+        doc
+    }
+
+    pub fn replica(&self) -> ReplicaId {
+        self.replica
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            ops: self.ops.values().cloned().collect(),
+        }
+    }
+
+    /// The document as a plain value.
+    pub fn to_value(&self) -> Value {
+        self.node_value(Id::ROOT).unwrap_or(Value::None)
+    }
+
+    /// The value of the node `id`, if it exists.
+    pub fn node_value(&self, id: Id) -> Option<Value> {
+        let value = match self.nodes.get(&id)? {
+            Node::Map(map) => Value::Map(
+                map.iter()
+                    .filter_map(|(k, reg)| Some((k.clone(), self.slot_value(reg.slot.as_ref()?))))
+                    .collect::<Map>(),
+            ),
+            Node::List(items) => Value::List(
+                items
+                    .iter()
+                    .filter(|item| !item.removed)
+                    .map(|item| self.slot_value(&item.slot))
+                    .collect(),
+            ),
+            Node::Counter(n) => Value::from(*n),
+        };
+        Some(value)
+    }
+
+    /// The id of the map, list or counter at a JSON Pointer like `/todos/0`.
+    pub fn node(&self, pointer: &str) -> Option<Id> {
+        if pointer.is_empty() {
+            return Some(Id::ROOT);
+        }
+        parse_pointer(pointer)
+            .ok()?
+            .into_iter()
+            .try_fold(Id::ROOT, |id, token| {
+                let slot = match self.nodes.get(&id)? {
+                    Node::Map(map) => map.get(&token)?.slot.as_ref()?,
+                    Node::List(items) => {
+                        let idx = parse_index(&token)?;
+                        &items.iter().filter(|item| !item.removed).nth(idx)?.slot
+                    }
+                    Node::Counter(_) => return None,
+                };
+                match slot {
+                    Slot::Node(id) => Some(*id),
+                    Slot::Value(_) => None,
+                }
+            })
+    }
+
+    /// Set `key` of the map `obj`. Returns the operation id, which is also
+    /// the id of the node created for a [`NewValue::Map`], `List` or
+    /// `Counter`.
+    pub fn set(
+        &mut self,
+        obj: Id,
+        key: impl Into<String>,
+        value: impl Into<NewValue>,
+    ) -> Result<Id, CrdtError> {
+        self.map(obj)?;
+        Ok(self.commit(OpKind::Set {
+            obj,
+            key: key.into(),
+            value: value.into(),
+        }))
+    }
+
+    pub fn delete(&mut self, obj: Id, key: &str) -> Result<Id, CrdtError> {
+        match self.map(obj)?.get(key) {
+            Some(Register { slot: Some(_), .. }) => Ok(self.commit(OpKind::Delete {
+                obj,
+                key: key.to_owned(),
+            })),
+            _ => Err(CrdtError::MissingKey(key.to_owned())),
+        }
+    }
+
+    /// Insert into the list `obj` so the value ends up at `index`.
+    pub fn insert(
+        &mut self,
+        obj: Id,
+        index: usize,
+        value: impl Into<NewValue>,
+    ) -> Result<Id, CrdtError> {
+        let after = match index {
+            0 => None,
+            index => Some(self.visible_item(obj, index - 1)?),
+        };
+        if index == 0 {
+            self.list(obj)?;
+        }
+        Ok(self.commit(OpKind::Insert {
+            obj,
+            after,
+            value: value.into(),
+        }))
+    }
+
+    pub fn push(&mut self, obj: Id, value: impl Into<NewValue>) -> Result<Id, CrdtError> {
+        let len = self.list(obj)?.iter().filter(|item| !item.removed).count();
+        self.insert(obj, len, value)
+    }
+
+    pub fn remove(&mut self, obj: Id, index: usize) -> Result<Id, CrdtError> {
+        let item = self.visible_item(obj, index)?;
+        Ok(self.commit(OpKind::Remove { obj, item }))
+    }
+
+    pub fn increment(&mut self, obj: Id, by: i64) -> Result<Id, CrdtError> {
+        match self.nodes.get(&obj) {
+            Some(Node::Counter(_)) => Ok(self.commit(OpKind::Increment { obj, by })),
+            Some(_) => Err(CrdtError::InvalidType {
+                id: obj,
+                expected: "counter",
+            }),
+            None => Err(CrdtError::NotFound(obj)),
+        }
+    }
+
+    pub fn ops(&self) -> impl Iterator<Item = &Op> {
+        self.ops.values()
+    }
+
+    /// The greatest operation counter seen from every replica.
+    pub fn version(&self) -> Version {
+        let mut version = Version::new();
+        for id in self.ops.keys() {
+            let counter = version.entry(id.replica).or_default();
+            *counter = (*counter).max(id.counter);
+        }
+        version
+    }
+
+    /// The operations a peer at `version` hasn't seen.
+    pub fn ops_since(&self, version: &Version) -> Vec<Op> {
+        self.ops
+            .values()
+            .filter(|op| op.id.counter > version.get(&op.id.replica).copied().unwrap_or(0))
+            .cloned()
+            .collect()
+    }
+
+    /// Apply operations from other replicas. Known operations are skipped
+    /// and operations on unknown nodes are ignored, so the result only
+    /// depends on the set of operations seen.
+    ///
+    /// Operations are integrated one by one as long as every node and list
+    /// item they refer to is known, which holds whenever replicas exchange
+    /// all their operations. Only when an operation may supply what an
+    /// earlier one was missing is the document rebuilt by replaying every
+    /// operation, which costs time linear in the document's history.
+    pub fn apply(&mut self, ops: impl IntoIterator<Item = Op>) {
+        let mut new = ops
+            .into_iter()
+            .filter(|op| !self.ops.contains_key(&op.id))
+            .collect::<Vec<_>>();
+        if new.is_empty() {
+            return;
+        }
+        new.sort_by_key(|op| op.id);
+        new.dedup_by_key(|op| op.id);
+
+        let mut rebuild = false;
+        for op in new {
+            self.clock = self.clock.max(op.id.counter);
+            rebuild = rebuild || self.first_orphan.is_some_and(|orphan| op.id < orphan);
+            if !rebuild && !self.integrate(&op) {
+                self.first_orphan = Some(self.first_orphan.map_or(op.id, |id| id.min(op.id)));
+            }
+            self.ops.insert(op.id, op);
+        }
+        if rebuild {
+            self.rebuild();
+        }
+    }
+
+    /// Merge every operation of `other` into this replica.
+    pub fn merge(&mut self, other: &Doc) {
+        self.apply(other.ops.values().cloned());
+    }
+
+    fn commit(&mut self, kind: OpKind) -> Id {
+        self.clock += 1;
+        let op = Op {
+            id: Id::new(self.clock, self.replica),
+            kind,
+        };
+        self.integrate(&op);
+        let id = op.id;
+        self.ops.insert(id, op);
+        id
+    }
+
+    /// Replay all operations in id order, which is a causal order.
+    fn rebuild(&mut self) {
+        self.nodes = BTreeMap::from([(Id::ROOT, Node::Map(BTreeMap::new()))]);
+        self.first_orphan = None;
+        let ops = std::mem::take(&mut self.ops);
+        for op in ops.values() {
+            if !self.integrate(op) && self.first_orphan.is_none() {
+                self.first_orphan = Some(op.id);
+            }
+        }
+        self.ops = ops;
+    }
+
+    /// Apply `op` to the nodes, returning false if the node or item it
+    /// refers to doesn't exist.
+    fn integrate(&mut self, op: &Op) -> bool {
+        match &op.kind {
+            OpKind::Set { obj, key, value } => {
+                let slot = self.create(op.id, value);
+                self.write(*obj, key, op.id, Some(slot))
+            }
+            OpKind::Delete { obj, key } => self.write(*obj, key, op.id, None),
+            OpKind::Insert { obj, after, value } => {
+                let slot = self.create(op.id, value);
+                let Some(Node::List(items)) = self.nodes.get_mut(obj) else {
+                    return false;
+                };
+                let mut pos = match after {
+                    None => 0,
+                    Some(after) => match items.iter().position(|item| item.id == *after) {
+                        Some(pos) => pos + 1,
+                        None => return false,
+                    },
+                };
+                // Concurrent inserts after the same item, and everything
+                // inserted after them, go first when their id is greater.
+                while pos < items.len() && items[pos].id > op.id {
+                    pos += 1;
+                }
+                items.insert(
+                    pos,
+                    Item {
+                        id: op.id,
+                        slot,
+                        removed: false,
+                    },
+                );
+                true
+            }
+            OpKind::Remove { obj, item } => match self.nodes.get_mut(obj) {
+                Some(Node::List(items)) => match items.iter_mut().find(|i| i.id == *item) {
+                    Some(item) => {
+                        item.removed = true;
+                        true
+                    }
+                    None => false,
+                },
+                _ => false,
+            },
+            OpKind::Increment { obj, by } => match self.nodes.get_mut(obj) {
+                Some(Node::Counter(n)) => {
+                    *n = n.wrapping_add(*by);
+                    true
+                }
+                _ => false,
+            },
+        }
+    }
+
+    /// Last writer wins: keep the write with the greatest id.
+    fn write(&mut self, obj: Id, key: &str, id: Id, slot: Option<Slot>) -> bool {
+        let Some(Node::Map(map)) = self.nodes.get_mut(&obj) else {
+            return false;
+        };
+        match map.get(key) {
+            Some(reg) if reg.id > id => {}
+            _ => {
+                map.insert(key.to_owned(), Register { id, slot });
+            }
+        }
+        true
+    }
+
+    /// Create the node for a new value. Nodes are kept even if the write
+    /// loses, so later operations on them still apply.
+    fn create(&mut self, id: Id, value: &NewValue) -> Slot {
+        let node = match value {
+            NewValue::Value(value) => return Slot::Value(value.clone()),
+            NewValue::Map => Node::Map(BTreeMap::new()),
+            NewValue::List => Node::List(Vec::new()),
+            NewValue::Counter => Node::Counter(0),
+        };
+        self.nodes.insert(id, node);
+        Slot::Node(id)
+    }
+
+    fn slot_value(&self, slot: &Slot) -> Value {
+        match slot {
+            Slot::Value(value) => value.clone(),
+            Slot::Node(id) => self.node_value(*id).unwrap_or(Value::None),
+        }
+    }
+
+    fn map(&self, obj: Id) -> Result<&BTreeMap<String, Register>, CrdtError> {
+        match self.nodes.get(&obj) {
+            Some(Node::Map(map)) => Ok(map),
+            Some(_) => Err(CrdtError::InvalidType {
+                id: obj,
+                expected: "map",
+            }),
+            None => Err(CrdtError::NotFound(obj)),
+        }
+    }
+
+    fn list(&self, obj: Id) -> Result<&[Item], CrdtError> {
+        match self.nodes.get(&obj) {
+            Some(Node::List(items)) => Ok(items),
+            Some(_) => Err(CrdtError::InvalidType {
+                id: obj,
+                expected: "list",
+            }),
+            None => Err(CrdtError::NotFound(obj)),
+        }
+    }
+
+    fn visible_item(&self, obj: Id, index: usize) -> Result<Id, CrdtError> {
+        let items = self.list(obj)?;
+        let mut visible = items.iter().filter(|item| !item.removed);
+        match visible.nth(index) {
+            Some(item) => Ok(item.id),
+            None => Err(CrdtError::OutOfBounds {
+                index,
+                len: items.iter().filter(|item| !item.removed).count(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sync(a: &mut Doc, b: &mut Doc) {
+        let (to_b, to_a) = (a.ops_since(&b.version()), b.ops_since(&a.version()));
+        b.apply(to_b);
+        a.apply(to_a);
+    }
+
+    #[test]
+    fn concurrent_edits_converge() {
+        let mut a = Doc::new(1);
+        let todos = a.set(Id::ROOT, "todos", NewValue::List).unwrap();
+        let likes = a.set(Id::ROOT, "likes", NewValue::Counter).unwrap();
+        a.push(todos, "milk").unwrap();
+        let mut b = Doc::from_snapshot(2, a.snapshot());
+
+        a.set(Id::ROOT, "title", "a").unwrap();
+        a.insert(todos, 0, "eggs").unwrap();
+        a.increment(likes, 2).unwrap();
+        b.set(Id::ROOT, "title", "b").unwrap();
+        b.insert(todos, 0, "bread").unwrap();
+        b.remove(todos, 1).unwrap();
+        b.increment(likes, 3).unwrap();
+
+        let mut c = b.clone();
+        c.merge(&a);
+        sync(&mut a, &mut b);
+
+        assert_eq!(a.to_value(), b.to_value());
+        assert_eq!(a.to_value(), c.to_value());
+        let value = a.to_value();
+        assert_eq!(value["title"], Value::from("b"));
+        assert_eq!(value["likes"], Value::from(5i64));
+        assert_eq!(
+            value["todos"],
+            Value::List(vec!["bread".into(), "eggs".into()])
+        );
+        assert_eq!(a.node("/todos"), Some(todos));
+        assert_eq!(a.node("/todos/01"), None);
+        assert_eq!(a.node("/todos/+1"), None);
+    }
+
+    #[test]
+    fn out_of_order_delivery() {
+        let mut a = Doc::new(1);
+        let todos = a.set(Id::ROOT, "todos", NewValue::List).unwrap();
+        a.push(todos, "milk").unwrap();
+        let item = a.insert(todos, 0, NewValue::Map).unwrap();
+        a.set(item, "done", true).unwrap();
+        a.remove(todos, 1).unwrap();
+
+        let mut ops = a.ops_since(&Version::new());
+        ops.reverse();
+        let mut b = Doc::new(2);
+        for op in ops {
+            b.apply([op]);
+        }
+        assert_eq!(a.to_value(), b.to_value());
+        assert_eq!(b.first_orphan, None);
+    }
+
+    #[test]
+    fn errors() {
+        let mut doc = Doc::new(1);
+        let list = doc.set(Id::ROOT, "list", NewValue::List).unwrap();
+        assert_eq!(
+            doc.remove(list, 0),
+            Err(CrdtError::OutOfBounds { index: 0, len: 0 })
+        );
+        assert!(matches!(
+            doc.increment(list, 1),
+            Err(CrdtError::InvalidType { .. })
+        ));
+        assert_eq!(
+            doc.delete(Id::ROOT, "missing"),
+            Err(CrdtError::MissingKey("missing".into()))
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshot_round_trip() {
+        let mut doc = Doc::new(1);
+        let map = doc.set(Id::ROOT, "nested", NewValue::Map).unwrap();
+        doc.set(map, "answer", 42u8).unwrap();
+
+        let json = serde_json::to_string(&doc.snapshot()).unwrap();
+        let restored = Doc::from_snapshot(2, serde_json::from_str(&json).unwrap());
+        assert_eq!(restored.to_value(), doc.to_value());
+    }
+}
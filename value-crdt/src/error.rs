@@ -0,0 +1,15 @@
+use thiserror::Error as ThisError;
+
+use crate::Id;
+
+#[derive(Debug, Clone, PartialEq, Eq, ThisError)]
+pub enum CrdtError {
+    #[error("no node with id {0}")]
+    NotFound(Id),
+    #[error("node {id} is not a {expected}")]
+    InvalidType { id: Id, expected: &'static str },
+    #[error("index {index} out of bounds for list of length {len}")]
+    OutOfBounds { index: usize, len: usize },
+    #[error("map has no key {0:?}")]
+    MissingKey(String),
+}
@@ -0,0 +1,42 @@
+use std::{collections::BTreeMap, fmt};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Identifies a replica. Every replica editing a document needs its own.
+pub type ReplicaId = u64;
+
+/// A Lamport timestamp, unique per operation. Ids are ordered by counter,
+/// ties broken by replica, which orders every causally later operation after
+/// the operations it has seen.
+///
+/// Container nodes are identified by the id of the operation creating them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Id {
+    pub counter: u64,
+    pub replica: ReplicaId,
+}
+
+impl Id {
+    /// The root map of every document.
+    pub const ROOT: Id = Id {
+        counter: 0,
+        replica: 0,
+    };
+
+    pub const fn new(counter: u64, replica: ReplicaId) -> Id {
+        Id { counter, replica }
+    }
+}
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.counter, self.replica)
+    }
+}
+
+/// The greatest counter seen from every replica, see [`Doc::ops_since`].
+///
+/// [`Doc::ops_since`]: crate::Doc::ops_since
+pub type Version = BTreeMap<ReplicaId, u64>;
@@ -0,0 +1,26 @@
+//! A JSON CRDT over `Value` documents.
+//!
+//! A [`Doc`] is a tree of maps, lists, counters and plain `Value` leaves.
+//! Every change is an [`Op`] stamped with a Lamport [`Id`], and replicas that
+//! have seen the same set of operations hold the same document, whatever
+//! order the operations arrived in:
+//!
+//! - map keys are last-writer-wins registers, so of two concurrent writes to
+//!   a key the one with the greater id is kept, deletes included;
+//! - lists are RGA sequences, concurrent inserts at the same position are
+//!   ordered by id and removed items leave a tombstone;
+//! - counters sum the increments of all replicas.
+//!
+//! The operation log is the state: exchange [`Doc::ops_since`] between peers
+//! or persist a [`Snapshot`].
+mod doc;
+mod error;
+mod id;
+mod op;
+
+pub use self::{
+    doc::{Doc, Snapshot},
+    error::CrdtError,
+    id::{Id, ReplicaId, Version},
+    op::{NewValue, Op, OpKind},
+};
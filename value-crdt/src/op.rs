@@ -0,0 +1,58 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use value::Value;
+
+use crate::Id;
+
+/// A single change to a document.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Op {
+    pub id: Id,
+    pub kind: OpKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum OpKind {
+    /// Set `key` of the map `obj`.
+    Set {
+        obj: Id,
+        key: String,
+        value: NewValue,
+    },
+    /// Delete `key` of the map `obj`.
+    Delete { obj: Id, key: String },
+    /// Insert into the list `obj` after the item `after`, or at the front.
+    /// The new item is identified by the operation's id.
+    Insert {
+        obj: Id,
+        after: Option<Id>,
+        value: NewValue,
+    },
+    /// Remove the item `item` of the list `obj`.
+    Remove { obj: Id, item: Id },
+    /// Add `by` to the counter `obj`.
+    Increment { obj: Id, by: i64 },
+}
+
+/// The value written by a set or insert.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NewValue {
+    /// A leaf, replaced as a whole. Maps and lists in it can't be edited
+    /// concurrently.
+    Value(Value),
+    /// An empty map node.
+    Map,
+    /// An empty list node.
+    List,
+    /// A counter node starting at zero.
+    Counter,
+}
+
+impl<T: Into<Value>> From<T> for NewValue {
+    fn from(value: T) -> Self {
+        NewValue::Value(value.into())
+    }
+}
@@ -2,7 +2,7 @@ use rquickjs::{
     Array as JsArray, Array, Ctx, Null as JsNull, Object, Result, String as JsString, Type,
     TypedArray, Value as JsValue,
 };
-use value::{Map, Value};
+use value::{LimitError, Limits, Map, Value};
 
 pub fn into_js<'js>(ctx: Ctx<'js>, value: Value) -> Result<JsValue> {
     match value {
@@ -34,6 +34,14 @@ pub fn into_js<'js>(ctx: Ctx<'js>, value: Value) -> Result<JsValue> {
 
             Ok(o.into_value())
         }
+        Value::Dict(dict) => match dict.into_string_keyed() {
+            Ok(map) => into_js(ctx, Value::Map(map)),
+            Err(err) => Err(rquickjs::Error::new_into_js_message(
+                "Value",
+                "Object",
+                err.to_string(),
+            )),
+        },
         Value::Bytes(bs) => Ok(TypedArray::new(ctx, bs)?.into_value()),
         #[cfg(feature = "datetime")]
         Value::DateTime(datetime) => {
@@ -66,23 +74,59 @@ macro_rules! call {
     }};
 }
 
+/// Convert a JS value without bounds, see [`from_js_with_limits`] for values
+/// produced by untrusted scripts.
 pub fn from_js<'js>(ctx: Ctx<'js>, value: JsValue<'js>) -> Result<Value> {
+    from_js_with_limits(ctx, value, &Limits::unlimited())
+}
+
+/// Convert a JS value, failing if it exceeds `limits`. Depth is counted like
+/// [`Value::check_limits`], so both accept the same values.
+pub fn from_js_with_limits<'js>(
+    ctx: Ctx<'js>,
+    value: JsValue<'js>,
+    limits: &Limits,
+) -> Result<Value> {
+    let mut nodes = 0;
+    from_js_inner(ctx, value, limits, &mut nodes, 0)
+}
+
+fn limit_error(err: LimitError) -> rquickjs::Error {
+    rquickjs::Error::new_from_js_message("value", "Value", err.to_string())
+}
+
+fn from_js_inner<'js>(
+    ctx: Ctx<'js>,
+    value: JsValue<'js>,
+    limits: &Limits,
+    nodes: &mut usize,
+    depth: usize,
+) -> Result<Value> {
+    *nodes += 1;
+    limits.check_nodes(*nodes).map_err(limit_error)?;
+    limits.check_depth(depth).map_err(limit_error)?;
+
     match value.type_of() {
         Type::Array => {
             let array = value.into_array().unwrap();
             let list = array
                 .iter::<JsValue>()
-                .map(|m| from_js(ctx, m.unwrap()))
+                .map(|m| from_js_inner(ctx, m?, limits, nodes, depth + 1))
                 .collect::<Result<_>>()?;
             Ok(Value::List(list))
         }
-        Type::String => Ok(Value::String(value.get()?)),
+        Type::String => {
+            let s: String = value.get()?;
+            limits.check_string_len(s.len()).map_err(limit_error)?;
+            Ok(Value::String(s))
+        }
         Type::Null | Type::Undefined | Type::Uninitialized => Ok(Value::None),
         Type::Object => {
             let date = ctx.globals().get::<_, JsValue>("Date")?;
 
             if let Ok(bytes) = TypedArray::<u8>::from_value(value.clone()) {
                 let bs: &[u8] = bytes.as_ref();
+                limits.check_bytes_len(bs.len()).map_err(limit_error)?;
                 return Ok(Value::Bytes(bs.to_vec()));
             } else if value.as_object().unwrap().is_instance_of(&date) {
                 #[cfg(feature = "datetime")]
@@ -109,7 +153,9 @@ pub fn from_js<'js>(ctx: Ctx<'js>, value: JsValue<'js>) -> Result<Value> {
             for v in obj.into_iter() {
                 let (k, v) = v?;
                 let k = k.to_string()?;
-                out.insert(k, from_js(ctx, v)?);
+                limits.check_string_len(k.len()).map_err(limit_error)?;
+                limits.check_map_keys(out.len() + 1).map_err(limit_error)?;
+                out.insert(k, from_js_inner(ctx, v, limits, nodes, depth + 1)?);
             }
 
             Ok(Value::Map(out))
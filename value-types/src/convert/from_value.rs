@@ -1,7 +1,11 @@
-use std::{
-    collections::{BTreeMap, HashMap},
-    convert::Infallible,
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
 };
+use core::convert::Infallible;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
 
 use value::{FromValueErr, Value};
 
@@ -78,6 +82,7 @@ where
     }
 }
 
+#[cfg(feature = "std")]
 impl<'a, T> FromValue for HashMap<String, T>
 where
     T: FromValue<Error = FromValueErr<'a>>,
@@ -0,0 +1,538 @@
+//! Spreadsheet style CSV as lists of records.
+//!
+//! Every row becomes a [`Value::Map`] keyed by the header row. Headers with
+//! dots, like `address.city`, produce nested maps and the writer flattens
+//! nested maps back into dotted columns. Without a schema every cell is read
+//! as a string; with a [`TypeDef`] each column is parsed as the type of the
+//! matching struct field.
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fmt, io,
+};
+
+use value::{Map, Number, Value};
+
+use crate::{StructDef, TypeDef, ValueType};
+
+#[derive(Debug)]
+pub enum CsvError {
+    Csv(::csv::Error),
+    /// A header that is empty, repeated or both a value and a parent of
+    /// another header, like `a` and `a.b`.
+    InvalidHeader {
+        column: String,
+    },
+    /// A cell that doesn't parse as the type the schema expects.
+    InvalidCell {
+        line: u64,
+        column: String,
+        expected: ValueType,
+        value: String,
+    },
+    /// A record passed to the writer that isn't a map.
+    NotAMap {
+        row: usize,
+    },
+    /// A value with no cell representation, like a list.
+    Unsupported {
+        row: usize,
+        column: String,
+    },
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Csv(err) => write!(f, "csv error: {}", err),
+            CsvError::InvalidHeader { column } => write!(f, "invalid header: '{}'", column),
+            CsvError::InvalidCell {
+                line,
+                column,
+                expected,
+                value,
+            } => write!(
+                f,
+                "line {}, column '{}': expected {:?}, found '{}'",
+                line, column, expected, value
+            ),
+            CsvError::NotAMap { row } => write!(f, "row {}: expected a map", row),
+            CsvError::Unsupported { row, column } => {
+                write!(
+                    f,
+                    "row {}, column '{}': value can't be written to a cell",
+                    row, column
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for CsvError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CsvError::Csv(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<::csv::Error> for CsvError {
+    fn from(err: ::csv::Error) -> Self {
+        CsvError::Csv(err)
+    }
+}
+
+/// Column and type of a header.
+struct Column {
+    path: Vec<String>,
+    ty: Option<TypeDef<Cow<'static, str>>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvReader {
+    delimiter: u8,
+    schema: Option<StructDef<Cow<'static, str>>>,
+}
+
+impl Default for CsvReader {
+    fn default() -> Self {
+        CsvReader::new()
+    }
+}
+
+impl CsvReader {
+    pub fn new() -> CsvReader {
+        CsvReader {
+            delimiter: b',',
+            schema: None,
+        }
+    }
+
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Parse columns as the fields of `schema`, looking up dotted headers in
+    /// nested structs. Columns missing from the schema stay strings and an
+    /// empty cell is `None` for optional fields.
+    pub fn schema<S: Into<Cow<'static, str>>>(mut self, schema: StructDef<S>) -> Self {
+        self.schema = Some(schema.to_owned());
+        self
+    }
+
+    /// Read all records into a [`Value::List`].
+    pub fn read<R: io::Read>(&self, reader: R) -> Result<Value, CsvError> {
+        self.records(reader)?
+            .collect::<Result<_, _>>()
+            .map(Value::List)
+    }
+
+    pub fn read_str(&self, input: &str) -> Result<Value, CsvError> {
+        self.read(input.as_bytes())
+    }
+
+    /// Read records one at a time. Fails early if the header row is invalid.
+    pub fn records<R: io::Read>(
+        &self,
+        reader: R,
+    ) -> Result<impl Iterator<Item = Result<Value, CsvError>>, CsvError> {
+        let mut reader = ::csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .from_reader(reader);
+        let columns = self.columns(reader.headers()?)?;
+        Ok(reader
+            .into_records()
+            .map(move |record| read_record(&columns, &record?)))
+    }
+
+    fn columns(&self, headers: &::csv::StringRecord) -> Result<Vec<Column>, CsvError> {
+        check_headers(headers)?;
+        Ok(headers
+            .iter()
+            .map(|header| {
+                let path = header.split('.').map(String::from).collect::<Vec<_>>();
+                let ty = self.schema.as_ref().and_then(|schema| field(schema, &path));
+                Column {
+                    path,
+                    ty: ty.cloned(),
+                }
+            })
+            .collect())
+    }
+}
+
+/// Reject headers that are empty, repeated or a parent of another header.
+fn check_headers<'a, I>(headers: I) -> Result<(), CsvError>
+where
+    I: IntoIterator<Item = &'a str>,
+    I::IntoIter: Clone,
+{
+    let invalid = |column: &str| CsvError::InvalidHeader {
+        column: column.to_string(),
+    };
+    let headers = headers.into_iter();
+    let mut seen = HashSet::new();
+    for header in headers.clone() {
+        if header.split('.').any(str::is_empty) || !seen.insert(header) {
+            return Err(invalid(header));
+        }
+    }
+    for header in headers {
+        let parent = header
+            .match_indices('.')
+            .map(|(idx, _)| &header[..idx])
+            .find(|parent| seen.contains(parent));
+        if let Some(parent) = parent {
+            return Err(invalid(parent));
+        }
+    }
+    Ok(())
+}
+
+fn field<'a>(
+    schema: &'a StructDef<Cow<'static, str>>,
+    path: &[String],
+) -> Option<&'a TypeDef<Cow<'static, str>>> {
+    let (first, rest) = path.split_first()?;
+    let (_, ty) = schema
+        .fields()
+        .iter()
+        .find(|(name, _)| name.as_ref() == first)?;
+    if rest.is_empty() {
+        return Some(ty);
+    }
+    match ty {
+        TypeDef::Struct(schema) => field(schema, rest),
+        TypeDef::Optional(ty) => match &**ty {
+            TypeDef::Struct(schema) => field(schema, rest),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn read_record(columns: &[Column], record: &::csv::StringRecord) -> Result<Value, CsvError> {
+    let line = record.position().map_or(0, |pos| pos.line());
+    let mut row = Map::new();
+    for (column, cell) in columns.iter().zip(record.iter()) {
+        let value = match &column.ty {
+            Some(ty) => parse(ty, cell).map_err(|expected| CsvError::InvalidCell {
+                line,
+                column: column.path.join("."),
+                expected,
+                value: cell.to_string(),
+            })?,
+            None => Value::String(cell.to_string()),
+        };
+        let (last, parents) = column.path.split_last().expect("header is not empty");
+        let mut map = &mut row;
+        for key in parents {
+            let entry = map
+                .entry(key.clone())
+                .or_insert_with(|| Value::Map(Map::new()));
+            map = match entry {
+                Value::Map(map) => map,
+                _ => unreachable!("headers are validated"),
+            };
+        }
+        map.insert(last.clone(), value);
+    }
+    Ok(Value::Map(row))
+}
+
+/// Parse a cell as `ty`, returning the expected type on failure.
+fn parse(ty: &TypeDef<Cow<'static, str>>, cell: &str) -> Result<Value, ValueType> {
+    match ty {
+        TypeDef::Optional(_) if cell.is_empty() => Ok(Value::None),
+        TypeDef::Optional(ty) => parse(ty, cell),
+        TypeDef::Union(union) => {
+            let mut expected = ValueType::String;
+            for ty in union.types() {
+                match parse(ty, cell) {
+                    Ok(value) => return Ok(value),
+                    Err(ty) => expected = ty,
+                }
+            }
+            Err(expected)
+        }
+        TypeDef::Value(ty) => parse_value(*ty, cell).ok_or(*ty),
+        _ => Ok(Value::String(cell.to_string())),
+    }
+}
+
+fn parse_value(ty: ValueType, cell: &str) -> Option<Value> {
+    let cell = match ty {
+        ValueType::String | ValueType::Bytes => cell,
+        _ => cell.trim(),
+    };
+    let value = match ty {
+        ValueType::U8 => Number::U8(cell.parse().ok()?).into(),
+        ValueType::U16 => Number::U16(cell.parse().ok()?).into(),
+        ValueType::U32 => Number::U32(cell.parse().ok()?).into(),
+        ValueType::U64 => Number::U64(cell.parse().ok()?).into(),
+        ValueType::I8 => Number::I8(cell.parse().ok()?).into(),
+        ValueType::I16 => Number::I16(cell.parse().ok()?).into(),
+        ValueType::I32 => Number::I32(cell.parse().ok()?).into(),
+        ValueType::I64 => Number::I64(cell.parse().ok()?).into(),
+        ValueType::F32 => Number::from(cell.parse::<f32>().ok()?).into(),
+        ValueType::F64 => Number::from(cell.parse::<f64>().ok()?).into(),
+        ValueType::Bool => match cell.to_ascii_lowercase().as_str() {
+            "true" | "1" => Value::Bool(true),
+            "false" | "0" => Value::Bool(false),
+            _ => return None,
+        },
+        ValueType::Char => {
+            let mut chars = cell.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Value::Char(c),
+                _ => return None,
+            }
+        }
+        ValueType::String => Value::String(cell.to_string()),
+        ValueType::Bytes => Value::Bytes(cell.as_bytes().to_vec()),
+        ValueType::None if cell.is_empty() => Value::None,
+        #[cfg(feature = "datetime")]
+        ValueType::Date => Value::Date(cell.parse().ok()?),
+        #[cfg(feature = "datetime")]
+        ValueType::DateTime => Value::DateTime(cell.parse().ok()?),
+        ValueType::None | ValueType::List | ValueType::Map | ValueType::Dict => return None,
+    };
+    Some(value)
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvWriter {
+    delimiter: u8,
+    headers: Option<Vec<String>>,
+}
+
+impl Default for CsvWriter {
+    fn default() -> Self {
+        CsvWriter::new()
+    }
+}
+
+impl CsvWriter {
+    pub fn new() -> CsvWriter {
+        CsvWriter {
+            delimiter: b',',
+            headers: None,
+        }
+    }
+
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Write exactly these columns, in this order. By default the columns
+    /// are every dotted key in the order they first appear, with the keys of
+    /// each map in sorted order.
+    pub fn headers<I, S>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.headers = Some(headers.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Write a list of maps. Missing cells and `None` are written empty.
+    ///
+    /// The columns must read back with [`CsvReader`], so a key containing a
+    /// `.` that clashes with a nested key, or a map that is both a value and
+    /// a parent in different rows, is an [`CsvError::InvalidHeader`].
+    pub fn write<'a, W, I>(&self, writer: W, records: I) -> Result<(), CsvError>
+    where
+        W: io::Write,
+        I: IntoIterator<Item = &'a Value>,
+    {
+        let rows = records
+            .into_iter()
+            .enumerate()
+            .map(|(row, record)| match record {
+                Value::Map(map) => {
+                    let mut cells = Vec::new();
+                    flatten(row, String::new(), map, &mut cells)?;
+                    Ok(cells)
+                }
+                _ => Err(CsvError::NotAMap { row }),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let headers = match &self.headers {
+            Some(headers) => headers.clone(),
+            None => {
+                let mut seen = HashSet::new();
+                let mut headers = Vec::<String>::new();
+                for (column, _) in rows.iter().flatten() {
+                    if seen.insert(column.as_str()) {
+                        headers.push(column.clone());
+                    }
+                }
+                headers
+            }
+        };
+        check_headers(headers.iter().map(String::as_str))?;
+        let index = headers
+            .iter()
+            .enumerate()
+            .map(|(idx, header)| (header.as_str(), idx))
+            .collect::<HashMap<_, _>>();
+
+        let mut writer = ::csv::WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .from_writer(writer);
+        writer.write_record(&headers)?;
+        let mut record = Vec::with_capacity(headers.len());
+        for cells in &rows {
+            record.clear();
+            record.resize(headers.len(), None);
+            for (column, cell) in cells {
+                let Some(&idx) = index.get(column.as_str()) else {
+                    continue;
+                };
+                // A literal `x.y` key and a nested `x.y` in the same row
+                if record[idx].replace(cell.as_str()).is_some() {
+                    return Err(CsvError::InvalidHeader {
+                        column: column.clone(),
+                    });
+                }
+            }
+            writer.write_record(record.iter().map(|cell| cell.unwrap_or("")))?;
+        }
+        writer.flush().map_err(|err| CsvError::Csv(err.into()))?;
+        Ok(())
+    }
+
+    pub fn to_string<'a, I>(&self, records: I) -> Result<String, CsvError>
+    where
+        I: IntoIterator<Item = &'a Value>,
+    {
+        let mut out = Vec::new();
+        self.write(&mut out, records)?;
+        Ok(String::from_utf8(out).expect("csv output is utf-8"))
+    }
+}
+
+fn flatten(
+    row: usize,
+    prefix: String,
+    map: &Map,
+    cells: &mut Vec<(String, String)>,
+) -> Result<(), CsvError> {
+    for (key, value) in map.iter() {
+        let column = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        let cell = match value {
+            Value::Map(map) => {
+                flatten(row, column, map, cells)?;
+                continue;
+            }
+            Value::None => String::new(),
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) => n.to_string(),
+            Value::Char(c) => c.to_string(),
+            Value::String(s) => s.clone(),
+            #[cfg(feature = "datetime")]
+            Value::Date(date) => date.to_string(),
+            #[cfg(feature = "datetime")]
+            Value::DateTime(datetime) => format!("{}T{}", datetime.date(), datetime.time()),
+            Value::List(_) | Value::Dict(_) | Value::Bytes(_) => {
+                return Err(CsvError::Unsupported { row, column })
+            }
+        };
+        cells.push((column, cell));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const INPUT: &str = "name,age,address.city,address.zip,active\nRasmus,38,Aarhus,8000,true\nJane,,Odense,5000,false\n";
+
+    #[test]
+    fn read_nested() {
+        let rows = CsvReader::new().read_str(INPUT).unwrap();
+        assert_eq!(rows[0]["address"]["city"], Value::from("Aarhus"));
+        assert_eq!(rows[1]["age"], Value::from(""));
+
+        let output = CsvWriter::new().to_string(rows.as_list().unwrap()).unwrap();
+        assert_eq!(
+            output,
+            "active,address.city,address.zip,age,name\ntrue,Aarhus,8000,38,Rasmus\nfalse,Odense,5000,,Jane\n"
+        );
+        assert_eq!(CsvReader::new().read_str(&output).unwrap(), rows);
+    }
+
+    #[test]
+    fn schema() {
+        let schema = StructDef::<&'static str>::new(None)
+            .with_field("age", TypeDef::Optional(Box::new(ValueType::U8.into())))
+            .with_field("active", ValueType::Bool.into())
+            .with_field(
+                "address",
+                StructDef::new(None)
+                    .with_field("zip", ValueType::U16.into())
+                    .into(),
+            );
+        let reader = CsvReader::new().schema(schema);
+        let rows = reader.read_str(INPUT).unwrap();
+        assert_eq!(rows[0]["age"], Value::from(38u8));
+        assert_eq!(rows[1]["age"], Value::None);
+        assert_eq!(rows[1]["active"], Value::Bool(false));
+        assert_eq!(rows[1]["address"]["zip"], Value::from(5000u16));
+        assert_eq!(rows[1]["address"]["city"], Value::from("Odense"));
+
+        assert!(matches!(
+            reader.read_str("age\nold\n"),
+            Err(CsvError::InvalidCell {
+                line: 2,
+                expected: ValueType::U8,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            CsvReader::new().read_str("a,a.b\n1,2\n"),
+            Err(CsvError::InvalidHeader { column }) if column == "a"
+        ));
+        assert!(matches!(
+            CsvWriter::new().to_string(&[Value::from("x")]),
+            Err(CsvError::NotAMap { row: 0 })
+        ));
+
+        let mut nested = Map::new();
+        nested.insert("y", 1);
+        let mut row = Map::new();
+        row.insert("x.y", 2);
+        row.insert("x", nested.clone());
+        assert!(matches!(
+            CsvWriter::new().to_string(&[Value::Map(row)]),
+            Err(CsvError::InvalidHeader { column }) if column == "x.y"
+        ));
+
+        let mut first = Map::new();
+        first.insert("x", 1);
+        let mut second = Map::new();
+        second.insert("x", nested);
+        assert!(matches!(
+            CsvWriter::new().to_string(&[Value::Map(first), Value::Map(second)]),
+            Err(CsvError::InvalidHeader { column }) if column == "x"
+        ));
+        assert!(matches!(
+            CsvWriter::new().headers(["a", "a"]).to_string(&[]),
+            Err(CsvError::InvalidHeader { column }) if column == "a"
+        ));
+    }
+}
@@ -1,4 +1,4 @@
-use std::{borrow::Cow, collections::BTreeMap};
+use alloc::{borrow::Cow, collections::BTreeMap, string::String, vec::Vec};
 
 use crate::{
     typings::{TypeDef, ValueType},
@@ -1,5 +1,13 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 mod convert;
+#[cfg(feature = "csv")]
+pub mod csv;
 mod has_type;
+#[cfg(feature = "proptest")]
+mod strategy;
 mod typings;
 
 mod value_ext;
@@ -10,3 +18,6 @@ pub use self::{
     typings::*,
     value_ext::*,
 };
+
+#[cfg(feature = "csv")]
+pub use self::csv::{CsvError, CsvReader, CsvWriter};
@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+
+use proptest::{
+    arbitrary::any,
+    prelude::*,
+    strategy::{BoxedStrategy, Just, Union},
+};
+use value::{GenOptions, Kind, Value};
+
+use crate::{TypeDef, ValueType};
+
+impl ValueType {
+    /// Strategy producing values of this type. Lists and maps are filled with
+    /// arbitrary values shaped by `opts`.
+    pub fn strategy_with(&self, opts: &GenOptions) -> BoxedStrategy<Value> {
+        let nested = opts
+            .clone()
+            .with_max_depth(opts.max_depth().saturating_sub(1));
+        match self {
+            ValueType::U8 => any::<u8>().prop_map(Value::from).boxed(),
+            ValueType::U16 => any::<u16>().prop_map(Value::from).boxed(),
+            ValueType::U32 => any::<u32>().prop_map(Value::from).boxed(),
+            ValueType::U64 => any::<u64>().prop_map(Value::from).boxed(),
+            ValueType::I8 => any::<i8>().prop_map(Value::from).boxed(),
+            ValueType::I16 => any::<i16>().prop_map(Value::from).boxed(),
+            ValueType::I32 => any::<i32>().prop_map(Value::from).boxed(),
+            ValueType::I64 => any::<i64>().prop_map(Value::from).boxed(),
+            ValueType::F32 => GenOptions::float32().prop_map(Value::from).boxed(),
+            ValueType::F64 => GenOptions::float64().prop_map(Value::from).boxed(),
+            ValueType::Bool => opts.kind_strategy(Kind::Bool),
+            ValueType::Char => opts.kind_strategy(Kind::Char),
+            ValueType::String => opts.kind_strategy(Kind::String),
+            ValueType::Bytes => opts.kind_strategy(Kind::Bytes),
+            ValueType::None => Just(Value::None).boxed(),
+            ValueType::List => opts.list_strategy(nested.strategy()),
+            ValueType::Map => opts
+                .map_strategy(nested.strategy())
+                .prop_map(Value::Map)
+                .boxed(),
+            ValueType::Dict => opts
+                .dict_strategy(nested.strategy())
+                .prop_map(Value::from)
+                .boxed(),
+            #[cfg(feature = "datetime")]
+            ValueType::Date => opts.kind_strategy(Kind::Date),
+            #[cfg(feature = "datetime")]
+            ValueType::DateTime => opts.kind_strategy(Kind::DateTime),
+        }
+    }
+}
+
+impl<S: AsRef<str>> TypeDef<S> {
+    /// Strategy producing values conforming to this type definition.
+    pub fn strategy(&self) -> BoxedStrategy<Value> {
+        self.strategy_with(&GenOptions::default())
+    }
+
+    pub fn strategy_with(&self, opts: &GenOptions) -> BoxedStrategy<Value> {
+        match self {
+            TypeDef::Value(ty) => ty.strategy_with(opts),
+            TypeDef::Optional(ty) => prop_oneof![Just(Value::None), ty.strategy_with(opts)].boxed(),
+            TypeDef::Struct(st) => st
+                .fields()
+                .iter()
+                .map(|(name, ty)| {
+                    let name = name.as_ref().to_string();
+                    ty.strategy_with(opts).prop_map(move |v| (name.clone(), v))
+                })
+                .collect::<Vec<_>>()
+                .prop_map(|fields| {
+                    Value::Map(fields.into_iter().collect::<BTreeMap<_, _>>().into())
+                })
+                .boxed(),
+            TypeDef::Tuple(tuple) => tuple
+                .members()
+                .iter()
+                .map(|ty| ty.strategy_with(opts))
+                .collect::<Vec<_>>()
+                .prop_map(Value::List)
+                .boxed(),
+            TypeDef::Union(union) => {
+                union_of(union.types().iter().map(|ty| ty.strategy_with(opts)))
+            }
+            TypeDef::Enum(e) => union_of(e.variants().iter().map(|(name, ty)| {
+                let name = name.as_ref().to_string();
+                if ty.is_like(&TypeDef::<&str>::Value(ValueType::None)) {
+                    return Just(Value::String(name)).boxed();
+                }
+                ty.strategy_with(opts)
+                    .prop_map(move |v| {
+                        let mut map = BTreeMap::new();
+                        map.insert(name.clone(), v);
+                        Value::Map(map.into())
+                    })
+                    .boxed()
+            })),
+        }
+    }
+}
+
+fn union_of(strategies: impl Iterator<Item = BoxedStrategy<Value>>) -> BoxedStrategy<Value> {
+    let strategies = strategies.collect::<Vec<_>>();
+    if strategies.is_empty() {
+        return Just(Value::None).boxed();
+    }
+    Union::new(strategies).boxed()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{HasTypeDef, StructDef};
+
+    proptest! {
+        #[test]
+        fn struct_values_conform(value in TypeDef::Struct(
+            StructDef::<&'static str>::new(None)
+                .with_field("name", ValueType::String.into())
+                .with_field("age", ValueType::I32.into())
+                .with_field("tags", ValueType::List.into())
+        ).strategy()) {
+            let ty = TypeDef::Struct(
+                StructDef::<&'static str>::new(None)
+                    .with_field("name", ValueType::String.into())
+                    .with_field("age", ValueType::I32.into())
+                    .with_field("tags", ValueType::List.into()),
+            );
+            prop_assert!(ty.is_like(&value.type_def()));
+        }
+    }
+}
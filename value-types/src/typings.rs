@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use alloc::{borrow::Cow, boxed::Box, vec::Vec};
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -138,6 +138,12 @@ pub struct TupleDef<S> {
     members: Vec<TypeDef<S>>,
 }
 
+impl<S> TupleDef<S> {
+    pub fn members(&self) -> &Vec<TypeDef<S>> {
+        &self.members
+    }
+}
+
 impl<S: Into<Cow<'static, str>>> TupleDef<S> {
     pub fn to_owned(self) -> TupleDef<Cow<'static, str>> {
         TupleDef {
@@ -289,6 +295,7 @@ pub enum ValueType {
     String,
     List,
     Map,
+    Dict,
     Bytes,
     None,
     #[cfg(feature = "datetime")]
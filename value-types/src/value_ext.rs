@@ -27,6 +27,7 @@ impl ValueExt for Value {
             Value::None => ValueType::None,
             Value::List(_) => ValueType::List,
             Value::Map(_) => ValueType::Map,
+            Value::Dict(_) => ValueType::Dict,
             Value::Bytes(_) => ValueType::Bytes,
             #[cfg(feature = "datetime")]
             Value::Date(_) => ValueType::Date,
@@ -1,3 +1,4 @@
+use alloc::{string::ToString, vec};
 use value::Value;
 use value_types::{EnumDef, HasType, StructDef, TypeDef, UnionDef, ValueType};
 
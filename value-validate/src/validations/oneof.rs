@@ -1,4 +1,5 @@
 use crate::{types::ValidationList, Validation, ValidationBox, ValidationError};
+use alloc::vec::Vec;
 use core::any::Any;
 use value::Value;
 
@@ -0,0 +1,123 @@
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::{Dict, GenOptions, Kind, Map, Number, Value};
+
+impl GenOptions {
+    pub fn arbitrary_value(&self, u: &mut Unstructured<'_>) -> Result<Value> {
+        self.value_at(u, 0)
+    }
+
+    pub fn arbitrary_map(&self, u: &mut Unstructured<'_>) -> Result<Map> {
+        self.map_at(u, 0)
+    }
+
+    fn value_at(&self, u: &mut Unstructured<'_>, depth: usize) -> Result<Value> {
+        let kinds = self
+            .kinds
+            .iter()
+            .filter(|k| depth < self.max_depth || !k.is_container())
+            .collect::<Vec<_>>();
+
+        if kinds.is_empty() {
+            return Ok(Value::None);
+        }
+
+        let value = match **u.choose(&kinds)? {
+            Kind::Bool => Value::Bool(u.arbitrary()?),
+            Kind::Number => Value::Number(u.arbitrary()?),
+            Kind::Char => Value::Char(u.arbitrary()?),
+            Kind::String => Value::String(u.arbitrary()?),
+            Kind::Bytes => {
+                let len = u.int_in_range(0..=self.max_breadth)?;
+                Value::Bytes(u.bytes(len)?.to_vec())
+            }
+            Kind::None => Value::None,
+            Kind::List => {
+                let len = u.int_in_range(0..=self.max_breadth)?;
+                let mut list = Vec::with_capacity(len);
+                for _ in 0..len {
+                    list.push(self.value_at(u, depth + 1)?);
+                }
+                Value::List(list)
+            }
+            Kind::Map => Value::Map(self.map_at(u, depth)?),
+            Kind::Dict => {
+                // At least one entry, so the dict doesn't turn into a map on a round trip
+                let len = u.int_in_range(1..=self.max_breadth.max(1))?;
+                let mut dict = Dict::new();
+                for _ in 0..len {
+                    let key: Number = u.arbitrary()?;
+                    dict.insert(key, self.value_at(u, depth + 1)?);
+                }
+                Value::from(dict)
+            }
+            #[cfg(feature = "datetime")]
+            Kind::Date => Value::Date(arbitrary_date(u)?),
+            #[cfg(feature = "datetime")]
+            Kind::DateTime => {
+                let secs = u.int_in_range(0..=86_399)?;
+                let time = chrono::NaiveTime::from_num_seconds_from_midnight_opt(secs, 0)
+                    .ok_or(arbitrary::Error::IncorrectFormat)?;
+                Value::DateTime(arbitrary_date(u)?.and_time(time))
+            }
+        };
+
+        Ok(value)
+    }
+
+    fn map_at(&self, u: &mut Unstructured<'_>, depth: usize) -> Result<Map> {
+        let len = u.int_in_range(0..=self.max_breadth)?;
+        let mut map = Map::default();
+        for _ in 0..len {
+            let key: String = u.arbitrary()?;
+            map.insert(key, self.value_at(u, depth + 1)?);
+        }
+        Ok(map)
+    }
+}
+
+#[cfg(feature = "datetime")]
+fn arbitrary_date(u: &mut Unstructured<'_>) -> Result<chrono::NaiveDate> {
+    let days = u.int_in_range(1..=3_652_059)?;
+    chrono::NaiveDate::from_num_days_from_ce_opt(days).ok_or(arbitrary::Error::IncorrectFormat)
+}
+
+fn finite<F: Into<f64> + Copy>(f: F) -> bool {
+    f.into().is_finite()
+}
+
+impl<'a> Arbitrary<'a> for Number {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let n = match u.int_in_range(0..=9u8)? {
+            0 => Number::U8(u.arbitrary()?),
+            1 => Number::U16(u.arbitrary()?),
+            2 => Number::U32(u.arbitrary()?),
+            3 => Number::U64(u.arbitrary()?),
+            4 => Number::I8(u.arbitrary()?),
+            5 => Number::I16(u.arbitrary()?),
+            6 => Number::I32(u.arbitrary()?),
+            7 => Number::I64(u.arbitrary()?),
+            8 => {
+                let f: f32 = u.arbitrary()?;
+                Number::from(if finite(f) { f } else { 0.0 })
+            }
+            _ => {
+                let f: f64 = u.arbitrary()?;
+                Number::from(if finite(f) { f } else { 0.0 })
+            }
+        };
+        Ok(n)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Value {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        GenOptions::default().arbitrary_value(u)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Map {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        GenOptions::default().arbitrary_map(u)
+    }
+}
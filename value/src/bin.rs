@@ -0,0 +1,1040 @@
+//! A compact, self-describing binary encoding that preserves every `Value`
+//! variant exactly, for caches and IPC.
+//!
+//! # Format
+//!
+//! A document is a 3 byte header, the magic `VB` followed by the format
+//! [`VERSION`], and a single value. A value is a tag byte followed by its
+//! payload:
+//!
+//! | tag           | variant               | payload                                   |
+//! |---------------|-----------------------|-------------------------------------------|
+//! | `0x00`        | `None`                |                                           |
+//! | `0x01`/`0x02` | `Bool(false)`/`(true)`|                                           |
+//! | `0x03`        | `Char`                | varint code point                         |
+//! | `0x10`-`0x13` | `U8`, `U16`, `U32`, `U64` | varint                                |
+//! | `0x14`-`0x17` | `I8`, `I16`, `I32`, `I64` | zigzag varint                         |
+//! | `0x18`/`0x19` | `F32`/`F64`           | 4/8 bytes little endian                   |
+//! | `0x20`        | `String`              | varint length, UTF-8 bytes                |
+//! | `0x21`        | `Bytes`               | varint length, bytes                      |
+//! | `0x30`        | `List`                | varint count, values                      |
+//! | `0x31`        | `Map`                 | varint count, (varint length, UTF-8 key, value) pairs |
+//! | `0x32`        | `Dict`                | varint count, (key value, value) pairs    |
+//! | `0x40`        | `Date`                | zigzag varint day number, 0001-01-01 is 1 |
+//! | `0x41`        | `DateTime`            | date payload, varint seconds from midnight, varint nanoseconds |
+//!
+//! Varints are unsigned LEB128 of at most 10 bytes. Signed integers are
+//! zigzag encoded first, so small negative numbers stay small.
+//!
+//! A stream, written by [`StreamEncoder`], is the header followed by any
+//! number of frames, each a varint byte length and a value. A stream without
+//! values may also be empty, header included.
+//!
+//! # Decoding untrusted input
+//!
+//! Decoding never panics and never allocates more than the input can
+//! account for: every malformed input is reported as a [`BinError`] with the
+//! offset it was found at, and the decoded tree is bounded by [`Limits`].
+//! [`Decoder::decode_ref`] borrows strings and bytes from the input instead
+//! of copying them, see [`ValueRef`].
+#[cfg(not(feature = "std"))]
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::{fmt, str};
+#[cfg(feature = "std")]
+use std::borrow::Cow;
+
+use crate::{
+    events::{Event, Events},
+    Dict, LimitError, Limits, Map, Number, Value,
+};
+
+pub const MAGIC: [u8; 2] = *b"VB";
+
+pub const VERSION: u8 = 1;
+
+const NONE: u8 = 0x00;
+const FALSE: u8 = 0x01;
+const TRUE: u8 = 0x02;
+const CHAR: u8 = 0x03;
+const U8: u8 = 0x10;
+const U16: u8 = 0x11;
+const U32: u8 = 0x12;
+const U64: u8 = 0x13;
+const I8: u8 = 0x14;
+const I16: u8 = 0x15;
+const I32: u8 = 0x16;
+const I64: u8 = 0x17;
+const F32: u8 = 0x18;
+const F64: u8 = 0x19;
+const STRING: u8 = 0x20;
+const BYTES: u8 = 0x21;
+const LIST: u8 = 0x30;
+const MAP: u8 = 0x31;
+const DICT: u8 = 0x32;
+#[cfg(feature = "datetime")]
+const DATE: u8 = 0x40;
+#[cfg(feature = "datetime")]
+const DATETIME: u8 = 0x41;
+
+#[derive(Debug)]
+pub enum BinError {
+    UnexpectedEof,
+    /// The input doesn't start with [`MAGIC`].
+    InvalidHeader,
+    UnsupportedVersion(u8),
+    /// An unknown tag, or a date tag without the `datetime` feature.
+    InvalidTag {
+        tag: u8,
+        offset: usize,
+    },
+    /// A varint longer than 10 bytes or overflowing 64 bits.
+    InvalidVarint {
+        offset: usize,
+    },
+    /// An integer too large for its tagged width.
+    OutOfRange {
+        offset: usize,
+    },
+    InvalidUtf8 {
+        offset: usize,
+    },
+    InvalidChar {
+        offset: usize,
+    },
+    InvalidDate {
+        offset: usize,
+    },
+    /// Input left after the value.
+    TrailingBytes {
+        offset: usize,
+    },
+    Limit(LimitError),
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+}
+
+impl fmt::Display for BinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinError::UnexpectedEof => write!(f, "unexpected end of input"),
+            BinError::InvalidHeader => write!(f, "invalid header"),
+            BinError::UnsupportedVersion(version) => {
+                write!(f, "unsupported format version {}", version)
+            }
+            BinError::InvalidTag { tag, offset } => {
+                write!(f, "invalid tag {:#04x} at offset {}", tag, offset)
+            }
+            BinError::InvalidVarint { offset } => write!(f, "invalid varint at offset {}", offset),
+            BinError::OutOfRange { offset } => {
+                write!(f, "integer out of range at offset {}", offset)
+            }
+            BinError::InvalidUtf8 { offset } => write!(f, "invalid utf-8 at offset {}", offset),
+            BinError::InvalidChar { offset } => write!(f, "invalid char at offset {}", offset),
+            BinError::InvalidDate { offset } => write!(f, "invalid date at offset {}", offset),
+            BinError::TrailingBytes { offset } => {
+                write!(f, "trailing bytes at offset {}", offset)
+            }
+            BinError::Limit(err) => write!(f, "{}", err),
+            #[cfg(feature = "std")]
+            BinError::Io(err) => write!(f, "io error: {}", err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BinError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BinError::Limit(err) => Some(err),
+            BinError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<LimitError> for BinError {
+    fn from(err: LimitError) -> Self {
+        BinError::Limit(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for BinError {
+    fn from(err: std::io::Error) -> Self {
+        BinError::Io(err)
+    }
+}
+
+/// A decoded value borrowing its strings and bytes from the input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueRef<'a> {
+    Bool(bool),
+    Number(Number),
+    Char(char),
+    String(&'a str),
+    List(Vec<ValueRef<'a>>),
+    /// Map entries in encoded order.
+    Map(Vec<(&'a str, ValueRef<'a>)>),
+    Dict(Vec<(ValueRef<'a>, ValueRef<'a>)>),
+    Bytes(&'a [u8]),
+    #[cfg(feature = "datetime")]
+    Date(chrono::NaiveDate),
+    #[cfg(feature = "datetime")]
+    DateTime(chrono::NaiveDateTime),
+    None,
+}
+
+impl<'a> ValueRef<'a> {
+    pub fn get(&self, key: &str) -> Option<&ValueRef<'a>> {
+        match self {
+            ValueRef::Map(map) => map.iter().rev().find(|(k, _)| *k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn get_index(&self, idx: usize) -> Option<&ValueRef<'a>> {
+        match self {
+            ValueRef::List(list) => list.get(idx),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self {
+            ValueRef::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&'a [u8]> {
+        match self {
+            ValueRef::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        matches!(self, ValueRef::None)
+    }
+
+    pub fn to_value(&self) -> Value {
+        Value::from(self.clone())
+    }
+}
+
+impl<'a> From<ValueRef<'a>> for Value {
+    fn from(value: ValueRef<'a>) -> Value {
+        match value {
+            ValueRef::Bool(b) => Value::Bool(b),
+            ValueRef::Number(n) => Value::Number(n),
+            ValueRef::Char(c) => Value::Char(c),
+            ValueRef::String(s) => Value::String(s.into()),
+            ValueRef::List(list) => Value::List(list.into_iter().map(Value::from).collect()),
+            ValueRef::Map(map) => Value::Map(
+                map.into_iter()
+                    .map(|(k, v)| (String::from(k), Value::from(v)))
+                    .collect::<Map>(),
+            ),
+            ValueRef::Dict(entries) => Value::from(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (Value::from(k), Value::from(v)))
+                    .collect::<Dict>(),
+            ),
+            ValueRef::Bytes(bytes) => Value::Bytes(bytes.to_vec()),
+            #[cfg(feature = "datetime")]
+            ValueRef::Date(date) => Value::Date(date),
+            #[cfg(feature = "datetime")]
+            ValueRef::DateTime(datetime) => Value::DateTime(datetime),
+            ValueRef::None => Value::None,
+        }
+    }
+}
+
+/// Encode `value` as a document, header included.
+pub fn to_vec(value: &Value) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&MAGIC);
+    out.push(VERSION);
+    encode(value, &mut out);
+    out
+}
+
+/// Append the encoding of `value`, without a header, to `out`.
+pub fn encode(value: &Value, out: &mut Vec<u8>) {
+    match value {
+        Value::None => out.push(NONE),
+        Value::Bool(false) => out.push(FALSE),
+        Value::Bool(true) => out.push(TRUE),
+        Value::Char(c) => {
+            out.push(CHAR);
+            put_varint(out, *c as u64);
+        }
+        Value::Number(n) => encode_number(n, out),
+        Value::String(s) => {
+            out.push(STRING);
+            put_bytes(out, s.as_bytes());
+        }
+        Value::Bytes(bytes) => {
+            out.push(BYTES);
+            put_bytes(out, bytes);
+        }
+        Value::List(list) => {
+            out.push(LIST);
+            put_varint(out, list.len() as u64);
+            for item in list {
+                encode(item, out);
+            }
+        }
+        Value::Map(map) => {
+            out.push(MAP);
+            put_varint(out, map.len() as u64);
+            for (k, v) in map.iter() {
+                put_bytes(out, k.as_bytes());
+                encode(v, out);
+            }
+        }
+        Value::Dict(dict) => {
+            out.push(DICT);
+            put_varint(out, dict.len() as u64);
+            for (k, v) in dict.iter() {
+                encode(k, out);
+                encode(v, out);
+            }
+        }
+        #[cfg(feature = "datetime")]
+        Value::Date(date) => {
+            out.push(DATE);
+            put_date(out, date);
+        }
+        #[cfg(feature = "datetime")]
+        Value::DateTime(datetime) => {
+            use chrono::Timelike;
+
+            out.push(DATETIME);
+            put_date(out, &datetime.date());
+            put_varint(out, datetime.num_seconds_from_midnight().into());
+            put_varint(out, datetime.nanosecond().into());
+        }
+    }
+}
+
+fn encode_number(n: &Number, out: &mut Vec<u8>) {
+    match *n {
+        Number::U8(v) => put_tagged(out, U8, v.into()),
+        Number::U16(v) => put_tagged(out, U16, v.into()),
+        Number::U32(v) => put_tagged(out, U32, v.into()),
+        Number::U64(v) => put_tagged(out, U64, v),
+        Number::I8(v) => put_tagged(out, I8, zigzag(v.into())),
+        Number::I16(v) => put_tagged(out, I16, zigzag(v.into())),
+        Number::I32(v) => put_tagged(out, I32, zigzag(v.into())),
+        Number::I64(v) => put_tagged(out, I64, zigzag(v)),
+        Number::F32(_) => {
+            out.push(F32);
+            out.extend_from_slice(&n.as_f32().to_le_bytes());
+        }
+        Number::F64(_) => {
+            out.push(F64);
+            out.extend_from_slice(&n.as_f64().to_le_bytes());
+        }
+    }
+}
+
+fn put_tagged(out: &mut Vec<u8>, tag: u8, v: u64) {
+    out.push(tag);
+    put_varint(out, v);
+}
+
+fn put_varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push(v as u8 | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+#[cfg(feature = "datetime")]
+fn put_date(out: &mut Vec<u8>, date: &chrono::NaiveDate) {
+    use chrono::Datelike;
+
+    put_varint(out, zigzag(date.num_days_from_ce().into()));
+}
+
+fn zigzag(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+fn unzigzag(v: u64) -> i64 {
+    (v >> 1) as i64 ^ -((v & 1) as i64)
+}
+
+/// Decode a document with the default [`Limits`].
+pub fn from_slice(input: &[u8]) -> Result<Value, BinError> {
+    from_slice_ref(input).map(Value::from)
+}
+
+/// Decode a document without copying strings and bytes.
+pub fn from_slice_ref(input: &[u8]) -> Result<ValueRef<'_>, BinError> {
+    let mut decoder = Decoder::new(input)?;
+    let value = decoder.decode_ref()?;
+    decoder.finish()?;
+    Ok(value)
+}
+
+/// Decodes values from a document in memory.
+#[derive(Debug, Clone)]
+pub struct Decoder<'a> {
+    input: &'a [u8],
+    pos: usize,
+    limits: Limits,
+    nodes: usize,
+}
+
+impl<'a> Decoder<'a> {
+    /// Read the header of `input`.
+    pub fn new(input: &'a [u8]) -> Result<Decoder<'a>, BinError> {
+        let mut decoder = Decoder::raw(input);
+        match decoder.take(MAGIC.len()) {
+            Ok(magic) if magic == MAGIC => {}
+            _ => return Err(BinError::InvalidHeader),
+        }
+        match decoder.byte()? {
+            VERSION => Ok(decoder),
+            version => Err(BinError::UnsupportedVersion(version)),
+        }
+    }
+
+    /// Decode values without a header, as written by [`encode`].
+    pub fn raw(input: &'a [u8]) -> Decoder<'a> {
+        Decoder {
+            input,
+            pos: 0,
+            limits: Limits::default(),
+            nodes: 0,
+        }
+    }
+
+    /// Limits for every value decoded. Defaults to [`Limits::default`].
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The number of bytes read so far.
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos == self.input.len()
+    }
+
+    pub fn decode(&mut self) -> Result<Value, BinError> {
+        self.decode_ref().map(Value::from)
+    }
+
+    pub fn decode_ref(&mut self) -> Result<ValueRef<'a>, BinError> {
+        self.nodes = 0;
+        self.value(0)
+    }
+
+    /// Read the next value as a stream of [`Event`]s instead of a tree.
+    pub fn events(&mut self) -> BinEvents<'_, 'a> {
+        self.nodes = 0;
+        BinEvents {
+            decoder: self,
+            stack: Vec::new(),
+            started: false,
+        }
+    }
+
+    /// Fail if there is input left.
+    pub fn finish(&self) -> Result<(), BinError> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(BinError::TrailingBytes { offset: self.pos })
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<ValueRef<'a>, BinError> {
+        self.nodes += 1;
+        self.limits.check_nodes(self.nodes)?;
+        self.limits.check_depth(depth)?;
+
+        let offset = self.pos;
+        let value = match self.byte()? {
+            LIST => {
+                let len = self.len(1)?;
+                let mut list = Vec::with_capacity(len);
+                for _ in 0..len {
+                    list.push(self.value(depth + 1)?);
+                }
+                ValueRef::List(list)
+            }
+            MAP => {
+                let len = self.len(2)?;
+                self.limits.check_map_keys(len)?;
+                let mut map = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let key = self.str()?;
+                    map.push((key, self.value(depth + 1)?));
+                }
+                ValueRef::Map(map)
+            }
+            DICT => {
+                let len = self.len(2)?;
+                self.limits.check_map_keys(len)?;
+                let mut dict = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let key = self.value(depth + 1)?;
+                    dict.push((key, self.value(depth + 1)?));
+                }
+                ValueRef::Dict(dict)
+            }
+            tag => self.scalar(tag, offset)?,
+        };
+        Ok(value)
+    }
+
+    /// Any value but a list, map or dict, after its tag.
+    fn scalar(&mut self, tag: u8, offset: usize) -> Result<ValueRef<'a>, BinError> {
+        let value = match tag {
+            NONE => ValueRef::None,
+            FALSE => ValueRef::Bool(false),
+            TRUE => ValueRef::Bool(true),
+            CHAR => {
+                let c = u32::try_from(self.varint()?).ok().and_then(char::from_u32);
+                ValueRef::Char(c.ok_or(BinError::InvalidChar { offset })?)
+            }
+            U8 => ValueRef::Number(Number::U8(self.narrow()?)),
+            U16 => ValueRef::Number(Number::U16(self.narrow()?)),
+            U32 => ValueRef::Number(Number::U32(self.narrow()?)),
+            U64 => ValueRef::Number(Number::U64(self.varint()?)),
+            I8 => ValueRef::Number(Number::I8(self.narrow_signed()?)),
+            I16 => ValueRef::Number(Number::I16(self.narrow_signed()?)),
+            I32 => ValueRef::Number(Number::I32(self.narrow_signed()?)),
+            I64 => ValueRef::Number(Number::I64(unzigzag(self.varint()?))),
+            F32 => {
+                let bytes = self.take(4)?.try_into().expect("4 bytes");
+                ValueRef::Number(Number::from(f32::from_le_bytes(bytes)))
+            }
+            F64 => {
+                let bytes = self.take(8)?.try_into().expect("8 bytes");
+                ValueRef::Number(Number::from(f64::from_le_bytes(bytes)))
+            }
+            STRING => ValueRef::String(self.str()?),
+            BYTES => {
+                let len = self.len(1)?;
+                self.limits.check_bytes_len(len)?;
+                ValueRef::Bytes(self.take(len)?)
+            }
+            #[cfg(feature = "datetime")]
+            DATE => ValueRef::Date(self.date(offset)?),
+            #[cfg(feature = "datetime")]
+            DATETIME => {
+                let date = self.date(offset)?;
+                let secs = u32::try_from(self.varint()?);
+                let nanos = u32::try_from(self.varint()?);
+                let time = match (secs, nanos) {
+                    (Ok(secs), Ok(nanos)) => {
+                        chrono::NaiveTime::from_num_seconds_from_midnight_opt(secs, nanos)
+                    }
+                    _ => None,
+                };
+                ValueRef::DateTime(date.and_time(time.ok_or(BinError::InvalidDate { offset })?))
+            }
+            tag => return Err(BinError::InvalidTag { tag, offset }),
+        };
+        Ok(value)
+    }
+
+    fn byte(&mut self) -> Result<u8, BinError> {
+        let byte = *self.input.get(self.pos).ok_or(BinError::UnexpectedEof)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], BinError> {
+        let end = self.pos.checked_add(len).ok_or(BinError::UnexpectedEof)?;
+        let bytes = self
+            .input
+            .get(self.pos..end)
+            .ok_or(BinError::UnexpectedEof)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn varint(&mut self) -> Result<u64, BinError> {
+        let offset = self.pos;
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            if shift == 63 && byte > 1 {
+                return Err(BinError::InvalidVarint { offset });
+            }
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(BinError::InvalidVarint { offset })
+    }
+
+    fn narrow<T: TryFrom<u64>>(&mut self) -> Result<T, BinError> {
+        let offset = self.pos;
+        T::try_from(self.varint()?).map_err(|_| BinError::OutOfRange { offset })
+    }
+
+    fn narrow_signed<T: TryFrom<i64>>(&mut self) -> Result<T, BinError> {
+        let offset = self.pos;
+        T::try_from(unzigzag(self.varint()?)).map_err(|_| BinError::OutOfRange { offset })
+    }
+
+    /// A length or count, where each item takes at least `min_size` bytes.
+    /// Rejecting counts the remaining input can't hold keeps a corrupt
+    /// length from allocating.
+    fn len(&mut self, min_size: usize) -> Result<usize, BinError> {
+        let len = usize::try_from(self.varint()?).map_err(|_| BinError::UnexpectedEof)?;
+        match len.checked_mul(min_size) {
+            Some(size) if size <= self.input.len() - self.pos => Ok(len),
+            _ => Err(BinError::UnexpectedEof),
+        }
+    }
+
+    fn str(&mut self) -> Result<&'a str, BinError> {
+        let len = self.len(1)?;
+        self.limits.check_string_len(len)?;
+        let offset = self.pos;
+        str::from_utf8(self.take(len)?).map_err(|_| BinError::InvalidUtf8 { offset })
+    }
+
+    #[cfg(feature = "datetime")]
+    fn date(&mut self, offset: usize) -> Result<chrono::NaiveDate, BinError> {
+        i32::try_from(unzigzag(self.varint()?))
+            .ok()
+            .and_then(chrono::NaiveDate::from_num_days_from_ce_opt)
+            .ok_or(BinError::InvalidDate { offset })
+    }
+}
+
+/// A value read one [`Event`] at a time, see [`Decoder::events`].
+///
+/// Nothing but the current scalar is decoded, and strings and bytes are
+/// borrowed from the input, so memory use is bounded by the nesting depth.
+#[derive(Debug)]
+pub struct BinEvents<'d, 'a> {
+    decoder: &'d mut Decoder<'a>,
+    stack: Vec<Frame>,
+    started: bool,
+}
+
+/// An open container and how many items are left in it.
+#[derive(Debug)]
+enum Frame {
+    List(usize),
+    Map {
+        entries: usize,
+        key: bool,
+    },
+    /// Keys and values both count as items.
+    Dict(usize),
+}
+
+impl<'d, 'a> Events<'a> for BinEvents<'d, 'a> {
+    type Error = BinError;
+
+    fn next_event(&mut self) -> Result<Option<Event<'a>>, BinError> {
+        let event = match self.stack.last_mut() {
+            None if self.started => return Ok(None),
+            None => {
+                self.started = true;
+                self.value()?
+            }
+            Some(Frame::List(0)) => {
+                self.stack.pop();
+                Event::EndList
+            }
+            Some(Frame::Map { entries: 0, .. }) => {
+                self.stack.pop();
+                Event::EndMap
+            }
+            Some(Frame::Dict(0)) => {
+                self.stack.pop();
+                Event::EndDict
+            }
+            Some(Frame::Map { key, .. }) if !*key => {
+                *key = true;
+                Event::Key(Cow::Borrowed(self.decoder.str()?))
+            }
+            Some(Frame::Map { entries, key }) => {
+                *entries -= 1;
+                *key = false;
+                self.value()?
+            }
+            Some(Frame::List(items) | Frame::Dict(items)) => {
+                *items -= 1;
+                self.value()?
+            }
+        };
+        Ok(Some(event))
+    }
+}
+
+impl<'d, 'a> BinEvents<'d, 'a> {
+    fn value(&mut self) -> Result<Event<'a>, BinError> {
+        let decoder = &mut *self.decoder;
+        decoder.nodes += 1;
+        decoder.limits.check_nodes(decoder.nodes)?;
+        decoder.limits.check_depth(self.stack.len())?;
+
+        let offset = decoder.pos;
+        let (frame, event) = match decoder.byte()? {
+            LIST => (Frame::List(decoder.len(1)?), Event::StartList),
+            MAP => {
+                let entries = decoder.len(2)?;
+                decoder.limits.check_map_keys(entries)?;
+                (
+                    Frame::Map {
+                        entries,
+                        key: false,
+                    },
+                    Event::StartMap,
+                )
+            }
+            DICT => {
+                let entries = decoder.len(2)?;
+                decoder.limits.check_map_keys(entries)?;
+                (Frame::Dict(entries * 2), Event::StartDict)
+            }
+            tag => return Ok(scalar_event(decoder.scalar(tag, offset)?)),
+        };
+        self.stack.push(frame);
+        Ok(event)
+    }
+}
+
+/// The event for a scalar decoded by [`Decoder::scalar`].
+fn scalar_event(value: ValueRef<'_>) -> Event<'_> {
+    match value {
+        ValueRef::Bool(b) => Event::Bool(b),
+        ValueRef::Number(n) => Event::Number(n),
+        ValueRef::Char(c) => Event::Char(c),
+        ValueRef::String(s) => Event::String(Cow::Borrowed(s)),
+        ValueRef::Bytes(bytes) => Event::Bytes(Cow::Borrowed(bytes)),
+        #[cfg(feature = "datetime")]
+        ValueRef::Date(date) => Event::Date(date),
+        #[cfg(feature = "datetime")]
+        ValueRef::DateTime(datetime) => Event::DateTime(datetime),
+        ValueRef::List(_) | ValueRef::Map(_) | ValueRef::Dict(_) | ValueRef::None => Event::None,
+    }
+}
+
+#[cfg(feature = "std")]
+pub use self::stream::{StreamDecoder, StreamEncoder};
+
+#[cfg(feature = "std")]
+mod stream {
+    use std::io::{self, Read, Write};
+
+    use super::{encode, put_varint, BinError, Decoder, MAGIC, VERSION};
+    use crate::{Limits, Value};
+
+    /// Writes a stream of values, see the [module docs](super).
+    #[derive(Debug)]
+    pub struct StreamEncoder<W> {
+        writer: W,
+        buffer: Vec<u8>,
+        header: bool,
+    }
+
+    impl<W: Write> StreamEncoder<W> {
+        pub fn new(writer: W) -> StreamEncoder<W> {
+            StreamEncoder {
+                writer,
+                buffer: Vec::new(),
+                header: false,
+            }
+        }
+
+        /// Write one value. The header is written before the first one.
+        pub fn write(&mut self, value: &Value) -> io::Result<()> {
+            self.buffer.clear();
+            if !self.header {
+                self.buffer.extend_from_slice(&MAGIC);
+                self.buffer.push(VERSION);
+                self.header = true;
+            }
+            let start = self.buffer.len();
+            encode(value, &mut self.buffer);
+            let mut len = Vec::with_capacity(10);
+            put_varint(&mut len, (self.buffer.len() - start) as u64);
+            self.buffer.splice(start..start, len);
+            self.writer.write_all(&self.buffer)
+        }
+
+        pub fn flush(&mut self) -> io::Result<()> {
+            self.writer.flush()
+        }
+
+        pub fn into_inner(self) -> W {
+            self.writer
+        }
+    }
+
+    /// Reads a stream of values written by [`StreamEncoder`].
+    #[derive(Debug)]
+    pub struct StreamDecoder<R> {
+        reader: R,
+        buffer: Vec<u8>,
+        limits: Limits,
+        max_frame_len: usize,
+        header: bool,
+    }
+
+    impl<R: Read> StreamDecoder<R> {
+        pub fn new(reader: R) -> StreamDecoder<R> {
+            StreamDecoder {
+                reader,
+                buffer: Vec::new(),
+                limits: Limits::default(),
+                max_frame_len: 64 * 1024 * 1024,
+                header: false,
+            }
+        }
+
+        /// Limits applied to every value. Defaults to [`Limits::default`].
+        pub fn with_limits(mut self, limits: Limits) -> Self {
+            self.limits = limits;
+            self
+        }
+
+        /// The largest encoded value accepted, 64 MiB by default.
+        pub fn with_max_frame_len(mut self, len: usize) -> Self {
+            self.max_frame_len = len;
+            self
+        }
+
+        /// Read the next value, or `None` at the end of the stream.
+        pub fn read(&mut self) -> Result<Option<Value>, BinError> {
+            if !self.header {
+                let mut header = [0; 3];
+                // The encoder writes the header with the first value.
+                if self.reader.read(&mut header[..1])? == 0 {
+                    return Ok(None);
+                }
+                self.reader.read_exact(&mut header[1..]).map_err(eof)?;
+                Decoder::new(&header)?;
+                self.header = true;
+            }
+            let Some(len) = self.frame_len()? else {
+                return Ok(None);
+            };
+            if len > self.max_frame_len {
+                return Err(BinError::Limit(crate::LimitError::BytesLength(
+                    self.max_frame_len,
+                )));
+            }
+            self.buffer.clear();
+            (&mut self.reader)
+                .take(len as u64)
+                .read_to_end(&mut self.buffer)?;
+            if self.buffer.len() < len {
+                return Err(BinError::UnexpectedEof);
+            }
+            let mut decoder = Decoder::raw(&self.buffer).with_limits(self.limits);
+            let value = decoder.decode()?;
+            decoder.finish()?;
+            Ok(Some(value))
+        }
+
+        /// The varint length of the next frame, `None` on a clean end.
+        fn frame_len(&mut self) -> Result<Option<usize>, BinError> {
+            let mut bytes = Vec::with_capacity(10);
+            loop {
+                let mut byte = [0];
+                if self.reader.read(&mut byte)? == 0 {
+                    return match bytes.is_empty() {
+                        true => Ok(None),
+                        false => Err(BinError::UnexpectedEof),
+                    };
+                }
+                bytes.push(byte[0]);
+                if byte[0] & 0x80 == 0 || bytes.len() == 10 {
+                    break;
+                }
+            }
+            let len = Decoder::raw(&bytes).varint()?;
+            usize::try_from(len)
+                .map(Some)
+                .map_err(|_| BinError::InvalidVarint { offset: 0 })
+        }
+    }
+
+    impl<R: Read> Iterator for StreamDecoder<R> {
+        type Item = Result<Value, BinError>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.read().transpose()
+        }
+    }
+
+    fn eof(err: io::Error) -> BinError {
+        match err.kind() {
+            io::ErrorKind::UnexpectedEof => BinError::UnexpectedEof,
+            _ => BinError::Io(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    use super::*;
+
+    fn sample() -> Value {
+        let mut dict = Dict::new();
+        dict.insert(Value::from(1u8), "one");
+        let mut map = Map::new();
+        map.insert("name", "Rasmus");
+        map.insert("age", 38u8);
+        map.insert("balance", -1200i64);
+        map.insert("ratio", 0.5f32);
+        map.insert("big", u64::MAX);
+        map.insert("initial", Value::Char('R'));
+        map.insert("blob", Value::Bytes(vec![0, 1, 255]));
+        map.insert("tags", Value::List(vec![Value::from("a"), Value::None]));
+        map.insert("dict", Value::from(dict));
+        map.insert("active", true);
+        Value::Map(map)
+    }
+
+    fn same_variants(a: &Value, b: &Value) -> bool {
+        match (a, b) {
+            (Value::Number(a), Value::Number(b)) => {
+                core::mem::discriminant(a) == core::mem::discriminant(b) && a == b
+            }
+            (Value::Map(a), Value::Map(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .zip(b.iter())
+                        .all(|((ka, va), (kb, vb))| ka == kb && same_variants(va, vb))
+            }
+            (Value::List(a), Value::List(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_variants(a, b))
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let value = sample();
+        let bytes = to_vec(&value);
+        assert_eq!(&bytes[..3], b"VB\x01");
+        assert!(same_variants(&from_slice(&bytes).unwrap(), &value));
+
+        let view = from_slice_ref(&bytes).unwrap();
+        assert_eq!(view.get("name").and_then(ValueRef::as_str), Some("Rasmus"));
+        assert_eq!(
+            view.get("blob").and_then(ValueRef::as_bytes),
+            Some(&[0, 1, 255][..])
+        );
+    }
+
+    #[cfg(feature = "datetime")]
+    #[test]
+    fn dates() {
+        let date = chrono::NaiveDate::from_ymd_opt(-44, 3, 15).unwrap();
+        let datetime = date.and_hms_nano_opt(12, 30, 5, 123).unwrap();
+        let value = Value::List(vec![Value::Date(date), Value::DateTime(datetime)]);
+        assert_eq!(from_slice(&to_vec(&value)).unwrap(), value);
+    }
+
+    #[test]
+    fn corrupt_input() {
+        let bytes = to_vec(&sample());
+        for len in 0..bytes.len() {
+            assert!(from_slice(&bytes[..len]).is_err());
+        }
+        for idx in 3..bytes.len() {
+            let mut bytes = bytes.clone();
+            bytes[idx] ^= 0xff;
+            let _ = from_slice(&bytes);
+        }
+
+        assert!(matches!(
+            from_slice(b"VB\x02\x00"),
+            Err(BinError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            from_slice(b"VB\x01\x00\x00"),
+            Err(BinError::TrailingBytes { offset: 4 })
+        ));
+        assert!(matches!(
+            from_slice(b"VB\x01\x30\xff\xff\xff\xff\x0f"),
+            Err(BinError::UnexpectedEof)
+        ));
+        assert!(matches!(
+            from_slice(b"VB\x01\x10\x80\x02"),
+            Err(BinError::OutOfRange { offset: 4 })
+        ));
+        assert!(matches!(
+            from_slice(b"VB\x01\x13\xff\xff\xff\xff\xff\xff\xff\xff\xff\x02"),
+            Err(BinError::InvalidVarint { offset: 4 })
+        ));
+        assert!(matches!(
+            from_slice(b"VB\x01\x20\x01\xff"),
+            Err(BinError::InvalidUtf8 { offset: 5 })
+        ));
+
+        let mut deep = b"VB\x01".to_vec();
+        deep.extend(core::iter::repeat_n([LIST, 1], 200).flatten());
+        deep.push(NONE);
+        assert!(matches!(
+            from_slice(&deep),
+            Err(BinError::Limit(LimitError::Depth(128)))
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn stream() {
+        let mut encoder = StreamEncoder::new(Vec::new());
+        encoder.write(&sample()).unwrap();
+        encoder.write(&Value::from("second")).unwrap();
+        let bytes = encoder.into_inner();
+
+        let values = StreamDecoder::new(&bytes[..])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(values, vec![sample(), Value::from("second")]);
+
+        let mut decoder = StreamDecoder::new(&bytes[..bytes.len() - 1]);
+        assert!(decoder.next().unwrap().is_ok());
+        assert!(matches!(decoder.next(), Some(Err(BinError::UnexpectedEof))));
+
+        let empty = StreamEncoder::new(Vec::new()).into_inner();
+        assert!(StreamDecoder::new(&empty[..]).read().unwrap().is_none());
+        assert!(matches!(
+            StreamDecoder::new(&bytes[..2]).read(),
+            Err(BinError::UnexpectedEof)
+        ));
+    }
+
+    #[test]
+    fn large_dict() {
+        // Every entry used to be inserted with a scan of the previous ones.
+        let dict = (0..100_000u32).map(|n| (n, n)).collect::<Dict>();
+        let bytes = to_vec(&Value::from(dict));
+        let value = from_slice(&bytes).unwrap();
+        assert_eq!(value.as_dict().map(Dict::len), Some(100_000));
+    }
+}
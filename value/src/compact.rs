@@ -0,0 +1,508 @@
+//! A memory optimized, read-only representation of `Value` for caches
+//! holding many small records.
+//!
+//! [`CompactValue`] stores short strings inline, map keys as shared
+//! [`Key`]s deduplicated through an [`Interner`], maps as sorted slices
+//! instead of B-trees and boxes the rarely used variants. It takes 24 bytes
+//! instead of the 32 of a [`Value`] on 64-bit targets and small records need
+//! far fewer allocations. `Value` keeps its own layout, so it stays cheap to
+//! modify.
+//! Convert back with `Value::from` to modify a record.
+#[cfg(not(feature = "std"))]
+use alloc::{
+    borrow::ToOwned,
+    boxed::Box,
+    collections::BTreeSet,
+    string::{String, ToString},
+    sync::Arc,
+};
+use core::{fmt, mem::size_of, ops::Deref};
+#[cfg(feature = "std")]
+use std::{collections::BTreeSet, sync::Arc};
+
+use crate::{Dict, Map, Number, Value};
+
+const INLINE_CAPACITY: usize = 22;
+
+/// A string stored inline when it is at most 22 bytes long.
+#[derive(Clone)]
+pub struct SmallString(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Inline { len: u8, buf: [u8; INLINE_CAPACITY] },
+    Heap(Box<str>),
+}
+
+impl SmallString {
+    pub fn new(s: &str) -> SmallString {
+        if s.len() <= INLINE_CAPACITY {
+            let mut buf = [0; INLINE_CAPACITY];
+            buf[..s.len()].copy_from_slice(s.as_bytes());
+            SmallString(Repr::Inline {
+                len: s.len() as u8,
+                buf,
+            })
+        } else {
+            SmallString(Repr::Heap(s.into()))
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            // Only ever filled from a `&str` in `new`.
+            Repr::Inline { len, buf } => {
+                core::str::from_utf8(&buf[..*len as usize]).expect("inline string is utf-8")
+            }
+            Repr::Heap(s) => s,
+        }
+    }
+
+    pub fn is_inline(&self) -> bool {
+        matches!(self.0, Repr::Inline { .. })
+    }
+
+    /// Bytes allocated on the heap, zero for inline strings.
+    pub fn heap_size(&self) -> usize {
+        match &self.0 {
+            Repr::Inline { .. } => 0,
+            Repr::Heap(s) => s.len(),
+        }
+    }
+}
+
+impl Deref for SmallString {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for SmallString {
+    fn from(s: &str) -> SmallString {
+        SmallString::new(s)
+    }
+}
+
+impl From<String> for SmallString {
+    fn from(s: String) -> SmallString {
+        if s.len() <= INLINE_CAPACITY {
+            SmallString::new(&s)
+        } else {
+            SmallString(Repr::Heap(s.into_boxed_str()))
+        }
+    }
+}
+
+impl From<SmallString> for String {
+    fn from(s: SmallString) -> String {
+        match s.0 {
+            Repr::Inline { .. } => s.as_str().to_owned(),
+            Repr::Heap(s) => s.into_string(),
+        }
+    }
+}
+
+impl PartialEq for SmallString {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for SmallString {}
+
+impl fmt::Debug for SmallString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for SmallString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+/// An interned map key, cloning it only bumps a reference count.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Key(Arc<str>);
+
+impl Key {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Key {
+    type Target = str;
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+/// Deduplicates map keys, so every distinct key is allocated once no matter
+/// how many records use it. Share one interner between all records of a
+/// cache to get the most out of it.
+#[derive(Debug, Default, Clone)]
+pub struct Interner {
+    keys: BTreeSet<Arc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Interner {
+        Interner::default()
+    }
+
+    pub fn intern(&mut self, key: &str) -> Key {
+        if let Some(key) = self.keys.get(key) {
+            return Key(key.clone());
+        }
+        let key: Arc<str> = key.into();
+        self.keys.insert(key.clone());
+        Key(key)
+    }
+
+    /// Number of distinct keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Drop keys no longer used by any value.
+    pub fn purge(&mut self) {
+        self.keys.retain(|key| Arc::strong_count(key) > 1);
+    }
+
+    /// Bytes allocated for the interned keys themselves.
+    pub fn heap_size(&self) -> usize {
+        self.keys.iter().map(|key| key.len()).sum()
+    }
+}
+
+/// A map stored as a slice of entries sorted by key.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CompactMap {
+    entries: Box<[(Key, CompactValue)]>,
+}
+
+impl CompactMap {
+    pub fn get(&self, key: &str) -> Option<&CompactValue> {
+        self.entries
+            .binary_search_by(|(k, _)| k.as_str().cmp(key))
+            .ok()
+            .map(|idx| &self.entries[idx].1)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (&Key, &CompactValue)> + ExactSizeIterator {
+        self.entries.iter().map(|(k, v)| (k, v))
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &Key> + ExactSizeIterator {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &CompactValue> + ExactSizeIterator {
+        self.entries.iter().map(|(_, v)| v)
+    }
+}
+
+/// A read-only `Value` with a smaller memory footprint, see the
+/// [module docs](crate::compact).
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompactValue {
+    Bool(bool),
+    Number(Number),
+    Char(char),
+    String(SmallString),
+    List(Box<[CompactValue]>),
+    Map(CompactMap),
+    Dict(Box<Dict>),
+    Bytes(Box<[u8]>),
+    #[cfg(feature = "datetime")]
+    Date(chrono::NaiveDate),
+    #[cfg(feature = "datetime")]
+    DateTime(Box<chrono::NaiveDateTime>),
+    None,
+}
+
+impl CompactValue {
+    /// Convert `value`, interning its map keys in `interner`.
+    pub fn from_value(value: Value, interner: &mut Interner) -> CompactValue {
+        match value {
+            Value::Bool(b) => CompactValue::Bool(b),
+            Value::Number(n) => CompactValue::Number(n),
+            Value::Char(c) => CompactValue::Char(c),
+            Value::String(s) => CompactValue::String(s.into()),
+            Value::List(list) => CompactValue::List(
+                list.into_iter()
+                    .map(|v| CompactValue::from_value(v, interner))
+                    .collect(),
+            ),
+            // `Map` iterates in key order, so the entries come out sorted.
+            Value::Map(map) => CompactValue::Map(CompactMap {
+                entries: map
+                    .into_iter()
+                    .map(|(k, v)| (interner.intern(&k), CompactValue::from_value(v, interner)))
+                    .collect(),
+            }),
+            Value::Dict(dict) => CompactValue::Dict(dict),
+            Value::Bytes(bytes) => CompactValue::Bytes(bytes.into_boxed_slice()),
+            #[cfg(feature = "datetime")]
+            Value::Date(date) => CompactValue::Date(date),
+            #[cfg(feature = "datetime")]
+            Value::DateTime(datetime) => CompactValue::DateTime(Box::new(datetime)),
+            Value::None => CompactValue::None,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&CompactValue> {
+        match self {
+            CompactValue::Map(map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    pub fn get_index(&self, idx: usize) -> Option<&CompactValue> {
+        match self {
+            CompactValue::List(list) => list.get(idx),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            CompactValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<&Number> {
+        match self {
+            CompactValue::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            CompactValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[CompactValue]> {
+        match self {
+            CompactValue::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&CompactMap> {
+        match self {
+            CompactValue::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        matches!(self, CompactValue::None)
+    }
+
+    /// Bytes allocated on the heap by this value and its children. Interned
+    /// keys are shared and only counted by [`Interner::heap_size`].
+    pub fn heap_size(&self) -> usize {
+        match self {
+            CompactValue::String(s) => s.heap_size(),
+            CompactValue::List(list) => {
+                list.len() * size_of::<CompactValue>()
+                    + list.iter().map(CompactValue::heap_size).sum::<usize>()
+            }
+            CompactValue::Map(map) => {
+                map.len() * size_of::<(Key, CompactValue)>()
+                    + map.values().map(CompactValue::heap_size).sum::<usize>()
+            }
+            CompactValue::Dict(dict) => size_of::<Dict>() + dict_heap_size(dict),
+            CompactValue::Bytes(bytes) => bytes.len(),
+            #[cfg(feature = "datetime")]
+            CompactValue::DateTime(_) => size_of::<chrono::NaiveDateTime>(),
+            _ => 0,
+        }
+    }
+}
+
+impl From<Value> for CompactValue {
+    /// Convert with a fresh [`Interner`], which only deduplicates keys within
+    /// this value. Use [`CompactValue::from_value`] to share keys between
+    /// values.
+    fn from(value: Value) -> CompactValue {
+        CompactValue::from_value(value, &mut Interner::new())
+    }
+}
+
+impl From<CompactValue> for Value {
+    fn from(value: CompactValue) -> Value {
+        match value {
+            CompactValue::Bool(b) => Value::Bool(b),
+            CompactValue::Number(n) => Value::Number(n),
+            CompactValue::Char(c) => Value::Char(c),
+            CompactValue::String(s) => Value::String(s.into()),
+            CompactValue::List(list) => {
+                Value::List(list.into_vec().into_iter().map(Value::from).collect())
+            }
+            CompactValue::Map(map) => Value::Map(
+                map.entries
+                    .into_vec()
+                    .into_iter()
+                    .map(|(k, v)| (k.as_str().to_string(), Value::from(v)))
+                    .collect(),
+            ),
+            CompactValue::Dict(dict) => Value::Dict(dict),
+            CompactValue::Bytes(bytes) => Value::Bytes(bytes.into_vec()),
+            #[cfg(feature = "datetime")]
+            CompactValue::Date(date) => Value::Date(date),
+            #[cfg(feature = "datetime")]
+            CompactValue::DateTime(datetime) => Value::DateTime(*datetime),
+            CompactValue::None => Value::None,
+        }
+    }
+}
+
+impl Value {
+    /// Approximate number of bytes this value and its children allocate on
+    /// the heap. Map entries are counted by their size, ignoring the B-tree's
+    /// node overhead.
+    pub fn heap_size(&self) -> usize {
+        match self {
+            Value::String(s) => s.capacity(),
+            Value::List(list) => {
+                list.capacity() * size_of::<Value>()
+                    + list.iter().map(Value::heap_size).sum::<usize>()
+            }
+            Value::Map(map) => map_heap_size(map),
+            Value::Dict(dict) => dict_heap_size(dict),
+            Value::Bytes(bytes) => bytes.capacity(),
+            _ => 0,
+        }
+    }
+
+    /// Release excess capacity of every string, list and byte buffer in the
+    /// tree.
+    pub fn shrink_to_fit(&mut self) {
+        match self {
+            Value::String(s) => s.shrink_to_fit(),
+            Value::List(list) => {
+                list.shrink_to_fit();
+                list.iter_mut().for_each(Value::shrink_to_fit);
+            }
+            Value::Map(map) => map.values_mut().for_each(Value::shrink_to_fit),
+            Value::Dict(dict) => {
+                dict.entries.shrink_to_fit();
+                dict.index.shrink_to_fit();
+                for (k, v) in dict.entries.iter_mut() {
+                    k.shrink_to_fit();
+                    v.shrink_to_fit();
+                }
+            }
+            Value::Bytes(bytes) => bytes.shrink_to_fit(),
+            _ => {}
+        }
+    }
+}
+
+fn map_heap_size(map: &Map) -> usize {
+    map.iter()
+        .map(|(k, v)| size_of::<(String, Value)>() + k.capacity() + v.heap_size())
+        .sum()
+}
+
+fn dict_heap_size(dict: &Dict) -> usize {
+    dict.entries.capacity() * size_of::<(Value, Value)>()
+        + dict.index.capacity() * size_of::<usize>()
+        + dict
+            .iter()
+            .map(|(k, v)| k.heap_size() + v.heap_size())
+            .sum::<usize>()
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    use super::*;
+
+    fn record(name: &str) -> Value {
+        let mut map = Map::new();
+        map.insert("name", name);
+        map.insert("tags", Value::List(vec!["a".into(), "b".into()]));
+        map.insert("bio", "a string too long to be stored inline");
+        Value::Map(map)
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut interner = Interner::new();
+        let a = CompactValue::from_value(record("Rasmus"), &mut interner);
+        let b = CompactValue::from_value(record("Jane"), &mut interner);
+        assert_eq!(interner.len(), 3);
+
+        assert_eq!(a.get("name").and_then(CompactValue::as_str), Some("Rasmus"));
+        assert_eq!(
+            b.get("tags").and_then(|t| t.get_index(1)),
+            Some(&CompactValue::String("b".into()))
+        );
+        assert!(matches!(a.get("bio"), Some(CompactValue::String(s)) if !s.is_inline()));
+        assert_eq!(Value::from(a), record("Rasmus"));
+
+        drop(b);
+        interner.purge();
+        assert!(interner.is_empty());
+    }
+
+    #[test]
+    fn sizes() {
+        assert!(size_of::<CompactValue>() < size_of::<Value>());
+        // Keep the module docs in sync.
+        #[cfg(target_pointer_width = "64")]
+        {
+            assert_eq!(size_of::<CompactValue>(), 24);
+            assert_eq!(size_of::<Value>(), 32);
+        }
+
+        let mut value = record("Rasmus");
+        let compact = CompactValue::from(value.clone());
+        assert!(compact.heap_size() < value.heap_size());
+
+        if let Value::Map(map) = &mut value {
+            let mut name = String::with_capacity(64);
+            name.push_str("Rasmus");
+            map.insert("name", name);
+        }
+        let before = value.heap_size();
+        value.shrink_to_fit();
+        assert_eq!(before - value.heap_size(), 64 - "Rasmus".len());
+    }
+}
@@ -1,4 +1,5 @@
 mod number;
 mod value;
+mod value_ref;
 
-pub use self::{number::*, value::*};
+pub use self::{number::*, value::*, value_ref::*};
@@ -1,17 +1,14 @@
 #[cfg(not(feature = "std"))]
 use alloc::{
     borrow::ToOwned,
-    collections::BTreeMap,
     string::{String, ToString},
     vec::Vec,
 };
 use core::{fmt, marker::PhantomData};
 use serde::{de, forward_to_deserialize_any};
-#[cfg(feature = "std")]
-use std::collections::BTreeMap;
 
 use super::number;
-use crate::{value::Value, Map};
+use crate::{dict::MapBuilder, value::Value, Map};
 
 #[derive(Debug)]
 pub enum Unexpected {
@@ -43,7 +40,7 @@ pub(crate) fn unexpected(value: &Value) -> serde::de::Unexpected {
         Value::String(ref s) => serde::de::Unexpected::Str(s),
         Value::None => serde::de::Unexpected::Option,
         Value::List(_) => serde::de::Unexpected::Seq,
-        Value::Map(_) => serde::de::Unexpected::Map,
+        Value::Map(_) | Value::Dict(_) => serde::de::Unexpected::Map,
         Value::Bytes(ref b) => serde::de::Unexpected::Bytes(b),
         #[allow(unreachable_patterns)]
         _ => serde::de::Unexpected::Map,
@@ -296,9 +293,9 @@ impl<'de> de::Visitor<'de> for ValueVisitor {
         Ok(Value::String(value))
     }
 
-    // fn visit_unit<E>(self) -> Result<Value, E> {
-    //     Ok(Value::Unit)
-    // }
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::None)
+    }
 
     fn visit_none<E>(self) -> Result<Value, E> {
         Ok(Value::None)
@@ -322,11 +319,11 @@ impl<'de> de::Visitor<'de> for ValueVisitor {
     }
 
     fn visit_map<V: de::MapAccess<'de>>(self, mut visitor: V) -> Result<Value, V::Error> {
-        let mut values = BTreeMap::new();
-        while let Some((key, value)) = visitor.next_entry()? {
+        let mut values = MapBuilder::new();
+        while let Some((key, value)) = visitor.next_entry::<Value, Value>()? {
             values.insert(key, value);
         }
-        Ok(Value::Map(values.into()))
+        Ok(values.into_value())
     }
 
     fn visit_bytes<E>(self, v: &[u8]) -> Result<Value, E> {
@@ -394,6 +391,10 @@ where
                     )
                 },
             ))),
+            Value::Dict(v) => visitor
+                .visit_map(de::value::MapDeserializer::new(v.into_iter().map(
+                    |(k, v)| (ValueDeserializer::new(k), ValueDeserializer::new(v)),
+                ))),
             Value::Bytes(v) => visitor.visit_byte_buf(v),
             #[cfg(feature = "datetime")]
             Value::Date(v) => visitor.visit_string(v.to_string()),
@@ -0,0 +1,306 @@
+#[cfg(all(feature = "datetime", not(feature = "std")))]
+use alloc::string::ToString;
+use core::marker::PhantomData;
+use serde::{de, forward_to_deserialize_any};
+
+use super::{number, value::unexpected, DeserializerError};
+use crate::{value::Value, Map};
+
+/// Deserialize a `T` borrowing from `value`, so `&str` and `&[u8]` fields
+/// don't copy.
+pub fn from_value_ref<'de, T: de::Deserialize<'de>>(
+    value: &'de Value,
+) -> Result<T, DeserializerError> {
+    T::deserialize(ValueRefDeserializer::new(value))
+}
+
+/// Deserialize `value` over an existing `place` with serde's
+/// `deserialize_in_place`, reusing its allocations where the type supports
+/// it: `Vec` and `String` keep their buffers. Derived structs only update
+/// their fields in place with serde_derive's `deserialize_in_place` feature.
+pub fn from_value_ref_into<'de, T: de::Deserialize<'de>>(
+    value: &'de Value,
+    place: &mut T,
+) -> Result<(), DeserializerError> {
+    T::deserialize_in_place(ValueRefDeserializer::new(value), place)
+}
+
+/// Like [`ValueDeserializer`](super::ValueDeserializer) but borrowing the
+/// value instead of consuming it.
+pub struct ValueRefDeserializer<'de, E> {
+    value: &'de Value,
+    error: PhantomData<fn() -> E>,
+}
+
+impl<'de, E> ValueRefDeserializer<'de, E> {
+    pub fn new(value: &'de Value) -> Self {
+        ValueRefDeserializer {
+            value,
+            error: Default::default(),
+        }
+    }
+}
+
+fn map<'de, E>(
+    map: &'de Map,
+) -> de::value::MapDeserializer<
+    'de,
+    impl Iterator<
+        Item = (
+            de::value::BorrowedStrDeserializer<'de, E>,
+            ValueRefDeserializer<'de, E>,
+        ),
+    >,
+    E,
+>
+where
+    E: de::Error,
+{
+    de::value::MapDeserializer::new(map.iter().map(|(k, v)| {
+        (
+            de::value::BorrowedStrDeserializer::new(k.as_str()),
+            ValueRefDeserializer::new(v),
+        )
+    }))
+}
+
+impl<'de, E> de::Deserializer<'de> for ValueRefDeserializer<'de, E>
+where
+    E: de::Error,
+{
+    type Error = E;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Bool(v) => visitor.visit_bool(*v),
+            Value::Number(n) => number::NumberDeserializer::<E>::new(*n).deserialize_any(visitor),
+            Value::Char(v) => visitor.visit_char(*v),
+            Value::String(v) => visitor.visit_borrowed_str(v),
+            Value::None => visitor.visit_none(),
+            Value::List(v) => visitor.visit_seq(de::value::SeqDeserializer::new(
+                v.iter().map(ValueRefDeserializer::new),
+            )),
+            Value::Map(v) => visitor.visit_map(map(v)),
+            Value::Dict(v) => visitor
+                .visit_map(de::value::MapDeserializer::new(v.iter().map(|(k, v)| {
+                    (ValueRefDeserializer::new(k), ValueRefDeserializer::new(v))
+                }))),
+            Value::Bytes(v) => visitor.visit_borrowed_bytes(v),
+            #[cfg(feature = "datetime")]
+            Value::Date(v) => visitor.visit_string(v.to_string()),
+            #[cfg(feature = "datetime")]
+            Value::DateTime(v) => visitor.visit_string(v.to_string()),
+        }
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::None => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (variant, value) = match self.value {
+            Value::Map(value) => {
+                let mut iter = value.iter();
+                // enums are encoded as maps with a single key:value pair
+                match (iter.next(), iter.next()) {
+                    (Some((variant, value)), None) => (variant, Some(value)),
+                    _ => {
+                        return Err(de::Error::invalid_value(
+                            de::Unexpected::Map,
+                            &"map with a single key",
+                        ))
+                    }
+                }
+            }
+            Value::String(variant) => (variant, None),
+            other => return Err(de::Error::invalid_type(unexpected(other), &"string or map")),
+        };
+
+        visitor.visit_enum(EnumRefDeserializer {
+            variant,
+            value,
+            error: PhantomData,
+        })
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit
+        seq bytes byte_buf map unit_struct
+        tuple_struct struct tuple ignored_any identifier
+    }
+}
+
+impl<'de, E> de::IntoDeserializer<'de, E> for ValueRefDeserializer<'de, E>
+where
+    E: de::Error,
+{
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+struct EnumRefDeserializer<'de, E> {
+    variant: &'de str,
+    value: Option<&'de Value>,
+    error: PhantomData<fn() -> E>,
+}
+
+impl<'de, E> de::EnumAccess<'de> for EnumRefDeserializer<'de, E>
+where
+    E: de::Error,
+{
+    type Error = E;
+    type Variant = VariantRefDeserializer<'de, E>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant = VariantRefDeserializer {
+            value: self.value,
+            error: PhantomData,
+        };
+        seed.deserialize(de::value::BorrowedStrDeserializer::new(self.variant))
+            .map(|v| (v, variant))
+    }
+}
+
+struct VariantRefDeserializer<'de, E> {
+    value: Option<&'de Value>,
+    error: PhantomData<fn() -> E>,
+}
+
+impl<'de, E> de::VariantAccess<'de> for VariantRefDeserializer<'de, E>
+where
+    E: de::Error,
+{
+    type Error = E;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        match self.value {
+            Some(value) => de::Deserialize::deserialize(ValueRefDeserializer::new(value)),
+            None => Ok(()),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        match self.value {
+            Some(value) => seed.deserialize(ValueRefDeserializer::new(value)),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.value {
+            Some(Value::List(v)) => de::Deserializer::deserialize_any(
+                de::value::SeqDeserializer::new(v.iter().map(ValueRefDeserializer::new)),
+                visitor,
+            ),
+            Some(other) => Err(de::Error::invalid_type(unexpected(other), &"tuple variant")),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.value {
+            Some(Value::Map(v)) => de::Deserializer::deserialize_any(map(v), visitor),
+            Some(other) => Err(de::Error::invalid_type(
+                unexpected(other),
+                &"struct variant",
+            )),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::{string::String, vec, vec::Vec};
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Default, PartialEq, Deserialize)]
+    struct Config {
+        name: String,
+        ports: Vec<u16>,
+        mode: Option<Mode>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Mode {
+        Fast,
+        Limit { max: u8 },
+    }
+
+    #[test]
+    fn in_place() {
+        let mut value = Map::new();
+        value.insert("name", "web");
+        value.insert("ports", Value::List(vec![80u16.into(), 443u16.into()]));
+        let mut limit = Map::new();
+        limit.insert("max", 3u8);
+        let mut mode = Map::new();
+        mode.insert("Limit", limit);
+        value.insert("mode", mode);
+        let value = Value::Map(value);
+
+        let mut config = Config::default();
+        from_value_ref_into(&value, &mut config).unwrap();
+        assert_eq!(config.name, "web");
+        assert_eq!(config.ports, [80, 443]);
+        assert_eq!(config.mode, Some(Mode::Limit { max: 3 }));
+
+        let mut ports = Vec::<u16>::with_capacity(16);
+        from_value_ref_into(&value["ports"], &mut ports).unwrap();
+        assert_eq!(ports, [80, 443]);
+        assert!(ports.capacity() >= 16);
+
+        let mode = Value::String("Fast".into());
+        assert_eq!(from_value_ref::<Mode>(&mode).unwrap(), Mode::Fast);
+        let name = Value::String("borrowed".into());
+        assert_eq!(from_value_ref::<&str>(&name).unwrap(), "borrowed");
+        assert!(from_value_ref_into(&name, &mut config).is_err());
+    }
+}
@@ -0,0 +1,585 @@
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, string::ToString, vec, vec::Vec};
+use core::{cmp::Ordering, fmt, mem};
+
+use crate::{Map, Number, Value};
+
+/// A map keyed by arbitrary values, for data like `HashMap<u32, T>` or
+/// `BTreeMap<(i32, i32), T>` that does not fit a string keyed [`Map`].
+///
+/// Entries keep their insertion order, but equality doesn't depend on it.
+/// Keys are compared by value, so `1u8` and `1i64` are the same key and
+/// numbers are equal to floats with the same value. Lookups are a binary
+/// search; an insert of a new key shifts the index, so build large dicts
+/// with `collect` or `extend`, which sort once.
+#[derive(Clone, Default)]
+pub struct Dict {
+    pub(crate) entries: Vec<(Value, Value)>,
+    /// Positions in `entries`, sorted by key.
+    pub(crate) index: Vec<usize>,
+}
+
+impl Dict {
+    pub fn new() -> Dict {
+        Dict::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Dict {
+        Dict {
+            entries: Vec::with_capacity(capacity),
+            index: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// The position of `key` in the index, or where it would go.
+    fn search(&self, key: &Value) -> Result<usize, usize> {
+        self.index
+            .binary_search_by(|&idx| key_cmp(&self.entries[idx].0, key))
+    }
+
+    /// Insert a value, returning the previous value for an equal key.
+    pub fn insert(&mut self, key: impl Into<Value>, value: impl Into<Value>) -> Option<Value> {
+        let key = key.into();
+        let value = value.into();
+        match self.search(&key) {
+            Ok(pos) => Some(mem::replace(&mut self.entries[self.index[pos]].1, value)),
+            Err(pos) => {
+                self.index.insert(pos, self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        let pos = self.search(key).ok()?;
+        Some(&self.entries[self.index[pos]].1)
+    }
+
+    pub fn get_mut(&mut self, key: &Value) -> Option<&mut Value> {
+        let pos = self.search(key).ok()?;
+        Some(&mut self.entries[self.index[pos]].1)
+    }
+
+    pub fn contains_key(&self, key: &Value) -> bool {
+        self.search(key).is_ok()
+    }
+
+    /// Remove the entry for `key`, keeping the order of the remaining entries.
+    pub fn remove(&mut self, key: &Value) -> Option<Value> {
+        let pos = self.search(key).ok()?;
+        let idx = self.index.remove(pos);
+        for other in self.index.iter_mut().filter(|other| **other > idx) {
+            *other -= 1;
+        }
+        Some(self.entries.remove(idx).1)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&Value, &Value)> + ExactSizeIterator {
+        self.entries.iter().map(|(k, v)| (k, v))
+    }
+
+    pub fn iter_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = (&Value, &mut Value)> + ExactSizeIterator {
+        self.entries.iter_mut().map(|(k, v)| (&*k, v))
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &Value> + ExactSizeIterator {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &Value> + ExactSizeIterator {
+        self.entries.iter().map(|(_, v)| v)
+    }
+
+    pub fn values_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = &mut Value> + ExactSizeIterator {
+        self.entries.iter_mut().map(|(_, v)| v)
+    }
+
+    /// Convert into a string keyed map if every key already is a string.
+    pub fn into_map(self) -> Result<Map, Dict> {
+        if !self.entries.iter().all(|(k, _)| k.is_string()) {
+            return Err(self);
+        }
+
+        Ok(self
+            .entries
+            .into_iter()
+            .filter_map(|(k, v)| k.into_string().ok().map(|k| (k, v)))
+            .collect())
+    }
+
+    /// Convert into a string keyed map, formatting scalar keys as strings so
+    /// the result can be written as JSON. Values are converted recursively,
+    /// see [`Value::into_string_keyed`].
+    pub fn into_string_keyed(self) -> Result<Map, KeyError> {
+        let mut map = Map::new();
+        for (key, value) in self.entries {
+            let key = match key {
+                Value::String(s) => s,
+                Value::Char(c) => c.to_string(),
+                Value::Bool(b) => b.to_string(),
+                Value::Number(n) => n.to_string(),
+                key => return Err(KeyError { key }),
+            };
+            map.insert(key, value.into_string_keyed()?);
+        }
+        Ok(map)
+    }
+}
+
+impl Dict {
+    /// Entries in key order.
+    fn sorted(&self) -> impl Iterator<Item = (&Value, &Value)> + '_ {
+        self.index.iter().map(|&idx| {
+            let (k, v) = &self.entries[idx];
+            (k, v)
+        })
+    }
+
+    /// Rebuild the index after appending to `entries`. Entries with equal
+    /// keys are merged into the first one, holding the last value.
+    fn reindex(&mut self) {
+        let mut order = (0..self.entries.len()).collect::<Vec<_>>();
+        // Stable, so the earliest entry leads each run of equal keys.
+        order.sort_by(|&a, &b| key_cmp(&self.entries[a].0, &self.entries[b].0));
+
+        let mut keep = vec![true; self.entries.len()];
+        let mut duplicates = false;
+        let mut start = 0;
+        while start < order.len() {
+            let first = order[start];
+            let mut end = start + 1;
+            while end < order.len()
+                && key_cmp(&self.entries[first].0, &self.entries[order[end]].0).is_eq()
+            {
+                end += 1;
+            }
+            if end - start > 1 {
+                let value = mem::replace(&mut self.entries[order[end - 1]].1, Value::None);
+                self.entries[first].1 = value;
+                for &idx in &order[start + 1..end] {
+                    keep[idx] = false;
+                }
+                duplicates = true;
+            }
+            start = end;
+        }
+        if !duplicates {
+            self.index = order;
+            return;
+        }
+
+        let mut position = Vec::with_capacity(keep.len());
+        let mut next = 0;
+        for &kept in &keep {
+            position.push(next);
+            next += kept as usize;
+        }
+        let mut idx = 0;
+        self.entries.retain(|_| {
+            idx += 1;
+            keep[idx - 1]
+        });
+        self.index = order
+            .into_iter()
+            .filter(|&idx| keep[idx])
+            .map(|idx| position[idx])
+            .collect();
+    }
+}
+
+impl fmt::Debug for Dict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dict")
+            .field("entries", &self.entries)
+            .finish()
+    }
+}
+
+impl PartialEq for Dict {
+    fn eq(&self, other: &Dict) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+#[cfg(not(feature = "ordered_float"))]
+impl PartialOrd for Dict {
+    fn partial_cmp(&self, other: &Dict) -> Option<Ordering> {
+        for ((ka, va), (kb, vb)) in self.sorted().zip(other.sorted()) {
+            match key_cmp(ka, kb) {
+                Ordering::Equal => {}
+                ordering => return Some(ordering),
+            }
+            match va.partial_cmp(vb)? {
+                Ordering::Equal => {}
+                ordering => return Some(ordering),
+            }
+        }
+        Some(self.len().cmp(&other.len()))
+    }
+}
+
+#[cfg(feature = "ordered_float")]
+impl Eq for Dict {}
+
+#[cfg(feature = "ordered_float")]
+impl PartialOrd for Dict {
+    fn partial_cmp(&self, other: &Dict) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(feature = "ordered_float")]
+impl Ord for Dict {
+    fn cmp(&self, other: &Dict) -> Ordering {
+        self.sorted()
+            .zip(other.sorted())
+            .map(|((ka, va), (kb, vb))| key_cmp(ka, kb).then_with(|| va.cmp(vb)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| self.len().cmp(&other.len()))
+    }
+}
+
+#[cfg(feature = "ordered_float")]
+impl core::hash::Hash for Dict {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        // Keys that compare equal may differ in width, so only the values
+        // are hashed, in key order.
+        self.len().hash(state);
+        for (_, value) in self.sorted() {
+            value.hash(state);
+        }
+    }
+}
+
+/// The order of dict keys: by variant, then by value, with numbers of any
+/// width or float compared as numbers and NaN last.
+fn key_cmp(a: &Value, b: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::None => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::Char(_) => 3,
+            Value::String(_) => 4,
+            Value::Bytes(_) => 5,
+            Value::List(_) => 6,
+            Value::Map(_) => 7,
+            Value::Dict(_) => 8,
+            #[cfg(feature = "datetime")]
+            Value::Date(_) => 9,
+            #[cfg(feature = "datetime")]
+            Value::DateTime(_) => 10,
+        }
+    }
+
+    fn entries<'a>(
+        a: impl Iterator<Item = (&'a Value, &'a Value)>,
+        b: impl Iterator<Item = (&'a Value, &'a Value)>,
+    ) -> Option<Ordering> {
+        a.zip(b)
+            .map(|((ka, va), (kb, vb))| key_cmp(ka, kb).then_with(|| key_cmp(va, vb)))
+            .find(|ordering| ordering.is_ne())
+    }
+
+    match (a, b) {
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Number(a), Value::Number(b)) => number_cmp(a, b),
+        (Value::Char(a), Value::Char(b)) => a.cmp(b),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Bytes(a), Value::Bytes(b)) => a.cmp(b),
+        (Value::List(a), Value::List(b)) => a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| key_cmp(a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        (Value::Map(a), Value::Map(b)) => a
+            .iter()
+            .zip(b.iter())
+            .map(|((ka, va), (kb, vb))| ka.cmp(kb).then_with(|| key_cmp(va, vb)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        (Value::Dict(a), Value::Dict(b)) => {
+            entries(a.sorted(), b.sorted()).unwrap_or_else(|| a.len().cmp(&b.len()))
+        }
+        #[cfg(feature = "datetime")]
+        (Value::Date(a), Value::Date(b)) => a.cmp(b),
+        #[cfg(feature = "datetime")]
+        (Value::DateTime(a), Value::DateTime(b)) => a.cmp(b),
+        (a, b) => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn number_cmp(a: &Number, b: &Number) -> Ordering {
+    fn integer(n: &Number) -> Option<i128> {
+        match *n {
+            Number::F32(_) | Number::F64(_) => None,
+            Number::U64(n) => Some(n.into()),
+            n => Some(n.as_i64().into()),
+        }
+    }
+
+    fn int_float(i: i128, f: f64) -> Ordering {
+        if f.is_nan() {
+            return Ordering::Less;
+        }
+        // Rounding `i` is monotonic, so it only decides unequal pairs; an
+        // equal float is integral and converts back exactly.
+        match (i as f64).partial_cmp(&f) {
+            Some(Ordering::Equal) | None => i.cmp(&(f as i128)),
+            Some(ordering) => ordering,
+        }
+    }
+
+    match (integer(a), integer(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(a), None) => int_float(a, b.as_f64()),
+        (None, Some(b)) => int_float(b, a.as_f64()).reverse(),
+        (None, None) => {
+            let (a, b) = (a.as_f64(), b.as_f64());
+            a.partial_cmp(&b)
+                .unwrap_or_else(|| a.is_nan().cmp(&b.is_nan()))
+        }
+    }
+}
+
+/// Collects the entries of a map of unknown key types: into a [`Map`] while
+/// every key is a string, and into a [`Dict`], sorted once at the end, from
+/// the first key that isn't.
+#[cfg(any(feature = "serde", feature = "yaml"))]
+pub(crate) enum MapBuilder {
+    Map(Map),
+    Dict(Vec<(Value, Value)>),
+}
+
+#[cfg(any(feature = "serde", feature = "yaml"))]
+impl MapBuilder {
+    pub(crate) fn new() -> MapBuilder {
+        MapBuilder::Map(Map::new())
+    }
+
+    pub(crate) fn insert(&mut self, key: Value, value: Value) {
+        match self {
+            MapBuilder::Map(map) => match key {
+                Value::String(key) => {
+                    map.inner.insert(key, value);
+                }
+                key => {
+                    let mut entries = mem::take(map)
+                        .into_iter()
+                        .map(|(k, v)| (Value::String(k), v))
+                        .collect::<Vec<_>>();
+                    entries.push((key, value));
+                    *self = MapBuilder::Dict(entries);
+                }
+            },
+            MapBuilder::Dict(entries) => entries.push((key, value)),
+        }
+    }
+
+    pub(crate) fn into_value(self) -> Value {
+        match self {
+            MapBuilder::Map(map) => Value::Map(map),
+            MapBuilder::Dict(entries) => Value::from(entries.into_iter().collect::<Dict>()),
+        }
+    }
+}
+
+impl Value {
+    /// Replace every [`Dict`] in the tree by a string keyed [`Map`], see
+    /// [`Dict::into_string_keyed`].
+    pub fn into_string_keyed(self) -> Result<Value, KeyError> {
+        let value = match self {
+            Value::Dict(dict) => Value::Map(dict.into_string_keyed()?),
+            Value::Map(map) => Value::Map(
+                map.into_iter()
+                    .map(|(k, v)| v.into_string_keyed().map(|v| (k, v)))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::List(list) => Value::List(
+                list.into_iter()
+                    .map(Value::into_string_keyed)
+                    .collect::<Result<_, _>>()?,
+            ),
+            value => value,
+        };
+        Ok(value)
+    }
+}
+
+/// A [`Dict`] key that has no string representation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyError {
+    key: Value,
+}
+
+impl KeyError {
+    pub fn key(&self) -> &Value {
+        &self.key
+    }
+
+    pub fn into_key(self) -> Value {
+        self.key
+    }
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "key cannot be represented as a string: {:?}", self.key)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for KeyError {}
+
+impl<K: Into<Value>, V: Into<Value>> Extend<(K, V)> for Dict {
+    /// Append the entries and sort the index once, so a large batch costs
+    /// `O(n log n)` rather than an insert each.
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        let len = self.entries.len();
+        self.entries
+            .extend(iter.into_iter().map(|(k, v)| (k.into(), v.into())));
+        if self.entries.len() > len {
+            self.reindex();
+        }
+    }
+}
+
+impl<K: Into<Value>, V: Into<Value>> FromIterator<(K, V)> for Dict {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut dict = Dict::new();
+        dict.extend(iter);
+        dict
+    }
+}
+
+impl IntoIterator for Dict {
+    type Item = (Value, Value);
+    type IntoIter = <Vec<(Value, Value)> as IntoIterator>::IntoIter;
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl From<Map> for Dict {
+    fn from(map: Map) -> Dict {
+        map.into_iter()
+            .map(|(k, v)| (Value::String(k), v))
+            .collect()
+    }
+}
+
+impl From<Dict> for Value {
+    fn from(dict: Dict) -> Value {
+        Value::Dict(Box::new(dict))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::{vec, vec::Vec};
+
+    use super::*;
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn boxed_in_value() {
+        assert_eq!(core::mem::size_of::<Dict>(), 48);
+        assert_eq!(core::mem::size_of::<Value>(), 32);
+    }
+
+    #[test]
+    fn string_keyed() {
+        let dict: Dict = [(Value::from(1u32), "one"), (Value::Char('c'), "c")]
+            .into_iter()
+            .collect();
+        let map = Value::List(vec![Value::from(dict)])
+            .into_string_keyed()
+            .unwrap();
+        assert_eq!(map[0]["1"], Value::from("one"));
+        assert_eq!(map[0]["c"], Value::from("c"));
+
+        let dict: Dict = [(Value::List(vec![]), 1u8)].into_iter().collect();
+        assert!(dict.clone().into_map().is_err());
+        assert_eq!(
+            dict.into_string_keyed().unwrap_err().into_key(),
+            Value::List(vec![])
+        );
+    }
+
+    #[test]
+    fn index() {
+        let mut dict: Dict = [
+            (Value::from(2u8), "a"),
+            (Value::from(1u8), "b"),
+            (Value::from(2i64), "c"),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(dict.len(), 2);
+        assert_eq!(
+            dict.keys().collect::<Vec<_>>(),
+            [&Value::from(2u8), &Value::from(1u8)]
+        );
+        assert_eq!(dict.get(&Value::from(2.0f64)), Some(&Value::from("c")));
+        assert_eq!(dict.get(&Value::from(2.5f64)), None);
+
+        let reversed: Dict = dict
+            .iter()
+            .rev()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        assert_eq!(reversed, dict);
+
+        assert_eq!(dict.insert(Value::List(vec![]), true), None);
+        assert_eq!(dict.remove(&Value::from(2u32)), Some(Value::from("c")));
+        assert_eq!(dict.get(&Value::from(1u8)), Some(&Value::from("b")));
+        assert_eq!(dict.get(&Value::List(vec![])), Some(&Value::from(true)));
+        assert_ne!(reversed, dict);
+
+        let large: Dict = (0..100_000u32).rev().map(|n| (n, n)).collect();
+        assert_eq!(large.len(), 100_000);
+        assert_eq!(large.get(&Value::from(42u64)), Some(&Value::from(42u32)));
+    }
+
+    #[cfg(all(feature = "serde", feature = "std"))]
+    #[test]
+    fn serde_round_trip() {
+        use crate::{from_value, to_value};
+        use std::collections::{BTreeMap, HashMap};
+
+        let map = HashMap::from([(1u32, "a"), (2u32, "b")]);
+        let value = to_value(&map).unwrap();
+        assert!(value.is_dict());
+        assert_eq!(from_value::<HashMap<u32, String>>(value).unwrap().len(), 2);
+
+        let map = BTreeMap::from([((1, 2), true), ((3, 4), false)]);
+        let value = to_value(&map).unwrap();
+        assert_eq!(
+            from_value::<BTreeMap<(i32, i32), bool>>(value).unwrap(),
+            map
+        );
+
+        let value = to_value(BTreeMap::from([("a", 1)])).unwrap();
+        assert!(value.is_map());
+    }
+}
@@ -0,0 +1,347 @@
+//! Comparing values by meaning rather than representation, mostly for tests.
+#[cfg(not(feature = "std"))]
+use alloc::{format, vec, vec::Vec};
+use core::{fmt, mem};
+
+use crate::{Number, Path, Value};
+
+/// What [`Value::semantic_eq`] overlooks. The default is strict: numbers
+/// must have the same width and every key must be present on both sides.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct EqOptions {
+    pub(crate) ignore_number_width: bool,
+    pub(crate) float_epsilon: f64,
+    pub(crate) missing_as_none: bool,
+    pub(crate) unordered_lists: bool,
+}
+
+impl EqOptions {
+    /// Compare numbers by value, so `U8(1)` equals `I32(1)` and `F64(1.0)`.
+    pub fn with_ignore_number_width(mut self, ignore: bool) -> Self {
+        self.ignore_number_width = ignore;
+        self
+    }
+
+    /// Floats within `epsilon` of each other are equal.
+    pub fn with_float_epsilon(mut self, epsilon: f64) -> Self {
+        self.float_epsilon = epsilon;
+        self
+    }
+
+    /// A missing map key equals a key holding `None`.
+    pub fn with_missing_as_none(mut self, missing_as_none: bool) -> Self {
+        self.missing_as_none = missing_as_none;
+        self
+    }
+
+    /// Lists are equal when they hold the same items in any order.
+    pub fn with_unordered_lists(mut self, unordered: bool) -> Self {
+        self.unordered_lists = unordered;
+        self
+    }
+}
+
+/// A place where two values differ. A side is `None` when the key or item
+/// is missing from it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    pub path: Path,
+    pub left: Option<Value>,
+    pub right: Option<Value>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at '{}': ", self.path)?;
+        match (&self.left, &self.right) {
+            (Some(left), Some(right)) => write!(f, "left {:?}, right {:?}", left, right),
+            (Some(left), None) => write!(f, "left {:?}, missing on the right", left),
+            (None, Some(right)) => write!(f, "missing on the left, right {:?}", right),
+            (None, None) => write!(f, "missing on both sides"),
+        }
+    }
+}
+
+impl Value {
+    /// Equality under `options`, see [`EqOptions`].
+    pub fn semantic_eq(&self, other: &Value, options: &EqOptions) -> bool {
+        Compare {
+            options,
+            differences: None,
+        }
+        .value(&Path::new(), self, other)
+    }
+
+    /// Every place where `self` and `other` differ under `options`, in
+    /// depth first order. Empty when they are [`semantic_eq`](Self::semantic_eq).
+    pub fn differences(&self, other: &Value, options: &EqOptions) -> Vec<Difference> {
+        let mut compare = Compare {
+            options,
+            differences: Some(Vec::new()),
+        };
+        compare.value(&Path::new(), self, other);
+        compare.differences.unwrap_or_default()
+    }
+}
+
+/// Walks two values side by side. Without `differences` it stops at the
+/// first one.
+struct Compare<'a> {
+    options: &'a EqOptions,
+    differences: Option<Vec<Difference>>,
+}
+
+impl Compare<'_> {
+    fn differ(&mut self, path: &Path, left: Option<&Value>, right: Option<&Value>) -> bool {
+        if let Some(differences) = &mut self.differences {
+            differences.push(Difference {
+                path: path.clone(),
+                left: left.cloned(),
+                right: right.cloned(),
+            });
+        }
+        false
+    }
+
+    /// Whether to keep looking after a difference.
+    fn collecting(&self) -> bool {
+        self.differences.is_some()
+    }
+
+    fn value(&mut self, path: &Path, left: &Value, right: &Value) -> bool {
+        match (left, right) {
+            (Value::Number(a), Value::Number(b)) if self.number(a, b) => true,
+            (Value::Map(a), Value::Map(b)) => {
+                let mut equal = true;
+                let mut keys = a.keys().chain(b.keys()).collect::<Vec<_>>();
+                keys.sort();
+                keys.dedup();
+                for key in keys {
+                    let path = path.clone().key(key.as_str());
+                    equal &= match (a.get(key), b.get(key)) {
+                        (Some(l), Some(r)) => self.value(&path, l, r),
+                        (Some(Value::None), None) | (None, Some(Value::None))
+                            if self.options.missing_as_none =>
+                        {
+                            true
+                        }
+                        (l, r) => self.differ(&path, l, r),
+                    };
+                    if !equal && !self.collecting() {
+                        return false;
+                    }
+                }
+                equal
+            }
+            (Value::Dict(a), Value::Dict(b)) => {
+                let mut equal = true;
+                let right_only = b.iter().filter(|(k, _)| a.get(k).is_none());
+                let entries = a.iter().map(|(k, v)| (k, Some(v), b.get(k)));
+                for (key, l, r) in entries.chain(right_only.map(|(k, v)| (k, None, Some(v)))) {
+                    let path = match key {
+                        Value::String(key) => path.clone().key(key.as_str()),
+                        key => path.clone().key(format!("{:?}", key)),
+                    };
+                    equal &= match (l, r) {
+                        (Some(l), Some(r)) => self.value(&path, l, r),
+                        (Some(Value::None), None) | (None, Some(Value::None))
+                            if self.options.missing_as_none =>
+                        {
+                            true
+                        }
+                        (l, r) => self.differ(&path, l, r),
+                    };
+                    if !equal && !self.collecting() {
+                        return false;
+                    }
+                }
+                equal
+            }
+            (Value::List(a), Value::List(b)) if self.options.unordered_lists => {
+                self.unordered(path, a, b)
+            }
+            (Value::List(a), Value::List(b)) => {
+                let mut equal = true;
+                for idx in 0..a.len().max(b.len()) {
+                    let path = path.clone().index(idx);
+                    equal &= match (a.get(idx), b.get(idx)) {
+                        (Some(l), Some(r)) => self.value(&path, l, r),
+                        (l, r) => self.differ(&path, l, r),
+                    };
+                    if !equal && !self.collecting() {
+                        return false;
+                    }
+                }
+                equal
+            }
+            (Value::Number(_), _)
+            | (Value::Map(_), _)
+            | (Value::Dict(_), _)
+            | (Value::List(_), _) => self.differ(path, Some(left), Some(right)),
+            (left, right) if left == right => true,
+            (left, right) => self.differ(path, Some(left), Some(right)),
+        }
+    }
+
+    /// Pair as many items of `left` as possible with an equal item of
+    /// `right`. Equality isn't transitive with a float epsilon, so the first
+    /// equal item isn't necessarily the right one and this is a maximum
+    /// bipartite matching. Unpaired items on either side are the differences.
+    fn unordered(&mut self, path: &Path, left: &[Value], right: &[Value]) -> bool {
+        let differences = mem::take(&mut self.differences);
+        let candidates = left
+            .iter()
+            .map(|l| {
+                (0..right.len())
+                    .filter(|&r| self.value(path, l, &right[r]))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        self.differences = differences;
+
+        let pairs = max_matching(&candidates, right.len());
+        let mut used = vec![false; right.len()];
+        for &r in pairs.iter().flatten() {
+            used[r] = true;
+        }
+
+        let mut equal = true;
+        for (idx, _) in pairs.iter().enumerate().filter(|(_, pair)| pair.is_none()) {
+            equal = self.differ(&path.clone().index(idx), Some(&left[idx]), None);
+        }
+        for (idx, _) in used.iter().enumerate().filter(|(_, used)| !**used) {
+            equal = self.differ(&path.clone().index(idx), None, Some(&right[idx]));
+        }
+        equal
+    }
+
+    fn number(&self, a: &Number, b: &Number) -> bool {
+        if !self.options.ignore_number_width && mem::discriminant(a) != mem::discriminant(b) {
+            return false;
+        }
+        if a.is_float() || b.is_float() {
+            let (a, b) = (a.as_f64(), b.as_f64());
+            let delta = if a > b { a - b } else { b - a };
+            a == b || delta <= self.options.float_epsilon
+        } else {
+            integer(a) == integer(b)
+        }
+    }
+}
+
+/// A maximum matching between the left items, each with the right items it
+/// may pair with, and `right` right items. Returns the pair of every left
+/// item. Augmenting paths are searched depth first without recursion.
+fn max_matching(candidates: &[Vec<usize>], right: usize) -> Vec<Option<usize>> {
+    let mut owner = vec![None::<usize>; right];
+    let mut visited = vec![false; right];
+    let mut stack = Vec::new();
+    for start in 0..candidates.len() {
+        visited.iter_mut().for_each(|v| *v = false);
+        stack.clear();
+        stack.push((start, 0));
+        while let Some((left, next)) = stack.last_mut() {
+            let Some(&r) = candidates[*left].get(*next) else {
+                stack.pop();
+                continue;
+            };
+            *next += 1;
+            if mem::replace(&mut visited[r], true) {
+                continue;
+            }
+            match owner[r] {
+                Some(other) => stack.push((other, 0)),
+                None => {
+                    // Every frame moves to the right item it tried last.
+                    for &(left, next) in &stack {
+                        owner[candidates[left][next - 1]] = Some(left);
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    let mut pairs = vec![None; candidates.len()];
+    for (r, left) in owner.into_iter().enumerate() {
+        if let Some(left) = left {
+            pairs[left] = Some(r);
+        }
+    }
+    pairs
+}
+
+fn integer(n: &Number) -> i128 {
+    match *n {
+        Number::U64(n) => n.into(),
+        n => n.as_i64().into(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::string::ToString;
+
+    use super::*;
+    use crate::Map;
+
+    #[test]
+    fn options() {
+        let strict = EqOptions::default();
+        let loose = EqOptions::default()
+            .with_ignore_number_width(true)
+            .with_float_epsilon(1e-9)
+            .with_missing_as_none(true)
+            .with_unordered_lists(true);
+
+        let one = Value::from(1u8);
+        assert!(!one.semantic_eq(&Value::from(1i32), &strict));
+        assert!(one.semantic_eq(&Value::from(1i32), &loose));
+        assert!(one.semantic_eq(&Value::from(1.0f64), &loose));
+        assert!(!Value::from(-1i64).semantic_eq(&Value::from(u64::MAX), &loose));
+        assert!(Value::from(0.1 + 0.2).semantic_eq(&Value::from(0.3), &loose));
+        assert!(!Value::from(0.1 + 0.2).semantic_eq(&Value::from(0.3), &strict));
+
+        let mut a = Map::new();
+        a.insert("x", Value::None);
+        a.insert("list", Value::List(vec![1u8.into(), 2u8.into()]));
+        let mut b = Map::new();
+        b.insert("list", Value::List(vec![2u8.into(), 1u8.into()]));
+        assert!(Value::Map(a.clone()).semantic_eq(&Value::Map(b.clone()), &loose));
+        assert!(!Value::Map(a).semantic_eq(&Value::Map(b), &strict));
+
+        // Greedily pairing 1.0 with 1.05 would leave 1.1 without a partner.
+        let close = EqOptions::default()
+            .with_float_epsilon(0.06)
+            .with_unordered_lists(true);
+        let left = Value::List(vec![1.0f64.into(), 1.1f64.into()]);
+        let right = Value::List(vec![1.05f64.into(), 0.95f64.into()]);
+        assert!(left.semantic_eq(&right, &close));
+        let right = Value::List(vec![1.05f64.into(), 0.5f64.into()]);
+        assert_eq!(left.differences(&right, &close).len(), 2);
+    }
+
+    #[test]
+    fn differences() {
+        let mut a = Map::new();
+        a.insert("name", "a");
+        a.insert("tags", Value::List(vec!["x".into(), "y".into()]));
+        let mut b = a.clone();
+        b.insert("name", "b");
+        b.insert("extra", true);
+        b.insert("tags", Value::List(vec!["x".into()]));
+
+        let differences = Value::Map(a).differences(&Value::Map(b), &EqOptions::default());
+        let paths = differences
+            .iter()
+            .map(|d| d.path.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["extra", "name", "tags[1]"]);
+        assert_eq!(differences[0].left, None);
+        assert_eq!(
+            differences[2].to_string(),
+            "at 'tags[1]': left String(\"y\"), missing on the right"
+        );
+    }
+}
@@ -0,0 +1,744 @@
+//! Documents read as a stream of events instead of a tree.
+//!
+//! An [`Events`] reader reports a document depth first: a container is a
+//! start event, its items and an end event, and a map entry is a
+//! [`Event::Key`] followed by the value. [`JsonEvents`] reads JSON and
+//! [`Decoder::events`](crate::bin::Decoder::events) the binary format.
+//!
+//! A [`Selector`] builds only the parts of a document at a pointer and skips
+//! the rest, so pieces of a large payload can be extracted without
+//! materializing all of it.
+#[cfg(not(feature = "std"))]
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::borrow::Cow;
+
+use crate::{
+    pointer::{parse_index, parse_pointer},
+    Dict, LimitError, Limits, Map, Number, PathError, Value,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<'a> {
+    StartMap,
+    /// The key of the map entry whose value comes next.
+    Key(Cow<'a, str>),
+    EndMap,
+    StartList,
+    EndList,
+    /// A dict, whose items alternate between a key and a value, both of
+    /// them full values.
+    StartDict,
+    EndDict,
+    Bool(bool),
+    Number(Number),
+    Char(char),
+    String(Cow<'a, str>),
+    Bytes(Cow<'a, [u8]>),
+    #[cfg(feature = "datetime")]
+    Date(chrono::NaiveDate),
+    #[cfg(feature = "datetime")]
+    DateTime(chrono::NaiveDateTime),
+    None,
+}
+
+/// A reader producing the events of a single document.
+pub trait Events<'a> {
+    type Error;
+
+    /// The next event, or `None` once the document is complete. Readers
+    /// that can tell report input after the document as an error instead.
+    fn next_event(&mut self) -> Result<Option<Event<'a>>, Self::Error>;
+}
+
+impl<'a, E: Events<'a> + ?Sized> Events<'a> for &mut E {
+    type Error = E::Error;
+
+    fn next_event(&mut self) -> Result<Option<Event<'a>>, Self::Error> {
+        (**self).next_event()
+    }
+}
+
+/// Build the whole document, or `None` if `events` is already exhausted.
+/// Fails if the reader reports input after the document.
+pub fn read_value<'a, E: Events<'a> + ?Sized>(events: &mut E) -> Result<Option<Value>, E::Error> {
+    let value = next_value(events)?;
+    if value.is_some() {
+        finish(events)?;
+    }
+    Ok(value)
+}
+
+/// Build the next value, or `None` at the end of the document.
+fn next_value<'a, E: Events<'a> + ?Sized>(events: &mut E) -> Result<Option<Value>, E::Error> {
+    match events.next_event()? {
+        Some(event) => build(event, events).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Ask for the event after the root, surfacing errors like trailing input.
+fn finish<'a, E: Events<'a> + ?Sized>(events: &mut E) -> Result<(), E::Error> {
+    let rest = events.next_event()?;
+    debug_assert!(rest.is_none(), "event after the end of the document");
+    Ok(())
+}
+
+/// Build the value starting with `first`.
+fn build<'a, E: Events<'a> + ?Sized>(first: Event<'a>, events: &mut E) -> Result<Value, E::Error> {
+    let value = match first {
+        Event::StartMap => {
+            let mut map = Map::new();
+            while let Some(Event::Key(key)) = events.next_event()? {
+                let value = next_value(events)?.unwrap_or(Value::None);
+                map.insert(key, value);
+            }
+            Value::Map(map)
+        }
+        Event::StartList => {
+            let mut list = Vec::new();
+            while let Some(item) = item(events, &Event::EndList)? {
+                list.push(build(item, events)?);
+            }
+            Value::List(list)
+        }
+        Event::StartDict => {
+            let mut entries = Vec::new();
+            while let Some(key) = item(events, &Event::EndDict)? {
+                let key = build(key, events)?;
+                let value = next_value(events)?.unwrap_or(Value::None);
+                entries.push((key, value));
+            }
+            Value::from(entries.into_iter().collect::<Dict>())
+        }
+        Event::Bool(b) => Value::Bool(b),
+        Event::Number(n) => Value::Number(n),
+        Event::Char(c) => Value::Char(c),
+        Event::String(s) => Value::String(s.into_owned()),
+        Event::Bytes(bytes) => Value::Bytes(bytes.into_owned()),
+        #[cfg(feature = "datetime")]
+        Event::Date(date) => Value::Date(date),
+        #[cfg(feature = "datetime")]
+        Event::DateTime(datetime) => Value::DateTime(datetime),
+        Event::Key(_) | Event::EndMap | Event::EndList | Event::EndDict | Event::None => {
+            Value::None
+        }
+    };
+    Ok(value)
+}
+
+/// The first event of the next item in a list or dict, `None` at `end`.
+fn item<'a, E: Events<'a> + ?Sized>(
+    events: &mut E,
+    end: &Event<'_>,
+) -> Result<Option<Event<'a>>, E::Error> {
+    Ok(events.next_event()?.filter(|event| event != end))
+}
+
+/// Consume the rest of the value starting with `first`.
+fn skip<'a, E: Events<'a> + ?Sized>(first: Event<'a>, events: &mut E) -> Result<(), E::Error> {
+    let mut depth = 0usize;
+    let mut event = Some(first);
+    while let Some(current) = event {
+        match current {
+            Event::StartMap | Event::StartList | Event::StartDict => depth += 1,
+            Event::EndMap | Event::EndList | Event::EndDict => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if depth == 0 {
+            break;
+        }
+        event = events.next_event()?;
+    }
+    Ok(())
+}
+
+/// Materializes the values of a document at a JSON Pointer.
+///
+/// Besides the tokens of RFC 6901, `*` matches every map key and list
+/// index, so `/items/*/id` selects the id of each item. Dict entries match
+/// on string keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector {
+    tokens: Vec<String>,
+}
+
+impl Selector {
+    pub fn new(pointer: &str) -> Result<Selector, PathError> {
+        Ok(Selector {
+            tokens: parse_pointer(pointer)?,
+        })
+    }
+
+    /// Read a document and return the values at the pointer in document
+    /// order. Only the matches are built, everything else is skipped, but
+    /// the whole document is still read and checked, including that nothing
+    /// follows it.
+    pub fn select<'a, E: Events<'a> + ?Sized>(
+        &self,
+        events: &mut E,
+    ) -> Result<Vec<Value>, E::Error> {
+        let mut out = Vec::new();
+        if let Some(first) = events.next_event()? {
+            walk(first, events, &self.tokens, &mut out)?;
+            finish(events)?;
+        }
+        Ok(out)
+    }
+}
+
+fn walk<'a, E: Events<'a> + ?Sized>(
+    first: Event<'a>,
+    events: &mut E,
+    tokens: &[String],
+    out: &mut Vec<Value>,
+) -> Result<(), E::Error> {
+    let Some((token, rest)) = tokens.split_first() else {
+        out.push(build(first, events)?);
+        return Ok(());
+    };
+    let any = token == "*";
+
+    match first {
+        Event::StartMap => {
+            while let Some(Event::Key(key)) = events.next_event()? {
+                let Some(value) = events.next_event()? else {
+                    break;
+                };
+                if any || *token == key {
+                    walk(value, events, rest, out)?;
+                } else {
+                    skip(value, events)?;
+                }
+            }
+        }
+        Event::StartList => {
+            let index = parse_index(token);
+            let mut idx = 0;
+            while let Some(value) = item(events, &Event::EndList)? {
+                if any || index == Some(idx) {
+                    walk(value, events, rest, out)?;
+                } else {
+                    skip(value, events)?;
+                }
+                idx += 1;
+            }
+        }
+        Event::StartDict => {
+            while let Some(key) = item(events, &Event::EndDict)? {
+                let matches = any || matches!(&key, Event::String(key) if key == token);
+                skip(key, events)?;
+                let Some(value) = events.next_event()? else {
+                    break;
+                };
+                if matches {
+                    walk(value, events, rest, out)?;
+                } else {
+                    skip(value, events)?;
+                }
+            }
+        }
+        // Scalars have nothing below them.
+        _ => {}
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum JsonError {
+    UnexpectedEof,
+    Syntax {
+        offset: usize,
+        expected: &'static str,
+    },
+    /// An unknown escape sequence or a lone surrogate.
+    InvalidEscape {
+        offset: usize,
+    },
+    /// Input left after the document.
+    TrailingCharacters {
+        offset: usize,
+    },
+    Limit(LimitError),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::UnexpectedEof => write!(f, "unexpected end of input"),
+            JsonError::Syntax { offset, expected } => {
+                write!(f, "expected {} at offset {}", expected, offset)
+            }
+            JsonError::InvalidEscape { offset } => {
+                write!(f, "invalid escape at offset {}", offset)
+            }
+            JsonError::TrailingCharacters { offset } => {
+                write!(f, "trailing characters at offset {}", offset)
+            }
+            JsonError::Limit(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for JsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JsonError::Limit(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<LimitError> for JsonError {
+    fn from(err: LimitError) -> Self {
+        JsonError::Limit(err)
+    }
+}
+
+/// Reads a JSON document as [`Event`]s.
+///
+/// Integers become `U64`, or `I64` when negative, and floats and integers
+/// out of range `F64`. Strings without escapes are borrowed from the input.
+/// Like [`Decoder`](crate::bin::Decoder), the document is bounded by
+/// [`Limits`].
+///
+/// The whole document has to be in memory as a `&str`, which is what lets
+/// strings be borrowed; read an `io::Read` source into a `String` first.
+/// The tree is still never built unless asked for, so a [`Selector`] over a
+/// large document only holds the input and the selected values.
+#[derive(Debug, Clone)]
+pub struct JsonEvents<'a> {
+    input: &'a str,
+    pos: usize,
+    limits: Limits,
+    nodes: usize,
+    stack: Vec<Container>,
+    state: State,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    List,
+    Map { keys: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Value,
+    /// Just after `[`.
+    FirstItem,
+    /// Just after `{`.
+    FirstKey,
+    /// After an item, expecting a comma or the end of the container.
+    Next,
+    Done,
+}
+
+impl<'a> JsonEvents<'a> {
+    pub fn new(input: &'a str) -> JsonEvents<'a> {
+        JsonEvents {
+            input,
+            pos: 0,
+            limits: Limits::default(),
+            nodes: 0,
+            stack: Vec::new(),
+            state: State::Value,
+        }
+    }
+
+    /// Limits for the document. Defaults to [`Limits::default`].
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The number of bytes read so far.
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8, expected: &'static str) -> Result<(), JsonError> {
+        match self.peek() {
+            Some(b) if b == byte => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(_) => Err(self.syntax(expected)),
+            None => Err(JsonError::UnexpectedEof),
+        }
+    }
+
+    fn syntax(&self, expected: &'static str) -> JsonError {
+        JsonError::Syntax {
+            offset: self.pos,
+            expected,
+        }
+    }
+
+    fn after_value(&mut self) {
+        self.state = if self.stack.is_empty() {
+            State::Done
+        } else {
+            State::Next
+        };
+    }
+
+    fn end(&mut self) -> Event<'a> {
+        self.pos += 1;
+        let container = self.stack.pop();
+        self.after_value();
+        match container {
+            Some(Container::Map { .. }) => Event::EndMap,
+            _ => Event::EndList,
+        }
+    }
+
+    fn key(&mut self) -> Result<Event<'a>, JsonError> {
+        if self.peek().is_none() {
+            return Err(JsonError::UnexpectedEof);
+        }
+        if self.peek() != Some(b'"') {
+            return Err(self.syntax("a string key"));
+        }
+        if let Some(Container::Map { keys }) = self.stack.last_mut() {
+            *keys += 1;
+            self.limits.check_map_keys(*keys)?;
+        }
+        let key = self.string()?;
+        self.whitespace();
+        self.expect(b':', "':'")?;
+        self.state = State::Value;
+        Ok(Event::Key(key))
+    }
+
+    fn value(&mut self) -> Result<Event<'a>, JsonError> {
+        self.nodes += 1;
+        self.limits.check_nodes(self.nodes)?;
+        self.limits.check_depth(self.stack.len())?;
+
+        let event = match self.peek() {
+            None => return Err(JsonError::UnexpectedEof),
+            Some(b'{') => {
+                self.pos += 1;
+                self.stack.push(Container::Map { keys: 0 });
+                self.state = State::FirstKey;
+                return Ok(Event::StartMap);
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                self.stack.push(Container::List);
+                self.state = State::FirstItem;
+                return Ok(Event::StartList);
+            }
+            Some(b'"') => Event::String(self.string()?),
+            Some(b't') => self.literal("true", Event::Bool(true))?,
+            Some(b'f') => self.literal("false", Event::Bool(false))?,
+            Some(b'n') => self.literal("null", Event::None)?,
+            Some(b'-' | b'0'..=b'9') => Event::Number(self.number()?),
+            Some(_) => return Err(self.syntax("a value")),
+        };
+        self.after_value();
+        Ok(event)
+    }
+
+    fn literal(&mut self, word: &'static str, event: Event<'a>) -> Result<Event<'a>, JsonError> {
+        if self.input[self.pos..].starts_with(word) {
+            self.pos += word.len();
+            Ok(event)
+        } else {
+            Err(self.syntax(word))
+        }
+    }
+
+    fn digits(&mut self) -> Result<(), JsonError> {
+        if !self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            return Err(self.syntax("a digit"));
+        }
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        Ok(())
+    }
+
+    fn number(&mut self) -> Result<Number, JsonError> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        if self.peek() == Some(b'0') {
+            self.pos += 1;
+        } else {
+            self.digits()?;
+        }
+        let mut float = false;
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            self.digits()?;
+            float = true;
+        }
+        if let Some(b'e' | b'E') = self.peek() {
+            self.pos += 1;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.pos += 1;
+            }
+            self.digits()?;
+            float = true;
+        }
+
+        let text = &self.input[start..self.pos];
+        if !float {
+            if let Ok(n) = text.parse::<u64>() {
+                return Ok(Number::U64(n));
+            }
+            if let Ok(n) = text.parse::<i64>() {
+                return Ok(Number::I64(n));
+            }
+        }
+        // The grammar above only lets valid floats through.
+        Ok(Number::from(text.parse::<f64>().unwrap_or(f64::NAN)))
+    }
+
+    /// A string, the opening quote not yet consumed.
+    fn string(&mut self) -> Result<Cow<'a, str>, JsonError> {
+        self.pos += 1;
+        let start = self.pos;
+        let run = self.run();
+        let value = match self.peek() {
+            Some(b'"') => {
+                self.pos += 1;
+                Cow::Borrowed(&self.input[start..run])
+            }
+            _ => {
+                let mut out = String::from(&self.input[start..run]);
+                loop {
+                    match self.peek() {
+                        Some(b'"') => {
+                            self.pos += 1;
+                            break;
+                        }
+                        Some(b'\\') => out.push(self.escape()?),
+                        Some(_) => return Err(self.syntax("an escaped control character")),
+                        None => return Err(JsonError::UnexpectedEof),
+                    }
+                    let start = self.pos;
+                    out.push_str(&self.input[start..self.run()]);
+                }
+                Cow::Owned(out)
+            }
+        };
+        self.limits.check_string_len(value.len())?;
+        Ok(value)
+    }
+
+    /// Skip characters needing no escaping, returning where they end.
+    fn run(&mut self) -> usize {
+        while self
+            .peek()
+            .is_some_and(|b| b != b'"' && b != b'\\' && b >= 0x20)
+        {
+            self.pos += 1;
+        }
+        self.pos
+    }
+
+    fn escape(&mut self) -> Result<char, JsonError> {
+        let offset = self.pos;
+        self.pos += 1;
+        let c = match self.peek().ok_or(JsonError::UnexpectedEof)? {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                self.pos += 1;
+                let high = self.hex(offset)?;
+                let code = match high {
+                    0xd800..=0xdbff => {
+                        if !self.input[self.pos..].starts_with("\\u") {
+                            return Err(JsonError::InvalidEscape { offset });
+                        }
+                        self.pos += 2;
+                        match self.hex(offset)? {
+                            low @ 0xdc00..=0xdfff => {
+                                0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                            }
+                            _ => return Err(JsonError::InvalidEscape { offset }),
+                        }
+                    }
+                    code => code,
+                };
+                return char::from_u32(code).ok_or(JsonError::InvalidEscape { offset });
+            }
+            _ => return Err(JsonError::InvalidEscape { offset }),
+        };
+        self.pos += 1;
+        Ok(c)
+    }
+
+    fn hex(&mut self, offset: usize) -> Result<u32, JsonError> {
+        let digits = self
+            .input
+            .get(self.pos..self.pos + 4)
+            .ok_or(JsonError::UnexpectedEof)?;
+        if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(JsonError::InvalidEscape { offset });
+        }
+        self.pos += 4;
+        u32::from_str_radix(digits, 16).map_err(|_| JsonError::InvalidEscape { offset })
+    }
+}
+
+impl<'a> Events<'a> for JsonEvents<'a> {
+    type Error = JsonError;
+
+    fn next_event(&mut self) -> Result<Option<Event<'a>>, JsonError> {
+        self.whitespace();
+        let event = match self.state {
+            State::Done if self.pos < self.input.len() => {
+                return Err(JsonError::TrailingCharacters { offset: self.pos })
+            }
+            State::Done => return Ok(None),
+            State::Value => self.value()?,
+            State::FirstItem if self.peek() == Some(b']') => self.end(),
+            State::FirstItem => self.value()?,
+            State::FirstKey if self.peek() == Some(b'}') => self.end(),
+            State::FirstKey => self.key()?,
+            State::Next => match (self.peek(), self.stack.last()) {
+                (Some(b','), Some(container)) => {
+                    let container = *container;
+                    self.pos += 1;
+                    self.whitespace();
+                    match container {
+                        Container::List => self.value()?,
+                        Container::Map { .. } => self.key()?,
+                    }
+                }
+                (Some(b']'), Some(Container::List)) | (Some(b'}'), Some(Container::Map { .. })) => {
+                    self.end()
+                }
+                (None, _) => return Err(JsonError::UnexpectedEof),
+                _ => return Err(self.syntax("',' or the end of the container")),
+            },
+        };
+        Ok(Some(event))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bin;
+
+    fn events(input: &str) -> Result<Vec<Event<'_>>, JsonError> {
+        let mut reader = JsonEvents::new(input);
+        let mut out = Vec::new();
+        while let Some(event) = reader.next_event()? {
+            out.push(event);
+        }
+        Ok(out)
+    }
+
+    #[test]
+    fn json_events() {
+        assert_eq!(
+            events(r#" {"a": [1, -2, 0.5, "xé\n"], "b": {}, "c": [true, null]} "#).unwrap(),
+            [
+                Event::StartMap,
+                Event::Key("a".into()),
+                Event::StartList,
+                Event::Number(Number::U64(1)),
+                Event::Number(Number::I64(-2)),
+                Event::Number(Number::from(0.5f64)),
+                Event::String("x\u{e9}\n".into()),
+                Event::EndList,
+                Event::Key("b".into()),
+                Event::StartMap,
+                Event::EndMap,
+                Event::Key("c".into()),
+                Event::StartList,
+                Event::Bool(true),
+                Event::None,
+                Event::EndList,
+                Event::EndMap,
+            ]
+        );
+        assert_eq!(
+            events(r#""😀""#).unwrap(),
+            [Event::String("\u{1f600}".into())]
+        );
+    }
+
+    #[test]
+    fn json_errors() {
+        for (input, offset) in [("[1,]", 3), ("{\"a\" 1}", 5), ("[01]", 2), ("tru", 0)] {
+            assert!(
+                matches!(events(input), Err(JsonError::Syntax { offset: o, .. }) if o == offset),
+                "{}",
+                input
+            );
+        }
+        assert!(matches!(events("[1"), Err(JsonError::UnexpectedEof)));
+        assert!(matches!(
+            events("1 2"),
+            Err(JsonError::TrailingCharacters { offset: 2 })
+        ));
+        assert!(matches!(
+            read_value(&mut JsonEvents::new("{\"a\": 1} x")),
+            Err(JsonError::TrailingCharacters { offset: 9 })
+        ));
+        assert!(matches!(
+            Selector::new("/a")
+                .unwrap()
+                .select(&mut JsonEvents::new("{\"a\": 1}]")),
+            Err(JsonError::TrailingCharacters { offset: 8 })
+        ));
+        assert!(matches!(
+            events(r#""\ud800""#),
+            Err(JsonError::InvalidEscape { offset: 1 })
+        ));
+        let mut deep = JsonEvents::new("[[[1]]]").with_limits(Limits::default().with_max_depth(2));
+        assert!(matches!(
+            read_value(&mut deep),
+            Err(JsonError::Limit(LimitError::Depth(2)))
+        ));
+    }
+
+    #[test]
+    fn select() {
+        let input = r#"{"items": [{"id": 1, "tags": ["a"]}, {"id": 2}, {"name": "x"}], "id": 0}"#;
+        let ids = Selector::new("/items/*/id").unwrap();
+        assert_eq!(
+            ids.select(&mut JsonEvents::new(input)).unwrap(),
+            [Value::from(1u64), Value::from(2u64)]
+        );
+        let first = Selector::new("/items/0").unwrap();
+        let selected = first.select(&mut JsonEvents::new(input)).unwrap();
+        assert_eq!(selected[0]["tags"][0], Value::from("a"));
+        assert!(Selector::new("items").is_err());
+
+        let value = read_value(&mut JsonEvents::new(input)).unwrap().unwrap();
+        let bytes = bin::to_vec(&value);
+        let mut decoder = bin::Decoder::new(&bytes).unwrap();
+        assert_eq!(
+            ids.select(&mut decoder.events()).unwrap(),
+            [Value::from(1u64), Value::from(2u64)]
+        );
+        decoder.finish().unwrap();
+    }
+}
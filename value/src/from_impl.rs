@@ -10,12 +10,13 @@ use std::{collections::BTreeMap, string::String};
 
 use crate::{Number, Value};
 
-#[cfg(feature = "std")]
 mod try_from {
+    #[cfg(not(feature = "std"))]
+    use alloc::{string::String, vec::Vec};
+    pub use core::convert::TryFrom;
+    use core::fmt;
 
     use crate::{Map, Number, Value};
-    pub use std::convert::TryFrom;
-    use std::fmt;
 
     macro_rules! from_impl {
         ($type: ty, $method: ident, $as: ident, $as_mut: ident) => {
@@ -110,7 +111,6 @@ mod try_from {
     }
 }
 
-#[cfg(feature = "std")]
 pub use self::try_from::FromValueErr;
 
 macro_rules! from_impl {
@@ -132,6 +132,7 @@ macro_rules! from_impl {
 }
 
 from_impl!(bool, Bool);
+from_impl!(char, Char);
 from_impl!(Number, Number);
 from_impl!(String, String);
 from_impl!(Vec<u8>, Bytes);
@@ -176,6 +177,11 @@ impl From<f64> for Value {
     }
 }
 
+#[cfg(feature = "datetime")]
+from_impl!(chrono::NaiveDate, Date);
+#[cfg(feature = "datetime")]
+from_impl!(chrono::NaiveDateTime, DateTime);
+
 impl AsRef<Value> for Value {
     fn as_ref(&self) -> &Value {
         self
@@ -0,0 +1,96 @@
+/// The variants of `Value` a generator is allowed to produce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Bool,
+    Number,
+    Char,
+    String,
+    List,
+    Map,
+    /// Maps keyed by numbers.
+    Dict,
+    Bytes,
+    None,
+    #[cfg(feature = "datetime")]
+    Date,
+    #[cfg(feature = "datetime")]
+    DateTime,
+}
+
+impl Kind {
+    pub const ALL: &'static [Kind] = &[
+        Kind::Bool,
+        Kind::Number,
+        Kind::Char,
+        Kind::String,
+        Kind::List,
+        Kind::Map,
+        Kind::Dict,
+        Kind::Bytes,
+        Kind::None,
+        #[cfg(feature = "datetime")]
+        Kind::Date,
+        #[cfg(feature = "datetime")]
+        Kind::DateTime,
+    ];
+
+    pub fn is_container(&self) -> bool {
+        matches!(self, Kind::List | Kind::Map | Kind::Dict)
+    }
+}
+
+/// Shape of the values produced by the `arbitrary` and `proptest` generators.
+///
+/// Floats are always finite, so generated values compare equal to themselves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenOptions {
+    pub(crate) max_depth: usize,
+    pub(crate) max_breadth: usize,
+    pub(crate) kinds: Vec<Kind>,
+}
+
+impl Default for GenOptions {
+    fn default() -> Self {
+        GenOptions {
+            max_depth: 4,
+            max_breadth: 8,
+            kinds: Kind::ALL.to_vec(),
+        }
+    }
+}
+
+impl GenOptions {
+    /// Maximum nesting of lists and maps.
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Maximum number of elements in a list, map or byte string.
+    pub fn with_max_breadth(mut self, breadth: usize) -> Self {
+        self.max_breadth = breadth;
+        self
+    }
+
+    pub fn with_kinds(mut self, kinds: impl IntoIterator<Item = Kind>) -> Self {
+        self.kinds = kinds.into_iter().collect();
+        self
+    }
+
+    pub fn without_kind(mut self, kind: Kind) -> Self {
+        self.kinds.retain(|k| *k != kind);
+        self
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn max_breadth(&self) -> usize {
+        self.max_breadth
+    }
+
+    pub fn kinds(&self) -> &[Kind] {
+        &self.kinds
+    }
+}
@@ -3,25 +3,98 @@
 #[cfg(not(feature = "std"))]
 extern crate alloc;
 
-#[cfg(feature = "std")]
 mod macros;
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impl;
+pub mod bin;
+pub mod compact;
 #[cfg(feature = "serde")]
 pub mod de;
+mod dict;
+mod eq;
+pub mod events;
 mod from_impl;
+#[cfg(any(feature = "arbitrary", feature = "proptest"))]
+mod gen_options;
 mod index;
-mod map;
+mod limits;
+pub mod map;
 mod merge;
+#[cfg(feature = "ndjson")]
+pub mod ndjson;
 mod number;
+mod path;
+#[cfg(feature = "imbl")]
+pub mod persistent;
+pub mod pointer;
+#[cfg(feature = "proptest")]
+mod proptest_impl;
+pub mod redact;
+pub mod refs;
+#[cfg(feature = "rkyv")]
+mod rkyv_impl;
 #[cfg(feature = "serde")]
 pub mod ser;
+#[cfg(feature = "slog")]
+mod slog_impl;
+pub mod template;
+#[cfg(feature = "toml")]
+pub mod toml;
+#[cfg(feature = "valuable")]
+mod valuable_impl;
 // mod typed;
 mod value;
+#[cfg(feature = "yaml")]
+pub mod yaml;
 
-pub use self::{index::Index, map::*, merge::*, number::*, value::*};
+pub use self::{
+    compact::CompactValue,
+    dict::{Dict, KeyError},
+    eq::{Difference, EqOptions},
+    index::Index,
+    limits::*,
+    map::Map,
+    merge::*,
+    number::*,
+    path::*,
+    redact::{Redactor, REDACTED},
+    template::RenderError,
+    value::*,
+};
 
 #[cfg(feature = "serde")]
-pub use self::{de::from_value, ser::to_value};
+pub use self::{
+    de::{from_value, from_value_ref, from_value_ref_into},
+    ser::{
+        merge_serialize, to_value, to_value_into, to_value_with, ByteRepr, EnumTagging,
+        SerializerOptions,
+    },
+};
+
+#[cfg(feature = "slog")]
+pub use self::slog_impl::RedactedMap;
+
+#[cfg(any(feature = "arbitrary", feature = "proptest"))]
+pub use self::gen_options::{GenOptions, Kind};
+
+#[cfg(feature = "imbl")]
+pub use self::persistent::PValue;
+
+#[cfg(feature = "rkyv")]
+pub use self::{
+    map::ArchivedMap,
+    rkyv_impl::{from_archive, from_archive_unchecked, to_archive, ArchiveError},
+};
+
+#[cfg(feature = "toml")]
+pub use self::toml::{from_toml_str, to_toml_string, TomlError};
+
+#[cfg(feature = "yaml")]
+pub use self::yaml::{from_yaml_str, from_yaml_str_with_limits, to_yaml_string, YamlError};
+
+#[cfg(feature = "tracing")]
+pub use self::valuable_impl::{record_field, record_fields};
 
 #[cfg(not(feature = "serde"))]
 pub fn to_value<T: Into<Value>>(value: T) -> Value {
@@ -29,3 +102,136 @@ pub fn to_value<T: Into<Value>>(value: T) -> Value {
 }
 
 pub use from_impl::*;
+
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(not(feature = "std"))]
+    pub use alloc::vec;
+    #[cfg(feature = "std")]
+    pub use std::vec;
+
+    use crate::{Map, Value};
+
+    #[cfg(feature = "serde")]
+    pub fn to_value<T: serde::Serialize + ?Sized>(value: &T) -> Value {
+        crate::to_value(value).unwrap()
+    }
+
+    #[cfg(not(feature = "serde"))]
+    pub fn to_value<T: Clone + Into<Value>>(value: &T) -> Value {
+        value.clone().into()
+    }
+
+    /// A literal in `value!`, which keeps its exact type.
+    pub trait Literal {
+        fn into_value(self) -> Value;
+    }
+
+    macro_rules! literal_impl {
+        ($($ty:ty),*) => {
+            $(
+                impl Literal for $ty {
+                    fn into_value(self) -> Value {
+                        Value::from(self)
+                    }
+                }
+            )*
+        };
+    }
+
+    literal_impl!(bool, char, u8, u16, u32, u64, i8, i16, i32, i64, f32, f64, &str);
+
+    // Stored the way serde serializes them: as the 64 bit integer of the same
+    // sign. 128 bit literals have to fit in 64 bits.
+    macro_rules! wide_literal_impl {
+        ($($ty:ty => $to:ty),*) => {
+            $(
+                impl Literal for $ty {
+                    fn into_value(self) -> Value {
+                        match <$to>::try_from(self) {
+                            Ok(v) => Value::from(v),
+                            Err(_) => panic!("literal {} does not fit in {}", self, stringify!($to)),
+                        }
+                    }
+                }
+            )*
+        };
+    }
+
+    wide_literal_impl!(usize => u64, isize => i64, u128 => u64, i128 => i64);
+
+    impl<const N: usize> Literal for &[u8; N] {
+        fn into_value(self) -> Value {
+            Value::Bytes(self.to_vec())
+        }
+    }
+
+    pub fn literal<T: Literal>(literal: T) -> Value {
+        literal.into_value()
+    }
+
+    /// A `..base` entry in `value!`.
+    pub trait Spread {
+        fn spread_into(self, object: &mut Map);
+    }
+
+    impl Spread for Map {
+        fn spread_into(self, object: &mut Map) {
+            object.extend(self);
+        }
+    }
+
+    impl Spread for &Map {
+        fn spread_into(self, object: &mut Map) {
+            self.clone().spread_into(object)
+        }
+    }
+
+    impl Spread for Value {
+        fn spread_into(self, object: &mut Map) {
+            match self {
+                Value::Map(map) => map.spread_into(object),
+                other => panic!("cannot spread {:?} into a map", other),
+            }
+        }
+    }
+
+    impl Spread for &Value {
+        fn spread_into(self, object: &mut Map) {
+            self.clone().spread_into(object)
+        }
+    }
+
+    pub fn spread<T: Spread>(object: &mut Map, base: T) {
+        base.spread_into(object)
+    }
+
+    /// The failure message of `assert_value_eq!`.
+    pub struct Differences<'a>(pub &'a [crate::Difference]);
+
+    impl core::fmt::Display for Differences<'_> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            write!(f, "assertion `left semantic_eq right` failed")?;
+            for difference in self.0 {
+                write!(f, "\n  {}", difference)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "datetime")]
+    pub fn date(date: &str) -> Value {
+        match date.parse() {
+            Ok(date) => Value::Date(date),
+            Err(_) => panic!("invalid date {:?}", date),
+        }
+    }
+
+    #[cfg(feature = "datetime")]
+    pub fn datetime(datetime: &str) -> Value {
+        match datetime.parse() {
+            Ok(datetime) => Value::DateTime(datetime),
+            Err(_) => panic!("invalid datetime {:?}", datetime),
+        }
+    }
+}
@@ -0,0 +1,429 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec;
+use core::fmt;
+
+use crate::Value;
+
+/// Bounds on the size of a `Value` tree, used to reject untrusted input
+/// before it is materialized or walked recursively.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub(crate) max_depth: usize,
+    pub(crate) max_nodes: usize,
+    pub(crate) max_string_len: usize,
+    pub(crate) max_bytes_len: usize,
+    pub(crate) max_map_keys: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_depth: 128,
+            max_nodes: 1_000_000,
+            max_string_len: 16 * 1024 * 1024,
+            max_bytes_len: 16 * 1024 * 1024,
+            max_map_keys: 100_000,
+        }
+    }
+}
+
+impl Limits {
+    pub const fn unlimited() -> Limits {
+        Limits {
+            max_depth: usize::MAX,
+            max_nodes: usize::MAX,
+            max_string_len: usize::MAX,
+            max_bytes_len: usize::MAX,
+            max_map_keys: usize::MAX,
+        }
+    }
+
+    /// Maximum nesting of lists and maps. Scalars at the root have depth 0.
+    pub const fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Maximum number of values in the tree, containers included. The keys
+    /// of a [`Map`](crate::Map) aren't counted, the keys of a
+    /// [`Dict`](crate::Dict) are, as they are values themselves.
+    pub const fn with_max_nodes(mut self, nodes: usize) -> Self {
+        self.max_nodes = nodes;
+        self
+    }
+
+    /// Maximum length in bytes of strings and map keys.
+    pub const fn with_max_string_len(mut self, len: usize) -> Self {
+        self.max_string_len = len;
+        self
+    }
+
+    pub const fn with_max_bytes_len(mut self, len: usize) -> Self {
+        self.max_bytes_len = len;
+        self
+    }
+
+    /// Maximum number of keys in a single map.
+    pub const fn with_max_map_keys(mut self, keys: usize) -> Self {
+        self.max_map_keys = keys;
+        self
+    }
+
+    pub fn check_depth(&self, depth: usize) -> Result<(), LimitError> {
+        check(depth, self.max_depth, LimitError::Depth)
+    }
+
+    pub fn check_nodes(&self, nodes: usize) -> Result<(), LimitError> {
+        check(nodes, self.max_nodes, LimitError::Nodes)
+    }
+
+    pub fn check_string_len(&self, len: usize) -> Result<(), LimitError> {
+        check(len, self.max_string_len, LimitError::StringLength)
+    }
+
+    pub fn check_bytes_len(&self, len: usize) -> Result<(), LimitError> {
+        check(len, self.max_bytes_len, LimitError::BytesLength)
+    }
+
+    pub fn check_map_keys(&self, keys: usize) -> Result<(), LimitError> {
+        check(keys, self.max_map_keys, LimitError::MapKeys)
+    }
+}
+
+fn check(found: usize, max: usize, err: fn(usize) -> LimitError) -> Result<(), LimitError> {
+    if found > max {
+        Err(err(max))
+    } else {
+        Ok(())
+    }
+}
+
+/// The limit that was exceeded, carrying the configured maximum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitError {
+    Depth(usize),
+    Nodes(usize),
+    StringLength(usize),
+    BytesLength(usize),
+    MapKeys(usize),
+}
+
+impl fmt::Display for LimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitError::Depth(max) => write!(f, "maximum depth of {} exceeded", max),
+            LimitError::Nodes(max) => write!(f, "maximum of {} values exceeded", max),
+            LimitError::StringLength(max) => {
+                write!(f, "maximum string length of {} exceeded", max)
+            }
+            LimitError::BytesLength(max) => write!(f, "maximum bytes length of {} exceeded", max),
+            LimitError::MapKeys(max) => write!(f, "maximum of {} map keys exceeded", max),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LimitError {}
+
+impl Value {
+    /// Verify that the value stays within `limits`. The tree is walked without
+    /// recursion, so this is safe to call on arbitrarily deep values.
+    pub fn check_limits(&self, limits: &Limits) -> Result<(), LimitError> {
+        let mut nodes = 0;
+        let mut stack = vec![(self, 0)];
+
+        while let Some((value, depth)) = stack.pop() {
+            nodes += 1;
+            limits.check_nodes(nodes)?;
+            limits.check_depth(depth)?;
+
+            match value {
+                Value::String(s) => limits.check_string_len(s.len())?,
+                Value::Bytes(b) => limits.check_bytes_len(b.len())?,
+                Value::List(list) => {
+                    stack.extend(list.iter().map(|v| (v, depth + 1)));
+                }
+                Value::Map(map) => {
+                    limits.check_map_keys(map.len())?;
+                    for (k, v) in map.iter() {
+                        limits.check_string_len(k.len())?;
+                        stack.push((v, depth + 1));
+                    }
+                }
+                Value::Dict(dict) => {
+                    limits.check_map_keys(dict.len())?;
+                    for (k, v) in dict.iter() {
+                        stack.push((k, depth + 1));
+                        stack.push((v, depth + 1));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+mod seed {
+    #[cfg(not(feature = "std"))]
+    use alloc::{string::String, vec::Vec};
+    use core::{cell::Cell, fmt};
+    use serde::de::{self, DeserializeSeed, Visitor};
+
+    use super::{LimitError, Limits};
+    use crate::{de::ValueVisitor, dict::MapBuilder, Value};
+
+    impl Limits {
+        /// Deserialize a `Value`, failing as soon as the input exceeds the limits.
+        pub fn deserialize_value<'de, D>(&self, deserializer: D) -> Result<Value, D::Error>
+        where
+            D: de::Deserializer<'de>,
+        {
+            let nodes = Cell::new(0);
+            LimitedSeed {
+                limits: self,
+                nodes: &nodes,
+                depth: 0,
+                counted: true,
+            }
+            .deserialize(deserializer)
+        }
+
+        /// A `DeserializeSeed` producing a `Value` within these limits.
+        pub fn seed(&self) -> LimitedValue<'_> {
+            LimitedValue(self)
+        }
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    pub struct LimitedValue<'a>(&'a Limits);
+
+    impl<'a, 'de> DeserializeSeed<'de> for LimitedValue<'a> {
+        type Value = Value;
+
+        fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+            self.0.deserialize_value(deserializer)
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    struct LimitedSeed<'a> {
+        limits: &'a Limits,
+        nodes: &'a Cell<usize>,
+        depth: usize,
+        /// Whether the value counts as a node when it is read.
+        counted: bool,
+    }
+
+    impl<'a> LimitedSeed<'a> {
+        /// The seed for the items of a container. Their depth is checked
+        /// when they are read, so empty containers count like in
+        /// [`Value::check_limits`].
+        fn enter(&self) -> LimitedSeed<'a> {
+            LimitedSeed {
+                depth: self.depth + 1,
+                counted: true,
+                ..*self
+            }
+        }
+
+        /// The seed for map keys, which are counted once it is known whether
+        /// they are the string keys of a `Map` or the values of a `Dict`.
+        fn key(&self) -> LimitedSeed<'a> {
+            LimitedSeed {
+                counted: false,
+                ..self.enter()
+            }
+        }
+
+        fn count<E: de::Error>(&self, nodes: usize) -> Result<(), E> {
+            self.nodes.set(self.nodes.get() + nodes);
+            self.check(self.limits.check_nodes(self.nodes.get()))
+        }
+
+        fn check<E: de::Error>(&self, ret: Result<(), LimitError>) -> Result<(), E> {
+            ret.map_err(E::custom)
+        }
+    }
+
+    impl<'a, 'de> DeserializeSeed<'de> for LimitedSeed<'a> {
+        type Value = Value;
+
+        fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+            if self.counted {
+                self.count(1)?;
+            }
+            self.check(self.limits.check_depth(self.depth))?;
+            deserializer.deserialize_any(self)
+        }
+    }
+
+    macro_rules! forward {
+        ($($method: ident: $ty: ty),*) => {
+            $(
+                fn $method<E: de::Error>(self, v: $ty) -> Result<Value, E> {
+                    ValueVisitor.$method(v)
+                }
+            )*
+        };
+    }
+
+    impl<'a, 'de> Visitor<'de> for LimitedSeed<'a> {
+        type Value = Value;
+
+        fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+            fmt.write_str("any value")
+        }
+
+        forward!(
+            visit_bool: bool,
+            visit_i8: i8,
+            visit_i16: i16,
+            visit_i32: i32,
+            visit_i64: i64,
+            visit_u8: u8,
+            visit_u16: u16,
+            visit_u32: u32,
+            visit_u64: u64,
+            visit_f32: f32,
+            visit_f64: f64,
+            visit_char: char
+        );
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+            self.check(self.limits.check_string_len(v.len()))?;
+            ValueVisitor.visit_str(v)
+        }
+
+        fn visit_string<E: de::Error>(self, v: String) -> Result<Value, E> {
+            self.check(self.limits.check_string_len(v.len()))?;
+            ValueVisitor.visit_string(v)
+        }
+
+        fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Value, E> {
+            self.check(self.limits.check_bytes_len(v.len()))?;
+            ValueVisitor.visit_bytes(v)
+        }
+
+        fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Value, E> {
+            self.check(self.limits.check_bytes_len(v.len()))?;
+            ValueVisitor.visit_byte_buf(v)
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+            Ok(Value::None)
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+            Ok(Value::None)
+        }
+
+        fn visit_some<D: de::Deserializer<'de>>(self, d: D) -> Result<Value, D::Error> {
+            d.deserialize_any(self)
+        }
+
+        fn visit_seq<V: de::SeqAccess<'de>>(self, mut seq: V) -> Result<Value, V::Error> {
+            let child = self.enter();
+            let mut values = Vec::new();
+            while let Some(elem) = seq.next_element_seed(child)? {
+                values.push(elem);
+            }
+            Ok(Value::List(values))
+        }
+
+        fn visit_map<V: de::MapAccess<'de>>(self, mut access: V) -> Result<Value, V::Error> {
+            let child = self.enter();
+            let mut map = MapBuilder::new();
+            let mut len = 0;
+            while let Some(key) = access.next_key_seed(self.key())? {
+                len += 1;
+                self.check(self.limits.check_map_keys(len))?;
+                // A dict counts all its keys, including the string keys
+                // read while it was still a map.
+                match (&map, &key) {
+                    (MapBuilder::Map(_), Value::String(_)) => {}
+                    (MapBuilder::Map(map), _) => self.count(map.len() + 1)?,
+                    (MapBuilder::Dict(_), _) => self.count(1)?,
+                }
+                let value = access.next_value_seed(child)?;
+                map.insert(key, value);
+            }
+            Ok(map.into_value())
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+pub use self::seed::LimitedValue;
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    use super::*;
+
+    fn nested(depth: usize) -> Value {
+        let mut value = Value::None;
+        for _ in 0..depth {
+            value = Value::List(vec![value]);
+        }
+        value
+    }
+
+    #[test]
+    fn check_limits() {
+        let limits = Limits::default().with_max_depth(3).with_max_string_len(4);
+
+        assert_eq!(nested(3).check_limits(&limits), Ok(()));
+        assert_eq!(nested(4).check_limits(&limits), Err(LimitError::Depth(3)));
+        assert_eq!(
+            Value::from("hello").check_limits(&limits),
+            Err(LimitError::StringLength(4))
+        );
+        assert_eq!(
+            nested(3).check_limits(&Limits::default().with_max_nodes(3)),
+            Err(LimitError::Nodes(3))
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_with_limits() {
+        let limits = Limits::default().with_max_depth(2);
+
+        assert_eq!(limits.deserialize_value(nested(2)).unwrap(), nested(2));
+        assert!(limits.deserialize_value(nested(3)).is_err());
+
+        // An empty list at the maximum depth is accepted by both.
+        for (depth, ok) in [(2, true), (3, false)] {
+            let mut value = Value::List(vec![]);
+            for _ in 0..depth {
+                value = Value::List(vec![value]);
+            }
+            assert_eq!(value.check_limits(&limits).is_ok(), ok);
+            assert_eq!(limits.deserialize_value(value).is_ok(), ok);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn nodes_agree() {
+        let mut map = crate::Map::new();
+        map.insert("a", 1u8);
+        map.insert("b", 2u8);
+        let dict = [(Value::from("a"), 1u8), (Value::from(2u8), 2u8)]
+            .into_iter()
+            .collect::<crate::Dict>();
+
+        for (value, nodes) in [(Value::Map(map), 3), (Value::from(dict), 5)] {
+            for (max, ok) in [(nodes, true), (nodes - 1, false)] {
+                let limits = Limits::default().with_max_nodes(max);
+                assert_eq!(value.check_limits(&limits).is_ok(), ok);
+                assert_eq!(limits.deserialize_value(value.clone()).is_ok(), ok);
+            }
+        }
+    }
+}
@@ -19,7 +19,8 @@
 ///
 /// Variables or expressions can be interpolated into the JSON literal. Any type
 /// interpolated into an array element or object value must implement Serde's
-/// `Serialize` trait, while any type interpolated into a object key must
+/// `Serialize` trait (`Clone + Into<Value>` without the `serde` feature),
+/// while any type interpolated into a object key must
 /// implement `Into<String>`. If the `Serialize` implementation of the
 /// interpolated type decides to fail, or if the interpolated type contains a
 /// map with non-string keys, the `json!` macro will panic.
@@ -51,6 +52,34 @@
 ///     "comma -->",
 /// ]);
 /// ```
+///
+/// Literals keep their exact type: `b"..."` is [`Value::Bytes`], `'c'` is
+/// [`Value::Char`] and a suffixed number like `5u8` or `1.5f32` keeps its
+/// width. `usize` and `isize` become 64 bit numbers, as do `u128` and `i128`
+/// literals, which panic if they don't fit. With the `datetime` feature `@date("2024-02-29")` and
+/// `@datetime("2024-02-29T10:30:00")` are [`Value::Date`] and
+/// [`Value::DateTime`], panicking on invalid input. `..base` inserts the
+/// entries of another map, `Map` or `Value`, where it appears, so later
+/// entries override it.
+///
+/// ```
+/// # use value::{value, Number, Value};
+/// #
+/// let base = value!({ "retries": 3, "verbose": false });
+/// let value = value!({
+///     "magic": b"VB",
+///     "separator": ',',
+///     "version": 1u8,
+///     ..base,
+///     "verbose": true,
+/// });
+///
+/// assert_eq!(value["magic"], Value::Bytes(b"VB".to_vec()));
+/// assert_eq!(value["separator"], Value::Char(','));
+/// assert_eq!(value["version"], Value::Number(Number::U8(1)));
+/// assert_eq!(value["retries"], Value::Number(Number::I32(3)));
+/// assert_eq!(value["verbose"], Value::Bool(true));
+/// ```
 #[macro_export(local_inner_macros)]
 macro_rules! value {
     // Hide distracting implementation details from the generated rustdoc.
@@ -94,6 +123,16 @@ macro_rules! value_internal {
         value_internal!(@array [$($elems,)* value_internal!(false)] $($rest)*)
     };
 
+    // Next element is a date.
+    (@array [$($elems:expr,)*] @date ($date:expr) $($rest:tt)*) => {
+        value_internal!(@array [$($elems,)* value_internal!(@date ($date))] $($rest)*)
+    };
+
+    // Next element is a date and time.
+    (@array [$($elems:expr,)*] @datetime ($datetime:expr) $($rest:tt)*) => {
+        value_internal!(@array [$($elems,)* value_internal!(@datetime ($datetime))] $($rest)*)
+    };
+
     // Next element is an array.
     (@array [$($elems:expr,)*] [$($array:tt)*] $($rest:tt)*) => {
         value_internal!(@array [$($elems,)* value_internal!([$($array)*])] $($rest)*)
@@ -104,16 +143,41 @@ macro_rules! value_internal {
         value_internal!(@array [$($elems,)* value_internal!({$($map)*})] $($rest)*)
     };
 
+    // Next element starts with a minus. The `literal` fragment below would
+    // fail hard on `-x`, so negative numbers are parsed as expressions.
+    (@array [$($elems:expr,)*] - $($rest:tt)*) => {
+        value_internal!(@array_expr [$($elems,)*] - $($rest)*)
+    };
+
+    // Next element is a literal followed by comma.
+    (@array [$($elems:expr,)*] $next:literal, $($rest:tt)*) => {
+        value_internal!(@array [$($elems,)* $crate::__private::literal($next),] $($rest)*)
+    };
+
+    // Last element is a literal with no trailing comma.
+    (@array [$($elems:expr,)*] $last:literal) => {
+        value_internal!(@array [$($elems,)* $crate::__private::literal($last)])
+    };
+
+    (@array [$($elems:expr,)*] $($rest:tt)+) => {
+        value_internal!(@array_expr [$($elems,)*] $($rest)+)
+    };
+
     // Next element is an expression followed by comma.
-    (@array [$($elems:expr,)*] $next:expr, $($rest:tt)*) => {
+    (@array_expr [$($elems:expr,)*] $next:expr, $($rest:tt)*) => {
         value_internal!(@array [$($elems,)* value_internal!($next),] $($rest)*)
     };
 
     // Last element is an expression with no trailing comma.
-    (@array [$($elems:expr,)*] $last:expr) => {
+    (@array_expr [$($elems:expr,)*] $last:expr) => {
         value_internal!(@array [$($elems,)* value_internal!($last)])
     };
 
+    // Unexpected token after an expression.
+    (@array_expr [$($elems:expr,)*] $unexpected:tt $($rest:tt)*) => {
+        value_unexpected!($unexpected)
+    };
+
     // Comma after the most recent element.
     (@array [$($elems:expr),*] , $($rest:tt)*) => {
         value_internal!(@array [$($elems,)*] $($rest)*)
@@ -153,6 +217,17 @@ macro_rules! value_internal {
         let _ = $object.insert(($($key)+), $value);
     };
 
+    // Spread another map followed by comma.
+    (@object $object:ident () (.. $base:expr , $($rest:tt)*) $copy:tt) => {
+        $crate::__private::spread(&mut $object, $base);
+        value_internal!(@object $object () ($($rest)*) ($($rest)*));
+    };
+
+    // Spread another map last, without trailing comma.
+    (@object $object:ident () (.. $base:expr) $copy:tt) => {
+        $crate::__private::spread(&mut $object, $base);
+    };
+
     // Next value is `null`.
     (@object $object:ident ($($key:tt)+) (: null $($rest:tt)*) $copy:tt) => {
         value_internal!(@object $object [$($key)+] (value_internal!(null)) $($rest)*);
@@ -168,6 +243,16 @@ macro_rules! value_internal {
         value_internal!(@object $object [$($key)+] (value_internal!(false)) $($rest)*);
     };
 
+    // Next value is a date.
+    (@object $object:ident ($($key:tt)+) (: @date ($date:expr) $($rest:tt)*) $copy:tt) => {
+        value_internal!(@object $object [$($key)+] (value_internal!(@date ($date))) $($rest)*);
+    };
+
+    // Next value is a date and time.
+    (@object $object:ident ($($key:tt)+) (: @datetime ($datetime:expr) $($rest:tt)*) $copy:tt) => {
+        value_internal!(@object $object [$($key)+] (value_internal!(@datetime ($datetime))) $($rest)*);
+    };
+
     // Next value is an array.
     (@object $object:ident ($($key:tt)+) (: [$($array:tt)*] $($rest:tt)*) $copy:tt) => {
         value_internal!(@object $object [$($key)+] (value_internal!([$($array)*])) $($rest)*);
@@ -178,16 +263,40 @@ macro_rules! value_internal {
         value_internal!(@object $object [$($key)+] (value_internal!({$($map)*})) $($rest)*);
     };
 
+    // Next value starts with a minus, see the array rule above.
+    (@object $object:ident ($($key:tt)+) (: - $($rest:tt)*) $copy:tt) => {
+        value_internal!(@object_expr $object ($($key)+) (: - $($rest)*) $copy);
+    };
+
+    // Next value is a literal followed by comma.
+    (@object $object:ident ($($key:tt)+) (: $value:literal , $($rest:tt)*) $copy:tt) => {
+        value_internal!(@object $object [$($key)+] ($crate::__private::literal($value)) , $($rest)*);
+    };
+
+    // Last value is a literal with no trailing comma.
+    (@object $object:ident ($($key:tt)+) (: $value:literal) $copy:tt) => {
+        value_internal!(@object $object [$($key)+] ($crate::__private::literal($value)));
+    };
+
+    (@object $object:ident ($($key:tt)+) (: $($rest:tt)+) $copy:tt) => {
+        value_internal!(@object_expr $object ($($key)+) (: $($rest)+) $copy);
+    };
+
     // Next value is an expression followed by comma.
-    (@object $object:ident ($($key:tt)+) (: $value:expr , $($rest:tt)*) $copy:tt) => {
+    (@object_expr $object:ident ($($key:tt)+) (: $value:expr , $($rest:tt)*) $copy:tt) => {
         value_internal!(@object $object [$($key)+] (value_internal!($value)) , $($rest)*);
     };
 
     // Last value is an expression with no trailing comma.
-    (@object $object:ident ($($key:tt)+) (: $value:expr) $copy:tt) => {
+    (@object_expr $object:ident ($($key:tt)+) (: $value:expr) $copy:tt) => {
         value_internal!(@object $object [$($key)+] (value_internal!($value)));
     };
 
+    // Unexpected token after an expression.
+    (@object_expr $object:ident ($($key:tt)+) (: $($unexpected:tt)+) $copy:tt) => {
+        value_expect_expr_comma!($($unexpected)+);
+    };
+
     // Missing value for last entry. Trigger a reasonable error message.
     (@object $object:ident ($($key:tt)+) (:) $copy:tt) => {
         // "unexpected end of macro invocation"
@@ -236,7 +345,7 @@ macro_rules! value_internal {
     //////////////////////////////////////////////////////////////////////////
 
     (null) => {
-        $crate::Value::Null
+        $crate::Value::None
     };
 
     (true) => {
@@ -247,6 +356,14 @@ macro_rules! value_internal {
         $crate::Value::Bool(false)
     };
 
+    (@date ($date:expr)) => {
+        $crate::__private::date($date)
+    };
+
+    (@datetime ($datetime:expr)) => {
+        $crate::__private::datetime($datetime)
+    };
+
     ([]) => {
         $crate::Value::List(value_internal_vec![])
     };
@@ -267,10 +384,55 @@ macro_rules! value_internal {
         })
     };
 
+    // Negative literals keep their type like the literal rule below.
+    (- $lit:literal) => {
+        $crate::__private::literal(- $lit)
+    };
+
+    // Other negative expressions, which the literal rule can't look at.
+    (- $($tt:tt)+) => {
+        $crate::__private::to_value(&(- $($tt)+))
+    };
+
+    // Literals keep their exact type instead of going through Serialize.
+    ($lit:literal) => {
+        $crate::__private::literal($lit)
+    };
+
     // Any Serialize type: numbers, strings, struct literals, variables etc.
+    // Without the serde feature any `Clone + Into<Value>` type.
     // Must be below every other rule.
     ($other:expr) => {
-        $crate::to_value(&$other).unwrap()
+        $crate::__private::to_value(&$other)
+    };
+}
+
+/// Assert that two values are [`semantic_eq`](crate::Value::semantic_eq),
+/// listing every [`Difference`](crate::Difference) on failure.
+///
+/// Without options the comparison is strict, see
+/// [`EqOptions`](crate::EqOptions).
+///
+/// ```
+/// # use value::{assert_value_eq, value, EqOptions};
+/// #
+/// let options = EqOptions::default().with_ignore_number_width(true);
+/// assert_value_eq!(value!({ "id": 1u8 }), value!({ "id": 1 }), options);
+/// ```
+#[macro_export]
+macro_rules! assert_value_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_value_eq!($left, $right, $crate::EqOptions::default())
+    };
+    ($left:expr, $right:expr, $options:expr $(,)?) => {
+        match (&$left, &$right, &$options) {
+            (left, right, options) => {
+                let differences = left.differences(right, options);
+                if !differences.is_empty() {
+                    ::core::panic!("{}", $crate::__private::Differences(&differences));
+                }
+            }
+        }
     };
 }
 
@@ -281,7 +443,7 @@ macro_rules! value_internal {
 #[doc(hidden)]
 macro_rules! value_internal_vec {
     ($($content:tt)*) => {
-        vec![$($content)*]
+        $crate::__private::vec![$($content)*]
     };
 }
 
@@ -296,3 +458,87 @@ macro_rules! value_unexpected {
 macro_rules! value_expect_expr_comma {
     ($e:expr , $($tt:tt)*) => {};
 }
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::{string::String, vec};
+
+    use crate::{Map, Number, Value};
+
+    #[test]
+    fn literals() {
+        let value = value!([b"ab", 'c', 5u8, -5i16, 1.5f32, 2, -3, "s", 1 + 1]);
+        assert_eq!(
+            value,
+            Value::List(vec![
+                Value::Bytes(vec![b'a', b'b']),
+                Value::Char('c'),
+                Value::Number(Number::U8(5)),
+                Value::Number(Number::I16(-5)),
+                Value::Number(Number::from(1.5f32)),
+                Value::Number(Number::I32(2)),
+                Value::Number(Number::I32(-3)),
+                Value::String(String::from("s")),
+                Value::Number(Number::I32(2)),
+            ])
+        );
+        assert_eq!(value!(-7i64), Value::Number(Number::I64(-7)));
+        assert_eq!(
+            value!([1usize]),
+            Value::List(vec![Value::Number(Number::U64(1))])
+        );
+        assert_eq!(value!(5isize), Value::Number(Number::I64(5)));
+        assert_eq!(value!(1u128), Value::Number(Number::U64(1)));
+        assert_eq!(value!(-1i128), Value::Number(Number::I64(-1)));
+        assert_eq!(
+            value!({ "b": b"", "w": 7u32 })["w"],
+            Value::Number(Number::U32(7))
+        );
+    }
+
+    #[test]
+    fn spread_and_interpolation() {
+        let x = 4u16;
+        let mut base = Map::new();
+        base.insert("a", 1u8);
+        base.insert("b", 2u8);
+
+        let value =
+            value!({ "b": 0u8, ..&base, "c": -(x as i32), "x": x, ..value!({ "d": null }) });
+        assert_eq!(value["a"], Value::Number(Number::U8(1)));
+        assert_eq!(value["b"], Value::Number(Number::U8(2)));
+        assert_eq!(value["c"], Value::Number(Number::I32(-4)));
+        assert_eq!(value["x"], Value::Number(Number::U16(4)));
+        assert!(value.as_map().unwrap().contains_key("d"));
+        assert_eq!(value!({ ..base })["a"], Value::Number(Number::U8(1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "at 'a': left Number(U8(1)), right Number(I32(1))")]
+    fn assert_value_eq() {
+        let options = crate::EqOptions::default().with_missing_as_none(true);
+        assert_value_eq!(
+            value!({ "a": 1u8, "b": null }),
+            value!({ "a": 1u8 }),
+            options
+        );
+        assert_value_eq!(value!({ "a": 1u8 }), value!({ "a": 1 }));
+    }
+
+    #[cfg(feature = "datetime")]
+    #[test]
+    fn dates() {
+        let day = chrono::NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        let value = value!({
+            "day": @date("2024-02-29"),
+            "at": [@datetime("2024-02-29T10:30:00")],
+        });
+        assert_eq!(value["day"], Value::Date(day));
+        assert_eq!(
+            value["at"][0],
+            Value::DateTime(day.and_hms_opt(10, 30, 0).unwrap())
+        );
+        assert_eq!(value!(@date("2024-02-29")), Value::Date(day));
+    }
+}
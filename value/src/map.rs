@@ -1,21 +1,15 @@
 #[cfg(not(feature = "std"))]
 use alloc::{
-    collections::{
-        btree_map::{Entry, IntoIter, Iter},
-        BTreeMap,
-    },
+    collections::{btree_map, BTreeMap},
     string::{String, ToString},
 };
 #[cfg(feature = "std")]
 use std::{
-    collections::{
-        btree_map::{Entry, IntoIter, Iter},
-        BTreeMap,
-    },
+    collections::{btree_map, BTreeMap},
     string::String,
 };
 
-use core::ops;
+use core::{borrow::Borrow, iter::FusedIterator, mem, ops};
 
 use crate::Value;
 
@@ -27,17 +21,44 @@ use crate::Value;
     feature = "ordered_float",
     derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(
+        derive(Debug),
+        serialize_bounds(
+            __S: rkyv::ser::Writer + rkyv::ser::Allocator,
+            __S::Error: rkyv::rancor::Source,
+        ),
+        deserialize_bounds(__D::Error: rkyv::rancor::Source),
+        bytecheck(bounds(
+            __C: rkyv::validation::ArchiveContext,
+            __C::Error: rkyv::rancor::Source,
+        )),
+    )
+)]
 pub struct Map {
+    #[cfg_attr(feature = "rkyv", rkyv(omit_bounds))]
     pub(crate) inner: BTreeMap<String, Value>,
 }
 
 impl Map {
+    #[inline]
+    pub fn new() -> Map {
+        Map::default()
+    }
+
     pub fn with_capacity(_: usize) -> Map {
         Map {
             inner: BTreeMap::default(),
         }
     }
 
+    #[inline]
+    pub fn clear(&mut self) {
+        self.inner.clear()
+    }
+
     #[inline]
     pub fn insert(&mut self, name: impl ToString, value: impl Into<Value>) -> Option<Value> {
         self.inner.insert(name.to_string(), value.into())
@@ -53,22 +74,61 @@ impl Map {
         self.inner.get_mut(name.as_ref())
     }
 
+    #[inline]
+    pub fn get_key_value(&self, name: impl AsRef<str>) -> Option<(&String, &Value)> {
+        self.inner.get_key_value(name.as_ref())
+    }
+
     #[inline]
     pub fn contains(&self, name: impl AsRef<str>) -> bool {
         self.inner.contains_key(name.as_ref())
     }
 
+    #[inline]
+    pub fn contains_key(&self, name: impl AsRef<str>) -> bool {
+        self.contains(name)
+    }
+
     #[inline]
     pub fn remove(&mut self, name: impl AsRef<str>) -> Option<Value> {
         self.inner.remove(name.as_ref())
     }
 
     #[inline]
-    pub fn entry<S>(&mut self, key: S) -> Entry<'_, String, Value>
+    pub fn remove_entry(&mut self, name: impl AsRef<str>) -> Option<(String, Value)> {
+        self.inner.remove_entry(name.as_ref())
+    }
+
+    /// Move all entries from `other` into `self`, leaving `other` empty.
+    #[inline]
+    pub fn append(&mut self, other: &mut Map) {
+        self.inner.append(&mut other.inner)
+    }
+
+    /// Retain only the entries for which `f` returns true.
+    #[inline]
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&String, &mut Value) -> bool,
+    {
+        self.inner.retain(f)
+    }
+
+    /// Remove all entries, returning them in key order.
+    #[inline]
+    pub fn drain(&mut self) -> IntoIter {
+        mem::take(self).into_iter()
+    }
+
+    #[inline]
+    pub fn entry<S>(&mut self, key: S) -> Entry<'_>
     where
         S: Into<String>,
     {
-        self.inner.entry(key.into())
+        match self.inner.entry(key.into()) {
+            btree_map::Entry::Vacant(vacant) => Entry::Vacant(VacantEntry { vacant }),
+            btree_map::Entry::Occupied(occupied) => Entry::Occupied(OccupiedEntry { occupied }),
+        }
     }
 
     #[inline]
@@ -82,35 +142,310 @@ impl Map {
     }
 
     #[inline]
-    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (&'a String, &'a Value)> + 'a {
-        self.inner.iter()
+    pub fn first_key_value(&self) -> Option<(&String, &Value)> {
+        self.inner.first_key_value()
     }
 
     #[inline]
-    pub fn iter_mut<'a>(&'a self) -> impl Iterator<Item = (&'a String, &'a Value)> + 'a {
-        self.inner.iter()
+    pub fn last_key_value(&self) -> Option<(&String, &Value)> {
+        self.inner.last_key_value()
     }
+
+    #[inline]
+    pub fn pop_first(&mut self) -> Option<(String, Value)> {
+        self.inner.pop_first()
+    }
+
+    #[inline]
+    pub fn pop_last(&mut self) -> Option<(String, Value)> {
+        self.inner.pop_last()
+    }
+
+    /// Iterate over the entries whose keys fall within `range`, in key order.
+    #[inline]
+    pub fn range<T, R>(&self, range: R) -> Range<'_>
+    where
+        T: ?Sized + Ord,
+        String: Borrow<T>,
+        R: ops::RangeBounds<T>,
+    {
+        Range {
+            iter: self.inner.range(range),
+        }
+    }
+
+    #[inline]
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            iter: self.inner.iter(),
+        }
+    }
+
+    #[inline]
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut {
+            iter: self.inner.iter_mut(),
+        }
+    }
+
+    #[inline]
+    pub fn keys(&self) -> Keys<'_> {
+        Keys {
+            iter: self.inner.keys(),
+        }
+    }
+
+    #[inline]
+    pub fn values(&self) -> Values<'_> {
+        Values {
+            iter: self.inner.values(),
+        }
+    }
+
+    #[inline]
+    pub fn values_mut(&mut self) -> ValuesMut<'_> {
+        ValuesMut {
+            iter: self.inner.values_mut(),
+        }
+    }
+
+    #[inline]
+    pub fn into_keys(self) -> IntoKeys {
+        IntoKeys {
+            iter: self.inner.into_keys(),
+        }
+    }
+
+    #[inline]
+    pub fn into_values(self) -> IntoValues {
+        IntoValues {
+            iter: self.inner.into_values(),
+        }
+    }
+}
+
+/// A view into a single entry of a [`Map`], which may be vacant or occupied.
+pub enum Entry<'a> {
+    Vacant(VacantEntry<'a>),
+    Occupied(OccupiedEntry<'a>),
+}
+
+pub struct VacantEntry<'a> {
+    vacant: btree_map::VacantEntry<'a, String, Value>,
 }
 
-impl Extend<(String, Value)> for Map {
-    fn extend<T: IntoIterator<Item = (String, Value)>>(&mut self, iter: T) {
-        self.inner.extend(iter)
+pub struct OccupiedEntry<'a> {
+    occupied: btree_map::OccupiedEntry<'a, String, Value>,
+}
+
+impl<'a> Entry<'a> {
+    pub fn key(&self) -> &String {
+        match self {
+            Entry::Vacant(e) => e.key(),
+            Entry::Occupied(e) => e.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: impl Into<Value>) -> &'a mut Value {
+        match self {
+            Entry::Vacant(e) => e.insert(default),
+            Entry::Occupied(e) => e.into_mut(),
+        }
+    }
+
+    pub fn or_insert_with<F, V>(self, default: F) -> &'a mut Value
+    where
+        F: FnOnce() -> V,
+        V: Into<Value>,
+    {
+        match self {
+            Entry::Vacant(e) => e.insert(default()),
+            Entry::Occupied(e) => e.into_mut(),
+        }
+    }
+
+    /// Insert `Value::None` if the entry is vacant.
+    pub fn or_none(self) -> &'a mut Value {
+        self.or_insert(Value::None)
+    }
+
+    pub fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut Value),
+    {
+        match self {
+            Entry::Occupied(mut e) => {
+                f(e.get_mut());
+                Entry::Occupied(e)
+            }
+            Entry::Vacant(e) => Entry::Vacant(e),
+        }
+    }
+}
+
+impl<'a> VacantEntry<'a> {
+    pub fn key(&self) -> &String {
+        self.vacant.key()
+    }
+
+    pub fn into_key(self) -> String {
+        self.vacant.into_key()
+    }
+
+    pub fn insert(self, value: impl Into<Value>) -> &'a mut Value {
+        self.vacant.insert(value.into())
+    }
+}
+
+impl<'a> OccupiedEntry<'a> {
+    pub fn key(&self) -> &String {
+        self.occupied.key()
+    }
+
+    pub fn get(&self) -> &Value {
+        self.occupied.get()
+    }
+
+    pub fn get_mut(&mut self) -> &mut Value {
+        self.occupied.get_mut()
+    }
+
+    pub fn into_mut(self) -> &'a mut Value {
+        self.occupied.into_mut()
+    }
+
+    pub fn insert(&mut self, value: impl Into<Value>) -> Value {
+        self.occupied.insert(value.into())
+    }
+
+    pub fn remove(self) -> Value {
+        self.occupied.remove()
+    }
+
+    pub fn remove_entry(self) -> (String, Value) {
+        self.occupied.remove_entry()
+    }
+}
+
+macro_rules! delegate_iterator {
+    ($name: ident $(<$lt: lifetime>)?, $inner: ty, $item: ty) => {
+        pub struct $name $(<$lt>)? {
+            iter: $inner,
+        }
+
+        impl $(<$lt>)? Iterator for $name $(<$lt>)? {
+            type Item = $item;
+
+            #[inline]
+            fn next(&mut self) -> Option<Self::Item> {
+                self.iter.next()
+            }
+
+            #[inline]
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.iter.size_hint()
+            }
+        }
+
+        impl $(<$lt>)? DoubleEndedIterator for $name $(<$lt>)? {
+            #[inline]
+            fn next_back(&mut self) -> Option<Self::Item> {
+                self.iter.next_back()
+            }
+        }
+
+        impl $(<$lt>)? FusedIterator for $name $(<$lt>)? {}
+    };
+}
+
+macro_rules! exact_size {
+    ($($name: ident $(<$lt: lifetime>)?),*) => {
+        $(
+            impl $(<$lt>)? ExactSizeIterator for $name $(<$lt>)? {
+                #[inline]
+                fn len(&self) -> usize {
+                    self.iter.len()
+                }
+            }
+        )*
+    };
+}
+
+delegate_iterator!(
+    Iter<'a>,
+    btree_map::Iter<'a, String, Value>,
+    (&'a String, &'a Value)
+);
+delegate_iterator!(
+    IterMut<'a>,
+    btree_map::IterMut<'a, String, Value>,
+    (&'a String, &'a mut Value)
+);
+delegate_iterator!(IntoIter, btree_map::IntoIter<String, Value>, (String, Value));
+delegate_iterator!(Keys<'a>, btree_map::Keys<'a, String, Value>, &'a String);
+delegate_iterator!(Values<'a>, btree_map::Values<'a, String, Value>, &'a Value);
+delegate_iterator!(
+    ValuesMut<'a>,
+    btree_map::ValuesMut<'a, String, Value>,
+    &'a mut Value
+);
+delegate_iterator!(IntoKeys, btree_map::IntoKeys<String, Value>, String);
+delegate_iterator!(IntoValues, btree_map::IntoValues<String, Value>, Value);
+delegate_iterator!(
+    Range<'a>,
+    btree_map::Range<'a, String, Value>,
+    (&'a String, &'a Value)
+);
+
+exact_size!(
+    Iter<'a>,
+    IterMut<'a>,
+    IntoIter,
+    Keys<'a>,
+    Values<'a>,
+    ValuesMut<'a>,
+    IntoKeys,
+    IntoValues
+);
+
+impl<K: Into<String>, V: Into<Value>> Extend<(K, V)> for Map {
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        self.inner
+            .extend(iter.into_iter().map(|(k, v)| (k.into(), v.into())))
+    }
+}
+
+impl<K: Into<String>, V: Into<Value>> FromIterator<(K, V)> for Map {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut map = Map::default();
+        map.extend(iter);
+        map
     }
 }
 
 impl IntoIterator for Map {
     type Item = (String, Value);
-    type IntoIter = IntoIter<String, Value>;
+    type IntoIter = IntoIter;
     fn into_iter(self) -> Self::IntoIter {
-        self.inner.into_iter()
+        IntoIter {
+            iter: self.inner.into_iter(),
+        }
     }
 }
 
 impl<'a> IntoIterator for &'a Map {
     type Item = (&'a String, &'a Value);
-    type IntoIter = Iter<'a, String, Value>;
+    type IntoIter = Iter<'a>;
     fn into_iter(self) -> Self::IntoIter {
-        self.inner.iter()
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Map {
+    type Item = (&'a String, &'a mut Value);
+    type IntoIter = IterMut<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
     }
 }
 
@@ -143,3 +478,56 @@ impl From<BTreeMap<String, Value>> for Map {
         Map { inner: map }
     }
 }
+
+impl From<Map> for BTreeMap<String, Value> {
+    fn from(map: Map) -> BTreeMap<String, Value> {
+        map.inner
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    use core::ops::Bound;
+
+    use super::*;
+
+    #[test]
+    fn collection_api() {
+        let mut map: Map = [("b", 2), ("a", 1), ("c", 3)].into_iter().collect();
+
+        for value in map.values_mut() {
+            *value = Value::from(value.as_number().unwrap().as_u64() * 10);
+        }
+        for (_, value) in map.iter_mut().rev().take(1) {
+            *value = Value::from("last");
+        }
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["a", "b", "c"]);
+        assert_eq!(map.values().next_back(), Some(&Value::from("last")));
+        assert_eq!(map.first_key_value().map(|(k, _)| k.as_str()), Some("a"));
+
+        let range = map
+            .range::<str, _>((Bound::Included("b"), Bound::Unbounded))
+            .map(|(k, _)| k.as_str());
+        assert_eq!(range.collect::<Vec<_>>(), ["b", "c"]);
+
+        map.entry("a")
+            .and_modify(|v| *v = Value::Bool(true))
+            .or_none();
+        map.entry("d").or_insert_with(|| "new");
+        assert_eq!(map["a"], Value::Bool(true));
+        assert_eq!(map["d"], Value::from("new"));
+
+        map.retain(|k, _| k != "b");
+        let mut other = Map::from_iter([("e", 5)]);
+        map.append(&mut other);
+        assert!(other.is_empty());
+        assert_eq!(
+            map.drain().map(|(k, _)| k).collect::<Vec<_>>(),
+            ["a", "c", "d", "e"]
+        );
+        assert!(map.is_empty());
+    }
+}
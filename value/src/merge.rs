@@ -1,4 +1,10 @@
-use super::Value;
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, collections::BTreeSet, string::String, vec, vec::Vec};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::collections::BTreeSet;
+
+use super::{Map, Path, Segment, Value};
 
 pub fn merge(a: &mut Value, b: Value) {
     match (a, b) {
@@ -7,6 +13,16 @@ pub fn merge(a: &mut Value, b: Value) {
                 merge(a.entry(k).or_insert(Value::None), v);
             }
         }
+        (Value::Dict(ref mut a), Value::Dict(b)) => {
+            for (k, v) in *b {
+                match a.get_mut(&k) {
+                    Some(slot) => merge(slot, v),
+                    None => {
+                        a.insert(k, v);
+                    }
+                }
+            }
+        }
         (Value::List(ref mut a), Value::List(b)) => {
             a.extend(b);
         }
@@ -16,3 +32,457 @@ pub fn merge(a: &mut Value, b: Value) {
         (a, b) => *a = b,
     }
 }
+
+type Resolver<'a> = Box<dyn Fn(&Conflict) -> Option<Value> + 'a>;
+
+/// How [`merge3_with`] settles changes both sides made to the same value.
+#[derive(Default)]
+pub enum MergePolicy<'a> {
+    /// Report every conflict.
+    #[default]
+    Manual,
+    /// Keep our side of every conflict.
+    Ours,
+    /// Keep their side of every conflict.
+    Theirs,
+    /// Resolve conflicts with a closure. Returning `None` reports the
+    /// conflict.
+    Custom(Resolver<'a>),
+}
+
+impl<'a> MergePolicy<'a> {
+    pub fn custom<F>(resolve: F) -> MergePolicy<'a>
+    where
+        F: Fn(&Conflict) -> Option<Value> + 'a,
+    {
+        MergePolicy::Custom(Box::new(resolve))
+    }
+}
+
+impl fmt::Debug for MergePolicy<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergePolicy::Manual => f.write_str("Manual"),
+            MergePolicy::Ours => f.write_str("Ours"),
+            MergePolicy::Theirs => f.write_str("Theirs"),
+            MergePolicy::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+/// A value both sides changed differently. A side is `None` when it removed
+/// the value, or when the value didn't exist in the base.
+///
+/// For lists the conflict covers the range of items that could not be
+/// merged: `path` points at the list and each side holds its items of the
+/// range as a list. A resolution given as a list is spliced in their place.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub path: Path,
+    pub base: Option<Value>,
+    pub ours: Option<Value>,
+    pub theirs: Option<Value>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "conflict at '{}': ours {:?}, theirs {:?}",
+            self.path, self.ours, self.theirs
+        )
+    }
+}
+
+/// The conflicts [`merge3`] could not resolve, together with the merge
+/// result that keeps our side of each of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflicts {
+    conflicts: Vec<Conflict>,
+    merged: Value,
+}
+
+impl Conflicts {
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+
+    pub fn len(&self) -> usize {
+        self.conflicts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, Conflict> {
+        self.conflicts.iter()
+    }
+
+    /// The merge result with our side kept for every conflict.
+    pub fn merged(&self) -> &Value {
+        &self.merged
+    }
+
+    pub fn into_merged(self) -> Value {
+        self.merged
+    }
+}
+
+impl fmt::Display for Conflicts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} merge conflict(s)", self.conflicts.len())?;
+        for conflict in &self.conflicts {
+            write!(f, "; {}", conflict)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Conflicts {}
+
+impl<'a> IntoIterator for &'a Conflicts {
+    type Item = &'a Conflict;
+    type IntoIter = core::slice::Iter<'a, Conflict>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.conflicts.iter()
+    }
+}
+
+/// Three-way merge of `ours` and `theirs`, two edits of the common ancestor
+/// `base`. Changes to different map keys and list items are combined,
+/// changes to the same value are reported as [`Conflicts`].
+pub fn merge3(base: &Value, ours: &Value, theirs: &Value) -> Result<Value, Conflicts> {
+    merge3_with(base, ours, theirs, &MergePolicy::Manual)
+}
+
+/// Like [`merge3`], settling conflicts according to `policy`.
+pub fn merge3_with(
+    base: &Value,
+    ours: &Value,
+    theirs: &Value,
+    policy: &MergePolicy<'_>,
+) -> Result<Value, Conflicts> {
+    let mut merger = Merger {
+        policy,
+        path: Vec::new(),
+        conflicts: Vec::new(),
+    };
+    let merged = merger
+        .merge(Some(base), Some(ours), Some(theirs))
+        .unwrap_or(Value::None);
+    if merger.conflicts.is_empty() {
+        Ok(merged)
+    } else {
+        Err(Conflicts {
+            conflicts: merger.conflicts,
+            merged,
+        })
+    }
+}
+
+struct Merger<'p, 'a> {
+    policy: &'p MergePolicy<'a>,
+    path: Vec<Segment>,
+    conflicts: Vec<Conflict>,
+}
+
+impl Merger<'_, '_> {
+    fn merge(
+        &mut self,
+        base: Option<&Value>,
+        ours: Option<&Value>,
+        theirs: Option<&Value>,
+    ) -> Option<Value> {
+        if ours == theirs || theirs == base {
+            return ours.cloned();
+        }
+        if ours == base {
+            return theirs.cloned();
+        }
+
+        match (base, ours, theirs) {
+            (base, Some(Value::Map(ours)), Some(Value::Map(theirs))) => {
+                let empty = Map::new();
+                let base = match base {
+                    Some(Value::Map(base)) => base,
+                    _ => &empty,
+                };
+                Some(Value::Map(self.merge_maps(base, ours, theirs)))
+            }
+            (Some(Value::List(base)), Some(Value::List(ours)), Some(Value::List(theirs))) => {
+                Some(Value::List(self.merge_lists(base, ours, theirs)))
+            }
+            _ => self.conflict(base, ours, theirs),
+        }
+    }
+
+    fn merge_maps(&mut self, base: &Map, ours: &Map, theirs: &Map) -> Map {
+        let keys: BTreeSet<&String> = base
+            .keys()
+            .chain(ours.keys())
+            .chain(theirs.keys())
+            .collect();
+        let mut out = Map::new();
+        for key in keys {
+            self.path.push(Segment::Key(key.clone()));
+            if let Some(value) = self.merge(base.get(key), ours.get(key), theirs.get(key)) {
+                out.insert(key.clone(), value);
+            }
+            self.path.pop();
+        }
+        out
+    }
+
+    /// diff3 over the items: runs of items all three lists agree on are kept,
+    /// the changed ranges between them are taken from the side that changed
+    /// them, merged item by item when both sides kept the length, or
+    /// reported.
+    fn merge_lists(&mut self, base: &[Value], ours: &[Value], theirs: &[Value]) -> Vec<Value> {
+        let to_ours = lcs(base, ours);
+        let to_theirs = lcs(base, theirs);
+
+        let mut out = Vec::with_capacity(ours.len().max(theirs.len()));
+        let (mut b, mut o, mut t) = (0, 0, 0);
+        loop {
+            let stable = (b..base.len()).find_map(|k| Some((k, to_ours[k]?, to_theirs[k]?)));
+            let (nb, no, nt) = stable.unwrap_or((base.len(), ours.len(), theirs.len()));
+
+            if (nb, no, nt) == (b, o, t) {
+                if stable.is_none() {
+                    return out;
+                }
+                out.push(ours[o].clone());
+                b += 1;
+                o += 1;
+                t += 1;
+                continue;
+            }
+
+            self.merge_range(&base[b..nb], &ours[o..no], &theirs[t..nt], o, &mut out);
+            (b, o, t) = (nb, no, nt);
+        }
+    }
+
+    fn merge_range(
+        &mut self,
+        base: &[Value],
+        ours: &[Value],
+        theirs: &[Value],
+        offset: usize,
+        out: &mut Vec<Value>,
+    ) {
+        if ours == theirs || theirs == base {
+            out.extend_from_slice(ours);
+        } else if ours == base {
+            out.extend_from_slice(theirs);
+        } else if base.len() == ours.len() && base.len() == theirs.len() {
+            for (idx, ((b, o), t)) in base.iter().zip(ours).zip(theirs).enumerate() {
+                self.path.push(Segment::Index(offset + idx));
+                out.push(self.merge(Some(b), Some(o), Some(t)).unwrap_or(Value::None));
+                self.path.pop();
+            }
+        } else {
+            let resolved = self.conflict(
+                Some(&Value::List(base.to_vec())),
+                Some(&Value::List(ours.to_vec())),
+                Some(&Value::List(theirs.to_vec())),
+            );
+            match resolved {
+                Some(Value::List(items)) => out.extend(items),
+                Some(value) => out.push(value),
+                None => {}
+            }
+        }
+    }
+
+    fn conflict(
+        &mut self,
+        base: Option<&Value>,
+        ours: Option<&Value>,
+        theirs: Option<&Value>,
+    ) -> Option<Value> {
+        let conflict = Conflict {
+            path: self.path.iter().cloned().collect(),
+            base: base.cloned(),
+            ours: ours.cloned(),
+            theirs: theirs.cloned(),
+        };
+        match self.policy {
+            MergePolicy::Ours => conflict.ours,
+            MergePolicy::Theirs => conflict.theirs,
+            MergePolicy::Custom(resolve) => match resolve(&conflict) {
+                Some(value) => Some(value),
+                None => self.report(conflict),
+            },
+            MergePolicy::Manual => self.report(conflict),
+        }
+    }
+
+    fn report(&mut self, conflict: Conflict) -> Option<Value> {
+        let ours = conflict.ours.clone();
+        self.conflicts.push(conflict);
+        ours
+    }
+}
+
+/// The largest table [`lcs`] builds, 32 MiB on 64-bit targets.
+const MAX_LCS_CELLS: usize = 1 << 22;
+
+/// For every item of `a`, the index of the item of `b` it is paired with in
+/// a longest common subsequence.
+///
+/// The common prefix and suffix are paired directly. The table for the rest
+/// takes `a.len() * b.len()` cells; beyond [`MAX_LCS_CELLS`] its items are
+/// left unpaired, so they are merged as one changed range.
+fn lcs(a: &[Value], b: &[Value]) -> Vec<Option<usize>> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+
+    let mut pairs = vec![None; a.len()];
+    for (i, pair) in pairs[..prefix].iter_mut().enumerate() {
+        *pair = Some(i);
+    }
+    for k in 1..=suffix {
+        pairs[a.len() - k] = Some(b.len() - k);
+    }
+
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    if a_mid.len().saturating_mul(b_mid.len()) <= MAX_LCS_CELLS {
+        for (i, j) in lcs_table(a_mid, b_mid).into_iter().enumerate() {
+            pairs[prefix + i] = j.map(|j| prefix + j);
+        }
+    }
+    pairs
+}
+
+fn lcs_table(a: &[Value], b: &[Value]) -> Vec<Option<usize>> {
+    let width = b.len() + 1;
+    let mut table = vec![0usize; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            table[i * width + j] = if a[i] == b[j] {
+                table[(i + 1) * width + j + 1] + 1
+            } else {
+                table[(i + 1) * width + j].max(table[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut pairs = vec![None; a.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            pairs[i] = Some(j);
+            i += 1;
+            j += 1;
+        } else if table[(i + 1) * width + j] >= table[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::string::ToString;
+
+    use super::*;
+
+    fn list(items: &[u8]) -> Value {
+        Value::List(items.iter().map(|&i| Value::from(i)).collect())
+    }
+
+    fn doc(title: &str, tags: Value, count: u8) -> Value {
+        let mut map = Map::new();
+        map.insert("title", title);
+        map.insert("tags", tags);
+        map.insert("count", count);
+        Value::Map(map)
+    }
+
+    #[test]
+    fn merges_independent_changes() {
+        let base = doc("a", list(&[1, 2, 3, 4]), 0);
+        let ours = doc("b", list(&[0, 1, 2, 3, 4]), 0);
+        let mut theirs = doc("a", list(&[1, 2, 4, 5]), 1);
+        theirs.as_map_mut().unwrap().insert("new", true);
+
+        let merged = merge3(&base, &ours, &theirs).unwrap();
+        assert_eq!(merged["title"], Value::from("b"));
+        assert_eq!(merged["tags"], list(&[0, 1, 2, 4, 5]));
+        assert_eq!(merged["count"], Value::from(1u8));
+        assert_eq!(merged["new"], Value::from(true));
+    }
+
+    #[test]
+    fn reports_conflicts() {
+        let base = doc("a", list(&[1, 2]), 0);
+        let ours = doc("b", list(&[1, 3, 2]), 0);
+        let theirs = doc("c", list(&[1, 4, 2]), 0);
+
+        let conflicts = merge3(&base, &ours, &theirs).unwrap_err();
+        let paths: Vec<_> = conflicts.iter().map(|c| c.path.to_string()).collect();
+        assert_eq!(paths, ["tags", "title"]);
+        assert_eq!(conflicts.conflicts()[1].theirs, Some(Value::from("c")));
+        assert_eq!(conflicts.merged()["tags"], list(&[1, 3, 2]));
+
+        let merged = merge3_with(&base, &ours, &theirs, &MergePolicy::Theirs).unwrap();
+        assert_eq!(merged, theirs);
+
+        let both = MergePolicy::custom(|c| match (&c.ours, &c.theirs) {
+            (Some(Value::List(a)), Some(Value::List(b))) => {
+                Some(Value::List(a.iter().chain(b).cloned().collect()))
+            }
+            _ => None,
+        });
+        let conflicts = merge3_with(&base, &ours, &theirs, &both).unwrap_err();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts.merged()["tags"], list(&[1, 3, 4, 2]));
+    }
+
+    #[test]
+    fn large_lists() {
+        let base: Vec<Value> = (0..100_000u32).map(Value::from).collect();
+        let mut ours = base.clone();
+        ours.push(Value::from("end"));
+        let mut theirs = base.clone();
+        theirs[0] = Value::from("start");
+
+        let merged = merge3(
+            &Value::List(base.clone()),
+            &Value::List(ours),
+            &Value::List(theirs),
+        )
+        .unwrap();
+        let mut expected = base;
+        expected[0] = Value::from("start");
+        expected.push(Value::from("end"));
+        assert_eq!(merged, Value::List(expected));
+    }
+
+    #[test]
+    fn large_changes_conflict() {
+        let base: Vec<Value> = (0..3_000u32).map(Value::from).collect();
+        let ours: Vec<Value> = base.iter().rev().cloned().collect();
+        let theirs: Vec<Value> = base.iter().skip(1).cloned().collect();
+
+        let conflicts = merge3(
+            &Value::List(base.clone()),
+            &Value::List(ours.clone()),
+            &Value::List(theirs),
+        )
+        .unwrap_err();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts.merged(), &Value::List(ours));
+    }
+}
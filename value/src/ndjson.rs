@@ -0,0 +1,411 @@
+//! Streaming newline-delimited JSON (NDJSON) and JSON text sequences (RFC 7464).
+use std::{
+    fmt,
+    io::{self, BufRead, Write},
+};
+
+use crate::{Limits, Value};
+
+const RS: u8 = 0x1E;
+
+/// How records are delimited in the stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Framing {
+    /// One JSON document per line.
+    #[default]
+    Lines,
+    /// Each JSON document is prefixed by an ASCII record separator (`0x1E`)
+    /// and may span multiple lines.
+    JsonSeq,
+}
+
+impl Framing {
+    fn delimiter(&self) -> u8 {
+        match self {
+            Framing::Lines => b'\n',
+            Framing::JsonSeq => RS,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum NdjsonError {
+    Io(io::Error),
+    Json {
+        line: usize,
+        error: serde_json::Error,
+    },
+}
+
+impl NdjsonError {
+    /// The line the failing record started on, if the error relates to a record.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            NdjsonError::Json { line, .. } => Some(*line),
+            NdjsonError::Io(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for NdjsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NdjsonError::Io(err) => write!(f, "io error: {}", err),
+            NdjsonError::Json { line, error } => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+
+impl std::error::Error for NdjsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NdjsonError::Io(err) => Some(err),
+            NdjsonError::Json { error, .. } => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for NdjsonError {
+    fn from(err: io::Error) -> Self {
+        NdjsonError::Io(err)
+    }
+}
+
+/// Tracks line numbers and turns raw records into values.
+#[derive(Debug)]
+struct Decoder {
+    framing: Framing,
+    line: usize,
+    limits: Limits,
+}
+
+impl Decoder {
+    fn new(framing: Framing) -> Decoder {
+        Decoder {
+            framing,
+            line: 0,
+            limits: Limits::default(),
+        }
+    }
+}
+
+impl Decoder {
+    fn decode(&mut self, record: &[u8]) -> Option<Result<Value, NdjsonError>> {
+        let start = self.line + 1;
+        self.line += match self.framing {
+            Framing::Lines => 1,
+            Framing::JsonSeq => record.iter().filter(|b| **b == b'\n').count(),
+        };
+
+        let record = match record.iter().position(|b| !is_space(*b)) {
+            Some(start) => {
+                let end = record.iter().rposition(|b| !is_space(*b)).unwrap_or(start);
+                &record[start..=end]
+            }
+            None => return None,
+        };
+
+        Some(
+            self.parse(record)
+                .map_err(|error| NdjsonError::Json { line: start, error }),
+        )
+    }
+
+    fn parse(&self, record: &[u8]) -> Result<Value, serde_json::Error> {
+        let mut de = serde_json::Deserializer::from_slice(record);
+        let value = self.limits.deserialize_value(&mut de)?;
+        de.end()?;
+        Ok(value)
+    }
+}
+
+fn is_space(b: u8) -> bool {
+    b.is_ascii_whitespace() || b == RS
+}
+
+/// Iterator over the records of a NDJSON stream.
+///
+/// Blank lines are skipped. A malformed record yields an error carrying its
+/// line number, and iteration can continue with the next record.
+pub struct NdjsonReader<R> {
+    reader: R,
+    buf: Vec<u8>,
+    decoder: Decoder,
+}
+
+impl<R: BufRead> NdjsonReader<R> {
+    pub fn new(reader: R) -> NdjsonReader<R> {
+        NdjsonReader::with_framing(reader, Framing::Lines)
+    }
+
+    pub fn with_framing(reader: R, framing: Framing) -> NdjsonReader<R> {
+        NdjsonReader {
+            reader,
+            buf: Vec::new(),
+            decoder: Decoder::new(framing),
+        }
+    }
+
+    /// Limits applied to every record. Defaults to [`Limits::default`].
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.decoder.limits = limits;
+        self
+    }
+
+    /// Number of lines consumed so far.
+    pub fn line(&self) -> usize {
+        self.decoder.line
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: BufRead> Iterator for NdjsonReader<R> {
+    type Item = Result<Value, NdjsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            let delim = self.decoder.framing.delimiter();
+            match self.reader.read_until(delim, &mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(err) => return Some(Err(err.into())),
+            }
+
+            if let Some(ret) = self.decoder.decode(&self.buf) {
+                return Some(ret);
+            }
+        }
+    }
+}
+
+/// When a [`NdjsonWriter`] flushes the underlying writer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlushPolicy {
+    /// Only flush on explicit calls to [`NdjsonWriter::flush`].
+    #[default]
+    Manual,
+    /// Flush after every record.
+    EveryRecord,
+    /// Flush after every n records.
+    Every(usize),
+}
+
+/// Appends values to a writer, one record at a time.
+pub struct NdjsonWriter<W: Write> {
+    writer: W,
+    framing: Framing,
+    flush: FlushPolicy,
+    pending: usize,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    pub fn new(writer: W) -> NdjsonWriter<W> {
+        NdjsonWriter {
+            writer,
+            framing: Framing::Lines,
+            flush: FlushPolicy::Manual,
+            pending: 0,
+        }
+    }
+
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    pub fn with_flush_policy(mut self, flush: FlushPolicy) -> Self {
+        self.flush = flush;
+        self
+    }
+
+    pub fn write(&mut self, value: &Value) -> Result<(), NdjsonError> {
+        if self.framing == Framing::JsonSeq {
+            self.writer.write_all(&[RS])?;
+        }
+        serde_json::to_writer(&mut self.writer, value).map_err(io::Error::from)?;
+        self.writer.write_all(b"\n")?;
+
+        self.pending += 1;
+        let flush = match self.flush {
+            FlushPolicy::Manual => false,
+            FlushPolicy::EveryRecord => true,
+            FlushPolicy::Every(n) => self.pending >= n,
+        };
+
+        if flush {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn write_all<'a, I>(&mut self, values: I) -> Result<(), NdjsonError>
+    where
+        I: IntoIterator<Item = &'a Value>,
+    {
+        for value in values {
+            self.write(value)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.pending = 0;
+        self.writer.flush()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(feature = "ndjson-async")]
+pub use self::stream::NdjsonStream;
+
+#[cfg(feature = "ndjson-async")]
+mod stream {
+    use core::{
+        pin::Pin,
+        task::{ready, Context, Poll},
+    };
+    use futures_core::Stream;
+    use futures_io::AsyncBufRead;
+
+    use super::{Decoder, Framing, NdjsonError};
+    use crate::{Limits, Value};
+
+    /// Asynchronous counterpart of [`NdjsonReader`](super::NdjsonReader).
+    pub struct NdjsonStream<R> {
+        reader: R,
+        buf: Vec<u8>,
+        decoder: Decoder,
+        done: bool,
+    }
+
+    impl<R: AsyncBufRead + Unpin> NdjsonStream<R> {
+        pub fn new(reader: R) -> NdjsonStream<R> {
+            NdjsonStream::with_framing(reader, Framing::Lines)
+        }
+
+        pub fn with_framing(reader: R, framing: Framing) -> NdjsonStream<R> {
+            NdjsonStream {
+                reader,
+                buf: Vec::new(),
+                decoder: Decoder::new(framing),
+                done: false,
+            }
+        }
+
+        pub fn with_limits(mut self, limits: Limits) -> Self {
+            self.decoder.limits = limits;
+            self
+        }
+
+        pub fn line(&self) -> usize {
+            self.decoder.line
+        }
+
+        pub fn into_inner(self) -> R {
+            self.reader
+        }
+    }
+
+    impl<R: AsyncBufRead + Unpin> Stream for NdjsonStream<R> {
+        type Item = Result<Value, NdjsonError>;
+
+        fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            let this = self.get_mut();
+            let delim = this.decoder.framing.delimiter();
+
+            while !this.done {
+                let available = match ready!(Pin::new(&mut this.reader).poll_fill_buf(cx)) {
+                    Ok(available) => available,
+                    Err(err) => return Poll::Ready(Some(Err(err.into()))),
+                };
+
+                let (consumed, complete) = if available.is_empty() {
+                    this.done = true;
+                    (0, true)
+                } else {
+                    match available.iter().position(|b| *b == delim) {
+                        Some(idx) => {
+                            this.buf.extend_from_slice(&available[..=idx]);
+                            (idx + 1, true)
+                        }
+                        None => {
+                            this.buf.extend_from_slice(available);
+                            (available.len(), false)
+                        }
+                    }
+                };
+
+                Pin::new(&mut this.reader).consume(consumed);
+
+                if complete {
+                    let record = core::mem::take(&mut this.buf);
+                    if let Some(ret) = this.decoder.decode(&record) {
+                        return Poll::Ready(Some(ret));
+                    }
+                }
+            }
+
+            Poll::Ready(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reports_line_numbers() {
+        let input = b"{\"a\": 1}\n\n[1, 2]\n{oops}\n\"done\"\n";
+        let results = NdjsonReader::new(&input[..]).collect::<Vec<_>>();
+
+        assert_eq!(results.len(), 4);
+        assert!(results[0].as_ref().unwrap().is_map());
+        assert!(results[1].as_ref().unwrap().is_list());
+        assert_eq!(results[2].as_ref().unwrap_err().line(), Some(4));
+        assert_eq!(results[3].as_ref().unwrap(), &Value::from("done"));
+    }
+
+    #[test]
+    fn json_seq_round_trip() {
+        let values = vec![Value::from("a"), Value::from(true), Value::List(vec![])];
+
+        let mut writer = NdjsonWriter::new(Vec::new()).with_framing(Framing::JsonSeq);
+        writer.write_all(&values).unwrap();
+        let out = writer.into_inner().unwrap();
+
+        let read = NdjsonReader::with_framing(&out[..], Framing::JsonSeq)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(read, values);
+    }
+
+    #[cfg(feature = "ndjson-async")]
+    #[test]
+    fn stream_records() {
+        use core::{future::poll_fn, pin::Pin};
+        use futures_core::Stream;
+
+        let mut stream = NdjsonStream::new(&b"1\n\n{\"a\": [true]}\nnull"[..]);
+        let mut next =
+            || futures_executor::block_on(poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)));
+
+        assert!(next().unwrap().unwrap().is_number());
+        assert!(next().unwrap().unwrap().is_map());
+        assert!(next().unwrap().unwrap().is_none());
+        assert!(next().is_none());
+    }
+}
@@ -10,6 +10,11 @@ use ordered_float_lib::OrderedFloat;
     feature = "ordered_float",
     derive(Debug, Clone, Copy, Eq, PartialOrd, Ord, Hash)
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(derive(Debug, Clone, Copy))
+)]
 pub enum Number {
     U8(u8),
     U16(u16),
@@ -20,9 +25,9 @@ pub enum Number {
     I32(i32),
     I64(i64),
     #[cfg(feature = "ordered_float")]
-    F32(OrderedFloat<f32>),
+    F32(#[cfg_attr(feature = "rkyv", rkyv(with = crate::rkyv_impl::Float))] OrderedFloat<f32>),
     #[cfg(feature = "ordered_float")]
-    F64(OrderedFloat<f64>),
+    F64(#[cfg_attr(feature = "rkyv", rkyv(with = crate::rkyv_impl::Float))] OrderedFloat<f64>),
     #[cfg(not(feature = "ordered_float"))]
     F32(f32),
     #[cfg(not(feature = "ordered_float"))]
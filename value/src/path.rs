@@ -0,0 +1,534 @@
+//! Dotted paths into a `Value`, like `db.replicas[2].host`.
+//!
+//! Keys are separated by `.` and list indexes are written as `[n]`. A `\`
+//! escapes the next character, so `a\.b` is the single key `a.b`. The empty
+//! path refers to the root value.
+#[cfg(not(feature = "std"))]
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, mem, str::FromStr};
+#[cfg(feature = "std")]
+use std::{borrow::Cow, string::String};
+
+use crate::{Map, Value};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Path {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug)]
+pub enum PathError {
+    /// The path could not be parsed. `position` is the byte offset of the error.
+    Syntax {
+        position: usize,
+        message: &'static str,
+    },
+    /// A key or index along the path does not exist.
+    NotFound { path: String },
+    /// A value along the path is not the container the next segment needs.
+    InvalidType {
+        path: String,
+        expected: &'static str,
+    },
+    /// Setting the index would grow the list by more than the allowed padding.
+    IndexOutOfRange { path: String, len: usize },
+    /// The value at the path could not be deserialized into the requested type.
+    #[cfg(feature = "serde")]
+    Deserialize {
+        path: String,
+        error: crate::de::DeserializerError,
+    },
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::Syntax { position, message } => {
+                write!(f, "invalid path at {}: {}", position, message)
+            }
+            PathError::NotFound { path } => write!(f, "path not found: {}", path),
+            PathError::InvalidType { path, expected } => {
+                write!(f, "expected {} at {}", expected, path)
+            }
+            PathError::IndexOutOfRange { path, len } => {
+                write!(f, "{} is out of range for a list of length {}", path, len)
+            }
+            #[cfg(feature = "serde")]
+            PathError::Deserialize { path, error } => write!(f, "{}: {}", path, error),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PathError {}
+
+impl Path {
+    /// How many `Value::None` items [`Path::set`] inserts at most to reach an
+    /// index past the end of a list.
+    pub const MAX_PADDING: usize = 1024;
+
+    pub fn new() -> Path {
+        Path::default()
+    }
+
+    pub fn parse(path: &str) -> Result<Path, PathError> {
+        let syntax = |position, message| PathError::Syntax { position, message };
+
+        let mut segments = Vec::new();
+        let mut chars = path.char_indices().peekable();
+        // Whether a key may start at the current position
+        let mut expect_key = true;
+
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                '[' if expect_key && !segments.is_empty() => {
+                    return Err(syntax(pos, "expected key"))
+                }
+                '[' => {
+                    let mut index: Option<usize> = None;
+                    loop {
+                        match chars.next() {
+                            Some((_, ']')) => break,
+                            Some((pos, c)) => {
+                                let digit = c
+                                    .to_digit(10)
+                                    .ok_or_else(|| syntax(pos, "expected digit"))?;
+                                index = index
+                                    .unwrap_or(0)
+                                    .checked_mul(10)
+                                    .and_then(|i| i.checked_add(digit as usize))
+                                    .map(Some)
+                                    .ok_or_else(|| syntax(pos, "index out of range"))?;
+                            }
+                            None => return Err(syntax(path.len(), "unclosed '['")),
+                        }
+                    }
+                    let index = index.ok_or_else(|| syntax(pos, "empty index"))?;
+                    segments.push(Segment::Index(index));
+                    expect_key = false;
+                }
+                '.' if !expect_key => {
+                    expect_key = true;
+                    if chars.peek().is_none() {
+                        return Err(syntax(path.len(), "expected key"));
+                    }
+                }
+                '.' | ']' => return Err(syntax(pos, "unexpected character")),
+                _ if !expect_key => return Err(syntax(pos, "expected '.' or '['")),
+                _ => {
+                    let mut key = String::new();
+                    let (mut pos, mut c) = (pos, c);
+                    loop {
+                        if c == '\\' {
+                            match chars.next() {
+                                Some((_, c)) => key.push(c),
+                                None => return Err(syntax(pos, "dangling escape")),
+                            }
+                        } else {
+                            key.push(c);
+                        }
+                        match chars.next_if(|(_, c)| !matches!(c, '.' | '[' | ']')) {
+                            Some(next) => (pos, c) = next,
+                            None => break,
+                        }
+                    }
+                    segments.push(Segment::Key(key));
+                    expect_key = false;
+                }
+            }
+        }
+
+        Ok(Path { segments })
+    }
+
+    pub fn key(mut self, key: impl Into<String>) -> Path {
+        self.segments.push(Segment::Key(key.into()));
+        self
+    }
+
+    pub fn index(mut self, index: usize) -> Path {
+        self.segments.push(Segment::Index(index));
+        self
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn get<'a>(&self, value: &'a Value) -> Result<&'a Value, PathError> {
+        let mut current = value;
+        for (idx, segment) in self.segments.iter().enumerate() {
+            current = match (segment, current) {
+                (Segment::Key(key), Value::Map(map)) => map.get(key),
+                (Segment::Index(i), Value::List(list)) => list.get(*i),
+                _ => return Err(self.invalid_type(idx)),
+            }
+            .ok_or_else(|| self.not_found(idx))?;
+        }
+        Ok(current)
+    }
+
+    pub fn get_mut<'a>(&self, value: &'a mut Value) -> Result<&'a mut Value, PathError> {
+        self.get_mut_at(value, self.segments.len())
+    }
+
+    /// Set the value at the path, returning the previous one.
+    ///
+    /// Missing keys are created, `Value::None` along the way is replaced by an
+    /// empty map or list, and lists are grown with `Value::None` to fit the index,
+    /// by at most [`Path::MAX_PADDING`] items.
+    pub fn set(&self, value: &mut Value, new: Value) -> Result<Option<Value>, PathError> {
+        self.set_with_padding(value, new, Path::MAX_PADDING)
+    }
+
+    /// Like [`Path::set`], but growing lists by at most `max_padding` items
+    /// before the index. With `0` an index may only append to a list.
+    pub fn set_with_padding(
+        &self,
+        value: &mut Value,
+        new: Value,
+        max_padding: usize,
+    ) -> Result<Option<Value>, PathError> {
+        let mut current = value;
+        for (idx, segment) in self.segments.iter().enumerate() {
+            current = match segment {
+                Segment::Key(key) => {
+                    if current.is_none() {
+                        *current = Value::Map(Map::default());
+                    }
+                    match current {
+                        Value::Map(map) => map.entry(key.as_str()).or_insert(Value::None),
+                        _ => return Err(self.invalid_type(idx)),
+                    }
+                }
+                Segment::Index(i) => {
+                    if current.is_none() {
+                        *current = Value::List(Vec::new());
+                    }
+                    match current {
+                        Value::List(list) => {
+                            if list.len() <= *i {
+                                if *i - list.len() > max_padding {
+                                    return Err(PathError::IndexOutOfRange {
+                                        path: self.prefix(idx),
+                                        len: list.len(),
+                                    });
+                                }
+                                list.resize(*i + 1, Value::None);
+                            }
+                            &mut list[*i]
+                        }
+                        _ => return Err(self.invalid_type(idx)),
+                    }
+                }
+            };
+        }
+
+        Ok(mem::replace(current, new).into_option())
+    }
+
+    /// Remove the value at the path. Lists shift the following items down.
+    pub fn remove(&self, value: &mut Value) -> Result<Value, PathError> {
+        let last = match self.segments.last() {
+            Some(last) => last,
+            None => return Ok(mem::replace(value, Value::None)),
+        };
+
+        let idx = self.segments.len() - 1;
+        let removed = match (last, self.get_mut_at(value, idx)?) {
+            (Segment::Key(key), Value::Map(map)) => map.remove(key),
+            (Segment::Index(i), Value::List(list)) if *i < list.len() => Some(list.remove(*i)),
+            (Segment::Index(_), Value::List(_)) => None,
+            _ => return Err(self.invalid_type(idx)),
+        };
+
+        removed.ok_or_else(|| self.not_found(idx))
+    }
+
+    /// Take the value at the path, leaving `Value::None` in its place.
+    pub fn take(&self, value: &mut Value) -> Result<Value, PathError> {
+        self.get_mut(value)
+            .map(|value| mem::replace(value, Value::None))
+    }
+
+    fn get_mut_at<'a>(&self, value: &'a mut Value, len: usize) -> Result<&'a mut Value, PathError> {
+        let mut current = value;
+        for (idx, segment) in self.segments[..len].iter().enumerate() {
+            current = match (segment, current) {
+                (Segment::Key(key), Value::Map(map)) => map.get_mut(key),
+                (Segment::Index(i), Value::List(list)) => list.get_mut(*i),
+                _ => return Err(self.invalid_type(idx)),
+            }
+            .ok_or_else(|| self.not_found(idx))?;
+        }
+        Ok(current)
+    }
+
+    fn prefix(&self, idx: usize) -> String {
+        Segments(&self.segments[..=idx]).to_string()
+    }
+
+    fn not_found(&self, idx: usize) -> PathError {
+        PathError::NotFound {
+            path: self.prefix(idx),
+        }
+    }
+
+    fn invalid_type(&self, idx: usize) -> PathError {
+        let expected = match self.segments[idx] {
+            Segment::Key(_) => "map",
+            Segment::Index(_) => "list",
+        };
+        PathError::InvalidType {
+            path: self.prefix(idx),
+            expected,
+        }
+    }
+}
+
+struct Segments<'a>(&'a [Segment]);
+
+impl fmt::Display for Segments<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, segment) in self.0.iter().enumerate() {
+            match segment {
+                Segment::Key(key) => {
+                    if idx > 0 {
+                        f.write_str(".")?;
+                    }
+                    for c in key.chars() {
+                        if matches!(c, '.' | '[' | ']' | '\\') {
+                            f.write_str("\\")?;
+                        }
+                        write!(f, "{}", c)?;
+                    }
+                }
+                Segment::Index(i) => write!(f, "[{}]", i)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Segments(&self.segments).fmt(f)
+    }
+}
+
+impl FromStr for Path {
+    type Err = PathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Path::parse(s)
+    }
+}
+
+impl FromIterator<Segment> for Path {
+    fn from_iter<T: IntoIterator<Item = Segment>>(iter: T) -> Self {
+        Path {
+            segments: iter.into_iter().collect(),
+        }
+    }
+}
+
+// Prevent users from implementing the AsPath trait.
+mod private {
+    pub trait Sealed {}
+    impl Sealed for str {}
+    impl Sealed for super::String {}
+    impl Sealed for super::Path {}
+    impl<T> Sealed for &T where T: ?Sized + Sealed {}
+}
+
+/// Types usable as a path: strings are parsed, a `Path` is used as is.
+pub trait AsPath: private::Sealed {
+    fn as_path(&self) -> Result<Cow<'_, Path>, PathError>;
+}
+
+impl AsPath for str {
+    fn as_path(&self) -> Result<Cow<'_, Path>, PathError> {
+        Path::parse(self).map(Cow::Owned)
+    }
+}
+
+impl AsPath for String {
+    fn as_path(&self) -> Result<Cow<'_, Path>, PathError> {
+        self.as_str().as_path()
+    }
+}
+
+impl AsPath for Path {
+    fn as_path(&self) -> Result<Cow<'_, Path>, PathError> {
+        Ok(Cow::Borrowed(self))
+    }
+}
+
+impl<T> AsPath for &T
+where
+    T: ?Sized + AsPath,
+{
+    fn as_path(&self) -> Result<Cow<'_, Path>, PathError> {
+        (**self).as_path()
+    }
+}
+
+impl Value {
+    pub fn get_path<P: AsPath>(&self, path: P) -> Result<&Value, PathError> {
+        path.as_path()?.get(self)
+    }
+
+    pub fn get_path_mut<P: AsPath>(&mut self, path: P) -> Result<&mut Value, PathError> {
+        path.as_path()?.get_mut(self)
+    }
+
+    /// Deserialize the value at the path into `T`.
+    #[cfg(feature = "serde")]
+    pub fn get_path_as<T, P>(&self, path: P) -> Result<T, PathError>
+    where
+        T: serde::de::DeserializeOwned,
+        P: AsPath,
+    {
+        let path = path.as_path()?;
+        let value = path.get(self)?;
+        crate::from_value(value.clone()).map_err(|error| PathError::Deserialize {
+            path: path.to_string(),
+            error,
+        })
+    }
+
+    /// See [`Path::set`].
+    pub fn set_path<P: AsPath, V: Into<Value>>(
+        &mut self,
+        path: P,
+        value: V,
+    ) -> Result<Option<Value>, PathError> {
+        path.as_path()?.set(self, value.into())
+    }
+
+    pub fn remove_path<P: AsPath>(&mut self, path: P) -> Result<Value, PathError> {
+        path.as_path()?.remove(self)
+    }
+
+    pub fn take_path<P: AsPath>(&mut self, path: P) -> Result<Value, PathError> {
+        path.as_path()?.take(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::{format, vec};
+
+    use super::*;
+
+    #[test]
+    fn parse_and_display() {
+        let path = Path::parse(r"db.replicas[2].host\.name[0][1]").unwrap();
+        assert_eq!(
+            path,
+            Path::new()
+                .key("db")
+                .key("replicas")
+                .index(2)
+                .key("host.name")
+                .index(0)
+                .index(1)
+        );
+        assert_eq!(path.to_string(), r"db.replicas[2].host\.name[0][1]");
+        assert!(Path::parse("").unwrap().is_empty());
+
+        for invalid in [
+            "a.[0]", "a..b", "a.", ".a", "a[x]", "a[1", "a[]", "a]", "a[0]b", "a\\",
+        ] {
+            assert!(Path::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn set_get_remove() {
+        let mut value = Value::None;
+        value.set_path("db.replicas[2].host", "localhost").unwrap();
+
+        let replicas = value.get_path("db.replicas").unwrap().as_list().unwrap();
+        assert_eq!(replicas.len(), 3);
+        assert!(replicas[0].is_none());
+        assert_eq!(
+            value.get_path("db.replicas[2].host").unwrap(),
+            &Value::from("localhost")
+        );
+
+        assert!(matches!(
+            value.get_path("db.replicas[5]"),
+            Err(PathError::NotFound { path }) if path == "db.replicas[5]"
+        ));
+        assert!(matches!(
+            value.set_path("db.replicas[2].host[0]", 1),
+            Err(PathError::InvalidType {
+                expected: "list",
+                ..
+            })
+        ));
+
+        assert_eq!(
+            value.take_path("db.replicas[2].host").unwrap(),
+            Value::from("localhost")
+        );
+        assert!(value.get_path("db.replicas[2].host").unwrap().is_none());
+
+        value.remove_path("db.replicas[0]").unwrap();
+        assert_eq!(
+            value
+                .get_path("db.replicas")
+                .unwrap()
+                .as_list()
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(value.remove_path("db.missing").is_err());
+
+        assert!(matches!(
+            value.set_path(format!("huge[{}]", usize::MAX).as_str(), 1),
+            Err(PathError::IndexOutOfRange { path, len: 0 }) if path.starts_with("huge[")
+        ));
+        let path = Path::parse("list[1]").unwrap();
+        assert!(path.set_with_padding(&mut value, 1.into(), 0).is_err());
+        assert!(Path::parse("list[0]")
+            .unwrap()
+            .set_with_padding(&mut value, 1.into(), 0)
+            .is_ok());
+        assert!(path.set_with_padding(&mut value, 1.into(), 0).is_ok());
+        assert_eq!(
+            value
+                .set_path("", vec![Value::from(1)])
+                .unwrap()
+                .map(|v| v.is_map()),
+            Some(true)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn get_path_as() {
+        let mut value = Value::None;
+        value.set_path("server.port", 8080u16).unwrap();
+
+        assert_eq!(value.get_path_as::<u16, _>("server.port").unwrap(), 8080);
+        assert!(value.get_path_as::<bool, _>("server.port").is_err());
+    }
+}
@@ -0,0 +1,366 @@
+//! A persistent `Value` whose lists and maps share structure between
+//! versions, for keeping cheap snapshots like an undo history.
+//!
+//! Cloning a [`PValue`] is O(1) and updating it copies only the nodes on the
+//! path to the change, so every older version stays valid and untouched
+//! subtrees are shared between them. Values are addressed with JSON Pointers
+//! (RFC 6901), e.g. `/users/0/name`.
+use std::sync::Arc;
+
+use crate::{
+    pointer::{parse_index, parse_pointer},
+    Dict, Number, PathError, Value,
+};
+
+/// A hash array mapped trie keyed by strings.
+pub type PMap = imbl::HashMap<Arc<str>, PValue>;
+
+/// A relaxed radix balanced vector.
+pub type PList = imbl::Vector<PValue>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PValue {
+    Bool(bool),
+    Number(Number),
+    Char(char),
+    String(Arc<str>),
+    List(PList),
+    Map(PMap),
+    Dict(Arc<Dict>),
+    Bytes(Arc<[u8]>),
+    #[cfg(feature = "datetime")]
+    Date(chrono::NaiveDate),
+    #[cfg(feature = "datetime")]
+    DateTime(chrono::NaiveDateTime),
+    None,
+}
+
+impl PValue {
+    pub fn get(&self, key: &str) -> Option<&PValue> {
+        match self {
+            PValue::Map(map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    pub fn get_index(&self, idx: usize) -> Option<&PValue> {
+        match self {
+            PValue::List(list) => list.get(idx),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            PValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&PList> {
+        match self {
+            PValue::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&PMap> {
+        match self {
+            PValue::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        matches!(self, PValue::None)
+    }
+
+    /// Whether both values are the same node, i.e. one is an unmodified
+    /// snapshot of the other. Scalars are never shared.
+    pub fn ptr_eq(&self, other: &PValue) -> bool {
+        match (self, other) {
+            (PValue::List(a), PValue::List(b)) => a.ptr_eq(b),
+            (PValue::Map(a), PValue::Map(b)) => a.ptr_eq(b),
+            (PValue::String(a), PValue::String(b)) => Arc::ptr_eq(a, b),
+            (PValue::Dict(a), PValue::Dict(b)) => Arc::ptr_eq(a, b),
+            (PValue::Bytes(a), PValue::Bytes(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    pub fn pointer(&self, pointer: &str) -> Option<&PValue> {
+        parse_pointer(pointer)
+            .ok()?
+            .iter()
+            .try_fold(self, |value, token| value.child(token))
+    }
+
+    /// Mutable access to the value at `pointer`. Nodes along the path that
+    /// are shared with other versions are copied first.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut PValue> {
+        parse_pointer(pointer)
+            .ok()?
+            .iter()
+            .try_fold(self, |value, token| value.child_mut(token))
+    }
+
+    /// Set the value at `pointer`, returning the previous value. The parent
+    /// must exist; a new map entry is added for a missing key, and `-` or an
+    /// index equal to the length appends to a list.
+    pub fn set_pointer(
+        &mut self,
+        pointer: &str,
+        value: impl Into<PValue>,
+    ) -> Result<Option<PValue>, PathError> {
+        let tokens = parse_pointer(pointer)?;
+        let Some((last, parents)) = tokens.split_last() else {
+            return Ok(Some(core::mem::replace(self, value.into())));
+        };
+        match self.parent_mut(pointer, parents)? {
+            PValue::Map(map) => Ok(map.insert(Arc::from(last.as_str()), value.into())),
+            PValue::List(list) => {
+                let idx = match last.as_str() {
+                    "-" => list.len(),
+                    token => parse_index(token).ok_or_else(|| not_found(pointer))?,
+                };
+                if idx < list.len() {
+                    Ok(Some(list.set(idx, value.into())))
+                } else if idx == list.len() {
+                    list.push_back(value.into());
+                    Ok(None)
+                } else {
+                    Err(not_found(pointer))
+                }
+            }
+            _ => Err(invalid_type(pointer, parents.len())),
+        }
+    }
+
+    /// Remove the value at `pointer`. Later list items move down by one.
+    pub fn remove_pointer(&mut self, pointer: &str) -> Result<PValue, PathError> {
+        let tokens = parse_pointer(pointer)?;
+        let Some((last, parents)) = tokens.split_last() else {
+            return Ok(core::mem::replace(self, PValue::None));
+        };
+        match self.parent_mut(pointer, parents)? {
+            PValue::Map(map) => map.remove(last.as_str()).ok_or_else(|| not_found(pointer)),
+            PValue::List(list) => match parse_index(last) {
+                Some(idx) if idx < list.len() => Ok(list.remove(idx)),
+                _ => Err(not_found(pointer)),
+            },
+            _ => Err(invalid_type(pointer, parents.len())),
+        }
+    }
+
+    /// A new version with the value at `pointer` set, see
+    /// [`set_pointer`](PValue::set_pointer). `self` is left unchanged.
+    pub fn with_pointer(
+        &self,
+        pointer: &str,
+        value: impl Into<PValue>,
+    ) -> Result<PValue, PathError> {
+        let mut next = self.clone();
+        next.set_pointer(pointer, value)?;
+        Ok(next)
+    }
+
+    /// A new version with the value at `pointer` replaced by `f` applied to
+    /// it. `self` is left unchanged.
+    pub fn update_pointer<F>(&self, pointer: &str, f: F) -> Result<PValue, PathError>
+    where
+        F: FnOnce(&PValue) -> PValue,
+    {
+        let mut next = self.clone();
+        let slot = next
+            .pointer_mut(pointer)
+            .ok_or_else(|| not_found(pointer))?;
+        *slot = f(slot);
+        Ok(next)
+    }
+
+    /// A new version without the value at `pointer`. `self` is left
+    /// unchanged.
+    pub fn without_pointer(&self, pointer: &str) -> Result<PValue, PathError> {
+        let mut next = self.clone();
+        next.remove_pointer(pointer)?;
+        Ok(next)
+    }
+
+    fn child(&self, token: &str) -> Option<&PValue> {
+        match self {
+            PValue::Map(map) => map.get(token),
+            PValue::List(list) => list.get(parse_index(token)?),
+            _ => None,
+        }
+    }
+
+    fn child_mut(&mut self, token: &str) -> Option<&mut PValue> {
+        match self {
+            PValue::Map(map) => map.get_mut(token),
+            PValue::List(list) => list.get_mut(parse_index(token)?),
+            _ => None,
+        }
+    }
+
+    fn parent_mut(&mut self, pointer: &str, parents: &[String]) -> Result<&mut PValue, PathError> {
+        let mut current = self;
+        for (idx, token) in parents.iter().enumerate() {
+            if !matches!(current, PValue::Map(_) | PValue::List(_)) {
+                return Err(invalid_type(pointer, idx));
+            }
+            current = current
+                .child_mut(token)
+                .ok_or_else(|| not_found(&prefix(pointer, idx + 1)))?;
+        }
+        Ok(current)
+    }
+}
+
+/// The first `tokens` reference tokens of `pointer`.
+fn prefix(pointer: &str, tokens: usize) -> String {
+    match pointer.match_indices('/').nth(tokens) {
+        Some((end, _)) => pointer[..end].to_string(),
+        None => pointer.to_string(),
+    }
+}
+
+fn not_found(pointer: &str) -> PathError {
+    PathError::NotFound {
+        path: pointer.to_string(),
+    }
+}
+
+fn invalid_type(pointer: &str, tokens: usize) -> PathError {
+    PathError::InvalidType {
+        path: prefix(pointer, tokens),
+        expected: "map or list",
+    }
+}
+
+impl From<Value> for PValue {
+    fn from(value: Value) -> PValue {
+        match value {
+            Value::Bool(b) => PValue::Bool(b),
+            Value::Number(n) => PValue::Number(n),
+            Value::Char(c) => PValue::Char(c),
+            Value::String(s) => PValue::String(s.into()),
+            Value::List(list) => PValue::List(list.into_iter().map(PValue::from).collect()),
+            Value::Map(map) => PValue::Map(
+                map.into_iter()
+                    .map(|(k, v)| (Arc::from(k), PValue::from(v)))
+                    .collect(),
+            ),
+            Value::Dict(dict) => PValue::Dict(Arc::new(*dict)),
+            Value::Bytes(bytes) => PValue::Bytes(bytes.into()),
+            #[cfg(feature = "datetime")]
+            Value::Date(date) => PValue::Date(date),
+            #[cfg(feature = "datetime")]
+            Value::DateTime(datetime) => PValue::DateTime(datetime),
+            Value::None => PValue::None,
+        }
+    }
+}
+
+impl From<&PValue> for Value {
+    fn from(value: &PValue) -> Value {
+        match value {
+            PValue::Bool(b) => Value::Bool(*b),
+            PValue::Number(n) => Value::Number(*n),
+            PValue::Char(c) => Value::Char(*c),
+            PValue::String(s) => Value::String(s.to_string()),
+            PValue::List(list) => Value::List(list.iter().map(Value::from).collect()),
+            PValue::Map(map) => Value::Map(
+                map.iter()
+                    .map(|(k, v)| (k.to_string(), Value::from(v)))
+                    .collect(),
+            ),
+            PValue::Dict(dict) => Value::from((**dict).clone()),
+            PValue::Bytes(bytes) => Value::Bytes(bytes.to_vec()),
+            #[cfg(feature = "datetime")]
+            PValue::Date(date) => Value::Date(*date),
+            #[cfg(feature = "datetime")]
+            PValue::DateTime(datetime) => Value::DateTime(*datetime),
+            PValue::None => Value::None,
+        }
+    }
+}
+
+impl From<PValue> for Value {
+    fn from(value: PValue) -> Value {
+        Value::from(&value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Map;
+
+    fn document() -> PValue {
+        let mut user = Map::new();
+        user.insert("name", "Rasmus");
+        let mut doc = Map::new();
+        doc.insert("users", Value::List(vec![Value::Map(user)]));
+        doc.insert("settings", Value::Map(Map::new()));
+        doc.insert("a/b", 1u8);
+        PValue::from(Value::Map(doc))
+    }
+
+    #[test]
+    fn snapshots() {
+        let v1 = document();
+        let v2 = v1
+            .with_pointer("/users/0/name", PValue::String("Jane".into()))
+            .unwrap();
+        let v3 = v2.with_pointer("/users/-", PValue::None).unwrap();
+
+        assert_eq!(
+            v1.pointer("/users/0/name").and_then(PValue::as_str),
+            Some("Rasmus")
+        );
+        assert_eq!(
+            v2.pointer("/users/0/name").and_then(PValue::as_str),
+            Some("Jane")
+        );
+        assert_eq!(
+            v3.pointer("/users")
+                .and_then(PValue::as_list)
+                .map(|l| l.len()),
+            Some(2)
+        );
+        assert!(v1
+            .pointer("/settings")
+            .unwrap()
+            .ptr_eq(v2.pointer("/settings").unwrap()));
+
+        let v4 = v3.without_pointer("/a~1b").unwrap();
+        assert!(v3.pointer("/a~1b").is_some());
+        assert!(v4.pointer("/a~1b").is_none());
+
+        let value = Value::from(&v2);
+        assert_eq!(value["users"][0]["name"], Value::from("Jane"));
+        assert_eq!(PValue::from(value), v2);
+    }
+
+    #[test]
+    fn errors() {
+        let mut doc = document();
+        assert!(matches!(
+            doc.set_pointer("users", PValue::None),
+            Err(PathError::Syntax { .. })
+        ));
+        assert!(matches!(
+            doc.set_pointer("/missing/x", PValue::None),
+            Err(PathError::NotFound { path }) if path == "/missing"
+        ));
+        assert!(matches!(
+            doc.set_pointer("/a~1b/x", PValue::None),
+            Err(PathError::InvalidType { path, .. }) if path == "/a~1b"
+        ));
+        assert!(matches!(
+            doc.remove_pointer("/users/01"),
+            Err(PathError::NotFound { .. })
+        ));
+        assert!(doc.pointer("/a~2").is_none());
+    }
+}
@@ -0,0 +1,107 @@
+//! JSON Pointers (RFC 6901) like `/users/0/name`, the addressing scheme of
+//! JSON Schema and JSON Patch. `~1` escapes a `/` and `~0` a `~` in a token.
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+
+use crate::{PathError, Value};
+
+impl Value {
+    /// The value at a JSON Pointer. The empty pointer refers to the whole
+    /// value.
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        parse_pointer(pointer)
+            .ok()?
+            .iter()
+            .try_fold(self, |value, token| match value {
+                Value::Map(map) => map.get(token),
+                Value::List(list) => list.get(parse_index(token)?),
+                _ => None,
+            })
+    }
+
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+        parse_pointer(pointer)
+            .ok()?
+            .iter()
+            .try_fold(self, |value, token| match value {
+                Value::Map(map) => map.get_mut(token),
+                Value::List(list) => list.get_mut(parse_index(token)?),
+                _ => None,
+            })
+    }
+}
+
+/// Split a JSON Pointer into its unescaped reference tokens.
+pub fn parse_pointer(pointer: &str) -> Result<Vec<String>, PathError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !pointer.starts_with('/') {
+        return Err(PathError::Syntax {
+            position: 0,
+            message: "pointer must start with '/'",
+        });
+    }
+
+    let mut tokens = Vec::new();
+    let mut position = 1;
+    for token in pointer[1..].split('/') {
+        let mut out = String::with_capacity(token.len());
+        let mut chars = token.char_indices();
+        while let Some((idx, c)) = chars.next() {
+            if c != '~' {
+                out.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some((_, '0')) => out.push('~'),
+                Some((_, '1')) => out.push('/'),
+                _ => {
+                    return Err(PathError::Syntax {
+                        position: position + idx,
+                        message: "'~' must be followed by '0' or '1'",
+                    })
+                }
+            }
+        }
+        tokens.push(out);
+        position += token.len() + 1;
+    }
+    Ok(tokens)
+}
+
+/// A list index: digits without leading zeros.
+pub fn parse_index(token: &str) -> Option<usize> {
+    if token.is_empty()
+        || (token.len() > 1 && token.starts_with('0'))
+        || !token.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    token.parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    use super::*;
+    use crate::Map;
+
+    #[test]
+    fn pointer() {
+        let mut inner = Map::new();
+        inner.insert("a/b", 1u8);
+        inner.insert("m~n", 2u8);
+        let mut value = Value::List(vec![Value::Map(inner)]);
+
+        assert_eq!(value.pointer("/0/a~1b"), Some(&Value::from(1u8)));
+        assert_eq!(value.pointer("/0/m~0n"), Some(&Value::from(2u8)));
+        assert_eq!(value.pointer(""), Some(&value.clone()));
+        assert_eq!(value.pointer("/00"), None);
+        assert_eq!(value.pointer("0"), None);
+        *value.pointer_mut("/0/a~1b").unwrap() = Value::None;
+        assert!(value[0]["a/b"].is_none());
+    }
+}
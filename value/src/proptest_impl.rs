@@ -0,0 +1,203 @@
+use proptest::{
+    arbitrary::{any, Arbitrary},
+    collection::{btree_map, vec},
+    prelude::*,
+    strategy::{BoxedStrategy, Just, Union},
+};
+
+use crate::{Dict, GenOptions, Kind, Map, Number, Value};
+
+const KEY_PATTERN: &str = "\\PC{0,12}";
+const STRING_PATTERN: &str = "\\PC{0,32}";
+
+#[cfg(feature = "datetime")]
+fn date() -> impl Strategy<Value = chrono::NaiveDate> {
+    (1..=3_652_059i32).prop_map(|days| chrono::NaiveDate::from_num_days_from_ce_opt(days).unwrap())
+}
+
+#[cfg(feature = "datetime")]
+fn datetime() -> impl Strategy<Value = chrono::NaiveDateTime> {
+    (date(), 0..86_400u32).prop_map(|(date, secs)| {
+        date.and_time(chrono::NaiveTime::from_num_seconds_from_midnight_opt(secs, 0).unwrap())
+    })
+}
+
+impl GenOptions {
+    /// Strategy for the `f64`s in generated numbers. It never produces NaN,
+    /// which would break equality in round trip tests.
+    pub fn float64() -> impl Strategy<Value = f64> {
+        use proptest::num::f64::*;
+        POSITIVE | NEGATIVE | NORMAL | SUBNORMAL | ZERO
+    }
+
+    /// Like [`GenOptions::float64`], for `f32`.
+    pub fn float32() -> impl Strategy<Value = f32> {
+        use proptest::num::f32::*;
+        POSITIVE | NEGATIVE | NORMAL | SUBNORMAL | ZERO
+    }
+
+    /// Strategy producing values of a single `kind`. Lists and maps hold
+    /// scalars only; use [`GenOptions::strategy`] for nested values.
+    pub fn kind_strategy(&self, kind: Kind) -> BoxedStrategy<Value> {
+        let leaf = self.clone().with_max_depth(0);
+        match kind {
+            Kind::Bool => any::<bool>().prop_map(Value::Bool).boxed(),
+            Kind::Number => any::<Number>().prop_map(Value::Number).boxed(),
+            Kind::Char => any::<char>().prop_map(Value::Char).boxed(),
+            Kind::String => STRING_PATTERN.prop_map(Value::String).boxed(),
+            Kind::Bytes => vec(any::<u8>(), 0..=self.max_breadth)
+                .prop_map(Value::Bytes)
+                .boxed(),
+            Kind::None => Just(Value::None).boxed(),
+            Kind::List => self.list_strategy(leaf.strategy()),
+            Kind::Map => self
+                .map_strategy(leaf.strategy())
+                .prop_map(Value::Map)
+                .boxed(),
+            Kind::Dict => self
+                .dict_strategy(leaf.strategy())
+                .prop_map(Value::from)
+                .boxed(),
+            #[cfg(feature = "datetime")]
+            Kind::Date => date().prop_map(Value::Date).boxed(),
+            #[cfg(feature = "datetime")]
+            Kind::DateTime => datetime().prop_map(Value::DateTime).boxed(),
+        }
+    }
+
+    pub fn strategy(&self) -> BoxedStrategy<Value> {
+        let leaves = self
+            .kinds
+            .iter()
+            .filter(|k| !k.is_container())
+            .map(|k| self.kind_strategy(*k))
+            .collect::<Vec<_>>();
+
+        let leaf = if leaves.is_empty() {
+            Just(Value::None).boxed()
+        } else {
+            Union::new(leaves).boxed()
+        };
+
+        let containers = self
+            .kinds
+            .iter()
+            .filter(|k| k.is_container())
+            .copied()
+            .collect::<Vec<_>>();
+
+        if containers.is_empty() || self.max_depth == 0 {
+            return leaf;
+        }
+
+        let opts = self.clone();
+        let size = (self.max_breadth * self.max_depth).max(1) as u32;
+        leaf.prop_recursive(
+            self.max_depth as u32,
+            size * 4,
+            self.max_breadth as u32,
+            move |inner| {
+                let branches = containers
+                    .iter()
+                    .map(|kind| match kind {
+                        Kind::List => opts.list_strategy(inner.clone()),
+                        Kind::Dict => opts
+                            .dict_strategy(inner.clone())
+                            .prop_map(Value::from)
+                            .boxed(),
+                        _ => opts
+                            .map_strategy(inner.clone())
+                            .prop_map(Value::Map)
+                            .boxed(),
+                    })
+                    .collect::<Vec<_>>();
+                Union::new(branches)
+            },
+        )
+        .boxed()
+    }
+
+    pub fn list_strategy(&self, item: BoxedStrategy<Value>) -> BoxedStrategy<Value> {
+        vec(item, 0..=self.max_breadth)
+            .prop_map(Value::List)
+            .boxed()
+    }
+
+    pub fn map_strategy(&self, item: BoxedStrategy<Value>) -> BoxedStrategy<Map> {
+        btree_map(KEY_PATTERN, item, 0..=self.max_breadth)
+            .prop_map(Map::from)
+            .boxed()
+    }
+
+    /// Dicts keyed by numbers, with at least one entry so they don't turn
+    /// into maps on a serde round trip.
+    pub fn dict_strategy(&self, item: BoxedStrategy<Value>) -> BoxedStrategy<Dict> {
+        vec((any::<Number>(), item), 1..=self.max_breadth.max(1))
+            .prop_map(Dict::from_iter)
+            .boxed()
+    }
+}
+
+impl Arbitrary for Number {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Number>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        prop_oneof![
+            any::<u8>().prop_map(Number::U8),
+            any::<u16>().prop_map(Number::U16),
+            any::<u32>().prop_map(Number::U32),
+            any::<u64>().prop_map(Number::U64),
+            any::<i8>().prop_map(Number::I8),
+            any::<i16>().prop_map(Number::I16),
+            any::<i32>().prop_map(Number::I32),
+            any::<i64>().prop_map(Number::I64),
+            GenOptions::float32().prop_map(Number::from),
+            GenOptions::float64().prop_map(Number::from),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for Value {
+    type Parameters = GenOptions;
+    type Strategy = BoxedStrategy<Value>;
+
+    fn arbitrary_with(opts: Self::Parameters) -> Self::Strategy {
+        opts.strategy()
+    }
+}
+
+impl Arbitrary for Map {
+    type Parameters = GenOptions;
+    type Strategy = BoxedStrategy<Map>;
+
+    fn arbitrary_with(opts: Self::Parameters) -> Self::Strategy {
+        let inner = opts
+            .clone()
+            .with_max_depth(opts.max_depth.saturating_sub(1));
+        opts.map_strategy(inner.strategy())
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod test {
+    use super::*;
+    use crate::{from_value, to_value};
+
+    fn serde_options() -> GenOptions {
+        let opts = GenOptions::default();
+        // chrono serializes dates as strings, so they don't survive a round trip
+        #[cfg(feature = "datetime")]
+        let opts = opts.without_kind(Kind::Date).without_kind(Kind::DateTime);
+        opts
+    }
+
+    proptest! {
+        #[test]
+        fn serde_round_trip(value in any_with::<Value>(serde_options())) {
+            prop_assert_eq!(to_value(&value).unwrap(), value.clone());
+            prop_assert_eq!(from_value::<Value>(value.clone()).unwrap(), value);
+        }
+    }
+}
@@ -0,0 +1,541 @@
+//! Resolution of JSON Schema style `{"$ref": "..."}` references.
+//!
+//! A reference is a map with a `$ref` string, which stands for the value it
+//! points to; other keys next to `$ref` are ignored. References have the form
+//! `document#pointer`: an empty document refers to the document containing
+//! the reference, other documents are fetched through a [`Loader`], relative
+//! to the referencing document. The fragment is a JSON Pointer, see
+//! [`Value::pointer`].
+//!
+//! [`Resolver::resolve`] inlines every reference and fails on cycles and on
+//! results exceeding its [`Limits`], since a few references can expand to an
+//! exponentially large value, while
+//! [`Resolver::view`] follows references only as the document is walked, so
+//! recursive documents can be navigated too.
+#[cfg(not(feature = "std"))]
+use alloc::{
+    borrow::ToOwned,
+    collections::BTreeMap,
+    format,
+    rc::Rc,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{cell::RefCell, fmt};
+#[cfg(feature = "std")]
+use std::{collections::BTreeMap, rc::Rc};
+
+use crate::{pointer::parse_pointer, LimitError, Limits, Value};
+
+/// The key marking a reference.
+pub const REF_KEY: &str = "$ref";
+
+/// Fetches the external documents references point to.
+pub trait Loader {
+    /// Load the document at `uri`, as resolved against the referencing
+    /// document.
+    fn load(&self, uri: &str) -> Result<Value, LoadError>;
+}
+
+impl<F> Loader for F
+where
+    F: Fn(&str) -> Result<Value, LoadError>,
+{
+    fn load(&self, uri: &str) -> Result<Value, LoadError> {
+        self(uri)
+    }
+}
+
+/// A loader for documents held in memory, keyed by uri.
+impl Loader for BTreeMap<String, Value> {
+    fn load(&self, uri: &str) -> Result<Value, LoadError> {
+        self.get(uri)
+            .cloned()
+            .ok_or_else(|| LoadError::new("document not found"))
+    }
+}
+
+/// The default loader, which only allows references within the document.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoLoader;
+
+impl Loader for NoLoader {
+    fn load(&self, _uri: &str) -> Result<Value, LoadError> {
+        Err(LoadError::new("external references are not enabled"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadError {
+    message: String,
+}
+
+impl LoadError {
+    pub fn new(message: impl fmt::Display) -> LoadError {
+        LoadError {
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefError {
+    /// The fragment of a reference is not a JSON Pointer.
+    Invalid { reference: String },
+    /// A reference points at a value that doesn't exist.
+    NotFound { reference: String },
+    /// A reference leads back to itself. `chain` lists the absolute
+    /// references followed, ending with the repeated one.
+    Cycle { chain: Vec<String> },
+    /// An external document could not be loaded.
+    Load { uri: String, error: LoadError },
+    /// The resolved value exceeds the resolver's limits.
+    Limit(LimitError),
+}
+
+impl fmt::Display for RefError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RefError::Invalid { reference } => write!(f, "invalid reference: {}", reference),
+            RefError::NotFound { reference } => {
+                write!(f, "reference points nowhere: {}", reference)
+            }
+            RefError::Cycle { chain } => write!(f, "reference cycle: {}", chain.join(" -> ")),
+            RefError::Load { uri, error } => write!(f, "could not load {}: {}", uri, error),
+            RefError::Limit(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RefError {}
+
+struct Document {
+    uri: String,
+    value: Value,
+}
+
+/// Resolves references, caching every loaded document.
+pub struct Resolver<L = NoLoader> {
+    loader: L,
+    limits: Limits,
+    documents: RefCell<BTreeMap<String, Rc<Document>>>,
+}
+
+impl Resolver<NoLoader> {
+    pub fn new() -> Resolver<NoLoader> {
+        Resolver::with_loader(NoLoader)
+    }
+}
+
+impl Default for Resolver<NoLoader> {
+    fn default() -> Self {
+        Resolver::new()
+    }
+}
+
+impl<L: Loader> Resolver<L> {
+    pub fn with_loader(loader: L) -> Resolver<L> {
+        Resolver {
+            loader,
+            limits: Limits::default(),
+            documents: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Bound the depth and number of values of inlined results. Defaults to
+    /// [`Limits::default`].
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// A copy of `root` with every reference replaced by its target.
+    pub fn resolve(&self, root: &Value) -> Result<Value, RefError> {
+        self.resolve_in(root, "")
+    }
+
+    /// Like [`resolve`](Resolver::resolve), with relative references in
+    /// `root` resolved against `uri`.
+    pub fn resolve_in(&self, root: &Value, uri: &str) -> Result<Value, RefError> {
+        let doc = Rc::new(Document {
+            uri: uri.to_owned(),
+            value: root.clone(),
+        });
+        self.inline(&doc, &doc.value, &mut Vec::new(), &mut 0, 0)
+    }
+
+    /// A lazily resolving view of `root`.
+    pub fn view(&self, root: Value) -> RefNode<'_, L> {
+        self.view_in(root, "")
+    }
+
+    pub fn view_in(&self, root: Value, uri: &str) -> RefNode<'_, L> {
+        RefNode {
+            resolver: self,
+            doc: Rc::new(Document {
+                uri: uri.to_owned(),
+                value: root,
+            }),
+            pointer: String::new(),
+        }
+    }
+
+    /// Inline the references in `value`, which is at `depth` of the result.
+    /// `nodes` counts the values produced so far.
+    fn inline(
+        &self,
+        doc: &Rc<Document>,
+        value: &Value,
+        stack: &mut Vec<String>,
+        nodes: &mut usize,
+        depth: usize,
+    ) -> Result<Value, RefError> {
+        if reference(value).is_none() {
+            *nodes += 1;
+            self.limits.check_nodes(*nodes).map_err(RefError::Limit)?;
+            self.limits.check_depth(depth).map_err(RefError::Limit)?;
+        }
+        match value {
+            Value::Map(map) => {
+                match reference(value) {
+                    Some(reference) => {
+                        let (target_doc, pointer) = self.locate(doc, reference)?;
+                        let absolute = format!("{}#{}", target_doc.uri, pointer);
+                        if stack.contains(&absolute) {
+                            stack.push(absolute);
+                            return Err(RefError::Cycle {
+                                chain: core::mem::take(stack),
+                            });
+                        }
+                        let target = target_doc.value.pointer(&pointer).ok_or_else(|| {
+                            RefError::NotFound {
+                                reference: absolute.clone(),
+                            }
+                        })?;
+                        stack.push(absolute);
+                        let value = self.inline(&target_doc, target, stack, nodes, depth)?;
+                        stack.pop();
+                        Ok(value)
+                    }
+                    None => Ok(Value::Map(
+                        map.iter()
+                            .map(|(k, v)| {
+                                Ok((k.clone(), self.inline(doc, v, stack, nodes, depth + 1)?))
+                            })
+                            .collect::<Result<_, RefError>>()?,
+                    )),
+                }
+            }
+            Value::List(list) => Ok(Value::List(
+                list.iter()
+                    .map(|v| self.inline(doc, v, stack, nodes, depth + 1))
+                    .collect::<Result<_, _>>()?,
+            )),
+            value => Ok(value.clone()),
+        }
+    }
+
+    /// The document and pointer `reference`, found in `doc`, points to.
+    fn locate(
+        &self,
+        doc: &Rc<Document>,
+        reference: &str,
+    ) -> Result<(Rc<Document>, String), RefError> {
+        let (uri, fragment) = reference.split_once('#').unwrap_or((reference, ""));
+        if parse_pointer(fragment).is_err() {
+            return Err(RefError::Invalid {
+                reference: reference.to_owned(),
+            });
+        }
+        let fragment = fragment.to_owned();
+        if uri.is_empty() {
+            return Ok((doc.clone(), fragment));
+        }
+
+        let uri = join(&doc.uri, uri);
+        if let Some(doc) = self.documents.borrow().get(&uri) {
+            return Ok((doc.clone(), fragment));
+        }
+        let value = self.loader.load(&uri).map_err(|error| RefError::Load {
+            uri: uri.clone(),
+            error,
+        })?;
+        let doc = Rc::new(Document {
+            uri: uri.clone(),
+            value,
+        });
+        self.documents.borrow_mut().insert(uri, doc.clone());
+        Ok((doc, fragment))
+    }
+}
+
+impl<L> fmt::Debug for Resolver<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Resolver")
+            .field("documents", &self.documents.borrow().keys())
+            .finish()
+    }
+}
+
+/// A value within a document, reached through a [`Resolver::view`].
+/// References are followed when the node is reached, never before.
+pub struct RefNode<'r, L> {
+    resolver: &'r Resolver<L>,
+    doc: Rc<Document>,
+    pointer: String,
+}
+
+impl<'r, L: Loader> RefNode<'r, L> {
+    /// The value of this node. It is never a reference itself, but may
+    /// contain unresolved references.
+    pub fn value(&self) -> &Value {
+        self.doc
+            .value
+            .pointer(&self.pointer)
+            .expect("node pointers are valid")
+    }
+
+    /// Uri of the document containing this node.
+    pub fn uri(&self) -> &str {
+        &self.doc.uri
+    }
+
+    /// JSON Pointer of this node within its document.
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+
+    /// The map entry `key`, with references followed.
+    pub fn get(&self, key: &str) -> Result<Option<RefNode<'r, L>>, RefError> {
+        match self.value() {
+            Value::Map(map) if map.contains_key(key) => self.child(key).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// The list item at `idx`, with references followed.
+    pub fn index(&self, idx: usize) -> Result<Option<RefNode<'r, L>>, RefError> {
+        match self.value() {
+            Value::List(list) if idx < list.len() => self.child(&idx.to_string()).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// The subtree of this node with every reference inlined.
+    pub fn resolve(&self) -> Result<Value, RefError> {
+        let mut stack = Vec::new();
+        self.resolver
+            .inline(&self.doc, self.value(), &mut stack, &mut 0, 0)
+    }
+
+    fn child(&self, token: &str) -> Result<RefNode<'r, L>, RefError> {
+        let mut pointer = self.pointer.clone();
+        pointer.push('/');
+        pointer.push_str(&token.replace('~', "~0").replace('/', "~1"));
+        RefNode {
+            resolver: self.resolver,
+            doc: self.doc.clone(),
+            pointer,
+        }
+        .follow()
+    }
+
+    /// Follow references until a value that isn't one is reached.
+    fn follow(mut self) -> Result<RefNode<'r, L>, RefError> {
+        let mut chain = Vec::new();
+        while let Some(reference) = reference(self.value()) {
+            let (doc, pointer) = self.resolver.locate(&self.doc, reference)?;
+            let absolute = format!("{}#{}", doc.uri, pointer);
+            if doc.value.pointer(&pointer).is_none() {
+                return Err(RefError::NotFound {
+                    reference: absolute,
+                });
+            }
+            if chain.contains(&absolute) {
+                chain.push(absolute);
+                return Err(RefError::Cycle { chain });
+            }
+            chain.push(absolute);
+            self.doc = doc;
+            self.pointer = pointer;
+        }
+        Ok(self)
+    }
+}
+
+impl<L> fmt::Debug for RefNode<'_, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RefNode({}#{})", self.doc.uri, self.pointer)
+    }
+}
+
+/// The target of `value` if it is a reference.
+fn reference(value: &Value) -> Option<&str> {
+    match value {
+        Value::Map(map) => map.get(REF_KEY)?.as_string().map(String::as_str),
+        _ => None,
+    }
+}
+
+/// Resolve `uri` against the uri of the referencing document: absolute uris
+/// and paths are kept, relative ones replace the last path segment of `base`.
+fn join(base: &str, uri: &str) -> String {
+    if uri.contains("://") || uri.starts_with('/') {
+        return uri.to_owned();
+    }
+    match base.rfind('/') {
+        Some(idx) => format!("{}{}", &base[..=idx], uri),
+        None => uri.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    use super::*;
+    use crate::Map;
+
+    fn reference(target: &str) -> Value {
+        let mut map = Map::new();
+        map.insert(REF_KEY, target);
+        Value::Map(map)
+    }
+
+    fn schema() -> Value {
+        let mut address = Map::new();
+        address.insert("city", "Copenhagen");
+        let mut definitions = Map::new();
+        definitions.insert("address", address);
+        definitions.insert("person", reference("people.json#/person"));
+        definitions.insert("loop", reference("#/definitions/loop"));
+        let mut root = Map::new();
+        root.insert("definitions", definitions);
+        root.insert("home", reference("#/definitions/address"));
+        root.insert(
+            "people",
+            Value::List(vec![reference("#/definitions/person")]),
+        );
+        Value::Map(root)
+    }
+
+    fn loader() -> BTreeMap<String, Value> {
+        let mut person = Map::new();
+        person.insert("name", "Rasmus");
+        person.insert("friend", reference("#/person"));
+        let mut doc = Map::new();
+        doc.insert("person", person);
+        BTreeMap::from([("schemas/people.json".into(), Value::Map(doc))])
+    }
+
+    #[test]
+    fn inline() {
+        let resolver = Resolver::with_loader(loader());
+        let mut root = Map::new();
+        root.insert("person", reference("people.json#/person/name"));
+        root.insert("home", reference("#/address"));
+        root.insert("address", reference("#/city"));
+        root.insert("city", "Copenhagen");
+
+        let out = resolver
+            .resolve_in(&Value::Map(root), "schemas/root.json")
+            .unwrap();
+        assert_eq!(out["home"], Value::from("Copenhagen"));
+        assert_eq!(out["person"], Value::from("Rasmus"));
+
+        let err = resolver
+            .resolve_in(&schema(), "schemas/root.json")
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RefError::Cycle { chain } if chain == ["schemas/root.json#/definitions/loop"; 2]
+        ));
+
+        let mut root = Map::new();
+        root.insert("bad", reference("#/a~2b"));
+        assert!(matches!(
+            resolver.resolve(&Value::Map(root)),
+            Err(RefError::Invalid { reference }) if reference == "#/a~2b"
+        ));
+    }
+
+    #[test]
+    fn expansion() {
+        // Every level doubles the size of the one below.
+        let mut root = Map::new();
+        root.insert("l0", Value::List(vec![1u8.into(), 2u8.into()]));
+        for level in 1..40 {
+            let below = reference(&format!("#/l{}", level - 1));
+            root.insert(
+                format!("l{}", level),
+                Value::List(vec![below.clone(), below]),
+            );
+        }
+        let root = Value::Map(root);
+
+        let resolver = Resolver::new();
+        assert!(matches!(
+            resolver.resolve(&root),
+            Err(RefError::Limit(LimitError::Nodes(_)))
+        ));
+        let small = resolver.view(root.clone()).get("l3").unwrap().unwrap();
+        assert_eq!(small.resolve().unwrap().as_list().unwrap().len(), 2);
+        let resolver = Resolver::new().with_limits(Limits::default().with_max_nodes(10));
+        assert!(resolver
+            .view(root)
+            .get("l3")
+            .unwrap()
+            .unwrap()
+            .resolve()
+            .is_err());
+    }
+
+    #[test]
+    fn lazy() {
+        let resolver = Resolver::with_loader(loader());
+        let root = resolver.view_in(schema(), "schemas/root.json");
+
+        let friend = root
+            .get("people")
+            .unwrap()
+            .unwrap()
+            .index(0)
+            .unwrap()
+            .unwrap()
+            .get("friend")
+            .unwrap()
+            .unwrap()
+            .get("friend")
+            .unwrap()
+            .unwrap();
+        assert_eq!(friend.uri(), "schemas/people.json");
+        assert_eq!(friend.pointer(), "/person");
+        assert_eq!(friend.value()["name"], Value::from("Rasmus"));
+
+        let home = root.get("home").unwrap().unwrap().resolve().unwrap();
+        assert_eq!(home["city"], Value::from("Copenhagen"));
+
+        let definitions = root.get("definitions").unwrap().unwrap();
+        assert!(matches!(
+            definitions.get("loop"),
+            Err(RefError::Cycle { .. })
+        ));
+        assert!(matches!(
+            Resolver::new()
+                .view(schema())
+                .get("people")
+                .unwrap()
+                .unwrap()
+                .index(0),
+            Err(RefError::Load { .. })
+        ));
+    }
+}
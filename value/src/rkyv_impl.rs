@@ -0,0 +1,565 @@
+//! Zero-copy archives of values with rkyv.
+//!
+//! [`to_archive`] writes a value into an aligned buffer and
+//! [`from_archive`] validates such a buffer before handing out an
+//! [`ArchivedValue`] that reads straight from the bytes, so a memory-mapped
+//! file can be queried without deserializing it first.
+use core::{fmt, ops};
+
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, vec::Vec};
+use rkyv::util::AlignedVec;
+use rkyv::{
+    rancor::{self, Fallible, Source},
+    ser::{Allocator, Writer},
+    with::{ArchiveWith, DeserializeWith, SerializeWith},
+    Archive, Archived, Deserialize, Place, Resolver, Serialize,
+};
+
+use crate::{map::ArchivedMap, ArchivedNumber, ArchivedValue, Dict, Number, Value};
+
+/// An archive that failed to serialize, validate or deserialize.
+#[derive(Debug)]
+pub struct ArchiveError(rancor::Error);
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "archive error: {}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ArchiveError {}
+
+impl From<rancor::Error> for ArchiveError {
+    fn from(err: rancor::Error) -> Self {
+        ArchiveError(err)
+    }
+}
+
+/// Archive a value. The returned buffer is aligned for [`from_archive`].
+pub fn to_archive(value: &Value) -> Result<AlignedVec, ArchiveError> {
+    Ok(rkyv::to_bytes::<rancor::Error>(value)?)
+}
+
+/// Validate `bytes` and access the archived value in place.
+///
+/// Validation walks the whole archive once, checking every offset, length
+/// and tag, so the bytes may come from an untrusted source. `bytes` must be
+/// aligned to 16 bytes, which holds for [`AlignedVec`] and memory maps.
+pub fn from_archive(bytes: &[u8]) -> Result<&ArchivedValue, ArchiveError> {
+    Ok(rkyv::access::<ArchivedValue, rancor::Error>(bytes)?)
+}
+
+/// Access the archived value in `bytes` without validating it, for trusted
+/// caches where [`from_archive`]'s walk over the archive is too slow.
+///
+/// # Safety
+///
+/// `bytes` must be an archive written by [`to_archive`], unmodified since,
+/// and aligned like for [`from_archive`]. Anything else is undefined
+/// behavior.
+pub unsafe fn from_archive_unchecked(bytes: &[u8]) -> &ArchivedValue {
+    rkyv::access_unchecked::<ArchivedValue>(bytes)
+}
+
+impl ArchivedValue {
+    /// Look up `key` in a map, or a dict with string keys.
+    pub fn get(&self, key: &str) -> Option<&ArchivedValue> {
+        match self {
+            ArchivedValue::Map(map) => map.get(key),
+            ArchivedValue::Dict(dict) => dict
+                .iter()
+                .find(|entry| entry.0.as_str() == Some(key))
+                .map(|entry| &entry.1),
+            _ => None,
+        }
+    }
+
+    pub fn get_index(&self, idx: usize) -> Option<&ArchivedValue> {
+        self.as_list()?.get(idx)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ArchivedValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<Number> {
+        match self {
+            ArchivedValue::Number(n) => Some(n.to_number()),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ArchivedValue::String(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            ArchivedValue::Bytes(bytes) => Some(bytes.as_slice()),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[ArchivedValue]> {
+        match self {
+            ArchivedValue::List(list) => Some(list.as_slice()),
+            _ => None,
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&ArchivedMap> {
+        match self {
+            ArchivedValue::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        matches!(self, ArchivedValue::None)
+    }
+
+    /// Iterate the items of a list, or the values of a map or dict.
+    pub fn iter(&self) -> impl Iterator<Item = &ArchivedValue> + '_ {
+        let (list, map, dict): (&[ArchivedValue], _, &[_]) = match self {
+            ArchivedValue::List(list) => (list.as_slice(), None, &[]),
+            ArchivedValue::Map(map) => (&[], Some(map.inner.values()), &[]),
+            ArchivedValue::Dict(dict) => (&[], None, dict.as_slice()),
+            _ => (&[], None, &[]),
+        };
+        list.iter()
+            .chain(map.into_iter().flatten())
+            .chain(dict.iter().map(|entry| &entry.1))
+    }
+
+    /// Deserialize into an owned [`Value`]. Fails on dates out of chrono's
+    /// range, which validation doesn't catch.
+    pub fn to_value(&self) -> Result<Value, ArchiveError> {
+        Ok(rkyv::deserialize::<Value, rancor::Error>(self)?)
+    }
+}
+
+impl ops::Index<&str> for ArchivedValue {
+    type Output = ArchivedValue;
+
+    fn index(&self, key: &str) -> &ArchivedValue {
+        self.get(key).unwrap_or(&ArchivedValue::None)
+    }
+}
+
+impl ops::Index<usize> for ArchivedValue {
+    type Output = ArchivedValue;
+
+    fn index(&self, idx: usize) -> &ArchivedValue {
+        self.get_index(idx).unwrap_or(&ArchivedValue::None)
+    }
+}
+
+/// Compares in place, without deserializing. Like for [`Dict`]s, dict
+/// entries may be in any order.
+impl PartialEq<Value> for ArchivedValue {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (ArchivedValue::Bool(a), Value::Bool(b)) => a == b,
+            (ArchivedValue::Number(a), Value::Number(b)) => a.to_number() == *b,
+            (ArchivedValue::Char(a), Value::Char(b)) => a.to_native() == *b,
+            (ArchivedValue::String(a), Value::String(b)) => a.as_str() == b,
+            (ArchivedValue::Bytes(a), Value::Bytes(b)) => a.as_slice() == b.as_slice(),
+            (ArchivedValue::List(a), Value::List(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a == b)
+            }
+            (ArchivedValue::Map(a), Value::Map(b)) => {
+                a.len() == b.len()
+                    && b.iter()
+                        .all(|(k, v)| a.get(k).is_some_and(|value| value == v))
+            }
+            // Keys are deserialized to be looked up in the dict's index, they
+            // are small compared to the values.
+            (ArchivedValue::Dict(a), Value::Dict(b)) => {
+                a.len() == b.len()
+                    && a.iter().all(|entry| {
+                        entry
+                            .0
+                            .to_value()
+                            .ok()
+                            .and_then(|key| b.get(&key))
+                            .is_some_and(|value| entry.1 == *value)
+                    })
+            }
+            #[cfg(feature = "datetime")]
+            (ArchivedValue::Date(a), Value::Date(b)) => {
+                use chrono::Datelike;
+                a.to_native() == b.num_days_from_ce()
+            }
+            #[cfg(feature = "datetime")]
+            (ArchivedValue::DateTime(a), Value::DateTime(b)) => {
+                use chrono::{Datelike, Timelike};
+                let time = b.time();
+                a.0.to_native() == b.date().num_days_from_ce()
+                    && a.1.to_native() == time.num_seconds_from_midnight()
+                    && a.2.to_native() == time.nanosecond()
+            }
+            (ArchivedValue::None, Value::None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl ArchivedMap {
+    pub fn get(&self, key: &str) -> Option<&ArchivedValue> {
+        self.inner.get(key)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.inner.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Entries in key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ArchivedValue)> + '_ {
+        self.inner.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> + '_ {
+        self.inner.keys().map(|k| k.as_str())
+    }
+}
+
+impl ArchivedNumber {
+    pub fn to_number(&self) -> Number {
+        match *self {
+            ArchivedNumber::U8(n) => Number::U8(n),
+            ArchivedNumber::U16(n) => Number::U16(n.to_native()),
+            ArchivedNumber::U32(n) => Number::U32(n.to_native()),
+            ArchivedNumber::U64(n) => Number::U64(n.to_native()),
+            ArchivedNumber::I8(n) => Number::I8(n),
+            ArchivedNumber::I16(n) => Number::I16(n.to_native()),
+            ArchivedNumber::I32(n) => Number::I32(n.to_native()),
+            ArchivedNumber::I64(n) => Number::I64(n.to_native()),
+            ArchivedNumber::F32(n) => Number::from(n.to_native()),
+            ArchivedNumber::F64(n) => Number::from(n.to_native()),
+        }
+    }
+}
+
+/// Archives a boxed [`Dict`] as its list of entries; the key index is rebuilt
+/// when deserializing.
+pub(crate) struct DictEntries;
+
+type Entries = Vec<(Value, Value)>;
+
+impl ArchiveWith<Box<Dict>> for DictEntries {
+    type Archived = Archived<Entries>;
+    type Resolver = Resolver<Entries>;
+
+    fn resolve_with(field: &Box<Dict>, resolver: Self::Resolver, out: Place<Self::Archived>) {
+        field.entries.resolve(resolver, out);
+    }
+}
+
+impl<S> SerializeWith<Box<Dict>, S> for DictEntries
+where
+    S: Fallible + Writer + Allocator + ?Sized,
+    S::Error: Source,
+{
+    fn serialize_with(field: &Box<Dict>, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        field.entries.serialize(serializer)
+    }
+}
+
+impl<D> DeserializeWith<Archived<Entries>, Box<Dict>, D> for DictEntries
+where
+    D: Fallible + ?Sized,
+    D::Error: Source,
+{
+    fn deserialize_with(
+        field: &Archived<Entries>,
+        deserializer: &mut D,
+    ) -> Result<Box<Dict>, D::Error> {
+        let entries: Entries = field.deserialize(deserializer)?;
+        Ok(Box::new(entries.into_iter().collect()))
+    }
+}
+
+#[cfg(feature = "ordered_float")]
+pub(crate) use self::float::Float;
+
+#[cfg(feature = "ordered_float")]
+mod float {
+    use ordered_float_lib::OrderedFloat;
+    use rkyv::{
+        rancor::Fallible,
+        with::{ArchiveWith, DeserializeWith, SerializeWith},
+        Archive, Archived, Deserialize, Place, Serialize,
+    };
+
+    /// Archives an `OrderedFloat<T>` as a plain `T`.
+    pub struct Float;
+
+    impl<T: Archive> ArchiveWith<OrderedFloat<T>> for Float {
+        type Archived = Archived<T>;
+        type Resolver = T::Resolver;
+
+        fn resolve_with(field: &OrderedFloat<T>, resolver: T::Resolver, out: Place<Archived<T>>) {
+            field.0.resolve(resolver, out);
+        }
+    }
+
+    impl<T, S> SerializeWith<OrderedFloat<T>, S> for Float
+    where
+        T: Serialize<S>,
+        S: Fallible + ?Sized,
+    {
+        fn serialize_with(
+            field: &OrderedFloat<T>,
+            serializer: &mut S,
+        ) -> Result<T::Resolver, S::Error> {
+            field.0.serialize(serializer)
+        }
+    }
+
+    impl<T, D> DeserializeWith<Archived<T>, OrderedFloat<T>, D> for Float
+    where
+        T: Archive,
+        Archived<T>: Deserialize<T, D>,
+        D: Fallible + ?Sized,
+    {
+        fn deserialize_with(
+            field: &Archived<T>,
+            deserializer: &mut D,
+        ) -> Result<OrderedFloat<T>, D::Error> {
+            Ok(OrderedFloat(field.deserialize(deserializer)?))
+        }
+    }
+}
+
+#[cfg(feature = "datetime")]
+pub(crate) use self::datetime::{Date, DateTime};
+
+#[cfg(feature = "datetime")]
+mod datetime {
+    use core::fmt;
+
+    use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+    use rkyv::{
+        rancor::{Fallible, Source},
+        with::{ArchiveWith, DeserializeWith, SerializeWith},
+        Archive, Archived, Place,
+    };
+
+    /// Archives a `NaiveDate` as days since the common era.
+    pub struct Date;
+
+    /// Archives a `NaiveDateTime` as days since the common era, seconds
+    /// since midnight and nanoseconds.
+    pub struct DateTime;
+
+    #[derive(Debug)]
+    struct InvalidDate;
+
+    impl fmt::Display for InvalidDate {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "archived date out of range")
+        }
+    }
+
+    impl core::error::Error for InvalidDate {}
+
+    type Parts = (i32, u32, u32);
+
+    impl ArchiveWith<NaiveDate> for Date {
+        type Archived = Archived<i32>;
+        type Resolver = ();
+
+        fn resolve_with(field: &NaiveDate, _: (), out: Place<Archived<i32>>) {
+            field.num_days_from_ce().resolve((), out);
+        }
+    }
+
+    impl<S: Fallible + ?Sized> SerializeWith<NaiveDate, S> for Date {
+        fn serialize_with(_: &NaiveDate, _: &mut S) -> Result<(), S::Error> {
+            Ok(())
+        }
+    }
+
+    impl<D> DeserializeWith<Archived<i32>, NaiveDate, D> for Date
+    where
+        D: Fallible + ?Sized,
+        D::Error: Source,
+    {
+        fn deserialize_with(field: &Archived<i32>, _: &mut D) -> Result<NaiveDate, D::Error> {
+            NaiveDate::from_num_days_from_ce_opt(field.to_native())
+                .ok_or_else(|| D::Error::new(InvalidDate))
+        }
+    }
+
+    impl ArchiveWith<NaiveDateTime> for DateTime {
+        type Archived = Archived<Parts>;
+        type Resolver = ((), (), ());
+
+        fn resolve_with(field: &NaiveDateTime, _: ((), (), ()), out: Place<Archived<Parts>>) {
+            let time = field.time();
+            let parts: Parts = (
+                field.date().num_days_from_ce(),
+                time.num_seconds_from_midnight(),
+                time.nanosecond(),
+            );
+            parts.resolve(((), (), ()), out);
+        }
+    }
+
+    impl<S: Fallible + ?Sized> SerializeWith<NaiveDateTime, S> for DateTime {
+        fn serialize_with(_: &NaiveDateTime, _: &mut S) -> Result<((), (), ()), S::Error> {
+            Ok(((), (), ()))
+        }
+    }
+
+    impl<D> DeserializeWith<Archived<Parts>, NaiveDateTime, D> for DateTime
+    where
+        D: Fallible + ?Sized,
+        D::Error: Source,
+    {
+        fn deserialize_with(field: &Archived<Parts>, _: &mut D) -> Result<NaiveDateTime, D::Error> {
+            let date = NaiveDate::from_num_days_from_ce_opt(field.0.to_native());
+            let time = NaiveTime::from_num_seconds_from_midnight_opt(
+                field.1.to_native(),
+                field.2.to_native(),
+            );
+            match (date, time) {
+                (Some(date), Some(time)) => Ok(date.and_time(time)),
+                _ => Err(D::Error::new(InvalidDate)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::{vec, vec::Vec};
+
+    use super::*;
+    use crate::Map;
+
+    fn sample() -> Value {
+        let mut map = Map::new();
+        map.insert("name", "cache");
+        map.insert("size", 42u64);
+        map.insert("ratio", 0.5f64);
+        map.insert(
+            "items",
+            Value::List(vec![
+                Value::from(1i8),
+                Value::None,
+                Value::Bytes(vec![1, 2]),
+            ]),
+        );
+        Value::Map(map)
+    }
+
+    #[test]
+    fn round_trip() {
+        let value = sample();
+        let bytes = to_archive(&value).unwrap();
+        let archived = from_archive(&bytes).unwrap();
+
+        assert_eq!(archived["name"].as_str(), Some("cache"));
+        assert_eq!(archived["size"].as_number(), Some(Number::U64(42)));
+        assert_eq!(archived["items"][2].as_bytes(), Some(&[1u8, 2][..]));
+        assert!(archived["items"][1].is_none());
+        assert!(archived["missing"][0].is_none());
+        assert_eq!(archived["items"].iter().count(), 3);
+        assert_eq!(
+            archived.as_map().unwrap().keys().collect::<Vec<_>>(),
+            ["items", "name", "ratio", "size"]
+        );
+        assert_eq!(archived.to_value().unwrap(), value);
+        assert_eq!(*archived, value);
+        // SAFETY: `bytes` was just written by `to_archive`.
+        let unchecked = unsafe { from_archive_unchecked(&bytes) };
+        assert_eq!(*unchecked, value);
+
+        let mut other = value.clone();
+        other["items"] = Value::List(vec![Value::from(1i8), Value::None]);
+        assert_ne!(*archived, other);
+        other["items"] = value["items"].clone();
+        other["size"] = Value::from(43u64);
+        assert_ne!(*archived, other);
+
+        let dict = Value::from(
+            vec![
+                (Value::from(2u8), Value::from("b")),
+                (Value::from("a"), value),
+            ]
+            .into_iter()
+            .collect::<Dict>(),
+        );
+        let bytes = to_archive(&dict).unwrap();
+        let archived = from_archive(&bytes).unwrap();
+        let Value::Dict(entries) = &dict else {
+            unreachable!()
+        };
+        let reversed = Value::from(
+            entries
+                .iter()
+                .rev()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect::<Dict>(),
+        );
+        assert_eq!(*archived, reversed);
+        assert_ne!(
+            *archived,
+            Value::from(
+                entries
+                    .iter()
+                    .take(1)
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect::<Dict>()
+            )
+        );
+    }
+
+    #[cfg(feature = "datetime")]
+    #[test]
+    fn dates() {
+        let day = chrono::NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        let value = Value::List(vec![
+            Value::Date(day),
+            Value::DateTime(day.and_hms_nano_opt(10, 30, 0, 5).unwrap()),
+        ]);
+        let bytes = to_archive(&value).unwrap();
+        assert_eq!(from_archive(&bytes).unwrap().to_value().unwrap(), value);
+        assert_eq!(*from_archive(&bytes).unwrap(), value);
+        assert_ne!(
+            from_archive(&bytes).unwrap()[0],
+            Value::Date(day.succ_opt().unwrap())
+        );
+    }
+
+    #[test]
+    fn rejects_corrupt_archives() {
+        let bytes = to_archive(&sample()).unwrap();
+        assert!(from_archive(&bytes[..bytes.len() - 4]).is_err());
+
+        let mut corrupt = AlignedVec::<16>::new();
+        corrupt.extend_from_slice(&bytes);
+        // The root sits at the end of the archive, starting with its tag.
+        let root = corrupt.len() - core::mem::size_of::<ArchivedValue>();
+        corrupt[root] = 0xff;
+        assert!(from_archive(&corrupt).is_err());
+    }
+}
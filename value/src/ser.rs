@@ -1,4 +1,4 @@
-use crate::{number::Number, Map};
+use crate::{dict::MapBuilder, number::Number, Dict, Map};
 
 use super::value::Value;
 #[cfg(not(feature = "std"))]
@@ -50,6 +50,7 @@ impl ser::Serialize for Value {
             // Value::Option(Some(ref v)) => s.serialize_some(v),
             Value::List(ref v) => v.serialize(s),
             Value::Map(ref v) => v.serialize(s),
+            Value::Dict(ref v) => v.serialize(s),
             Value::Bytes(ref v) => s.serialize_bytes(v),
             #[cfg(feature = "datetime")]
             Value::Date(v) => v.serialize(s),
@@ -86,10 +87,147 @@ impl ser::Serialize for Number {
 }
 
 pub fn to_value<T: ser::Serialize>(value: T) -> Result<Value, SerializerError> {
-    value.serialize(Serializer)
+    value.serialize(Serializer::default())
 }
 
-struct Serializer;
+/// Like [`to_value`], configured by `options`.
+pub fn to_value_with<T: ser::Serialize + ?Sized>(
+    value: &T,
+    options: SerializerOptions,
+) -> Result<Value, SerializerError> {
+    value.serialize(Serializer::new(options))
+}
+
+/// How enum variants are represented, after serde's attributes of the same
+/// name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnumTagging {
+    /// `{"Variant": content}`, and unit variants as the string `"Variant"`.
+    #[default]
+    External,
+    /// `{tag: "Variant", ..fields}`. Newtype variants must hold a map or a
+    /// struct, and tuple variants are an error.
+    Internal { tag: &'static str },
+    /// `{tag: "Variant", content: content}`, without `content` for unit
+    /// variants.
+    Adjacent {
+        tag: &'static str,
+        content: &'static str,
+    },
+}
+
+/// What byte sequences become.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ByteRepr {
+    /// `serialize_bytes` becomes [`Value::Bytes`] and a `Vec<u8>`, which
+    /// serde serializes as a sequence, a [`Value::List`].
+    #[default]
+    Preserve,
+    /// Non-empty sequences of `u8` become [`Value::Bytes`] as well. An
+    /// empty `Vec<u8>` can't be told apart from other empty sequences and
+    /// stays a list.
+    Bytes,
+    /// Bytes become a [`Value::List`] of `U8` as well.
+    List,
+}
+
+/// Options for [`to_value_with`]. The default matches [`to_value`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SerializerOptions {
+    pub(crate) tagging: EnumTagging,
+    pub(crate) skip_none_fields: bool,
+    pub(crate) bytes: ByteRepr,
+    pub(crate) human_readable: bool,
+}
+
+impl Default for SerializerOptions {
+    fn default() -> Self {
+        SerializerOptions {
+            tagging: EnumTagging::External,
+            skip_none_fields: false,
+            bytes: ByteRepr::Preserve,
+            human_readable: true,
+        }
+    }
+}
+
+impl SerializerOptions {
+    pub fn with_tagging(mut self, tagging: EnumTagging) -> Self {
+        self.tagging = tagging;
+        self
+    }
+
+    /// Leave struct fields serialized as `None` out of the map.
+    pub fn with_skip_none_fields(mut self, skip: bool) -> Self {
+        self.skip_none_fields = skip;
+        self
+    }
+
+    pub fn with_bytes(mut self, bytes: ByteRepr) -> Self {
+        self.bytes = bytes;
+        self
+    }
+
+    /// Reported by `Serializer::is_human_readable`. Types like chrono's
+    /// dates and uuids serialize as strings when set, which is the default,
+    /// and in a compact form otherwise.
+    pub fn with_human_readable(mut self, human_readable: bool) -> Self {
+        self.human_readable = human_readable;
+        self
+    }
+}
+
+/// Serializes any `Serialize` type into a [`Value`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Serializer {
+    options: SerializerOptions,
+}
+
+impl Serializer {
+    pub fn new(options: SerializerOptions) -> Serializer {
+        Serializer { options }
+    }
+
+    /// A variant with its content, `None` for unit variants.
+    fn variant(
+        &self,
+        variant: &'static str,
+        content: Option<Value>,
+    ) -> Result<Value, SerializerError> {
+        let mut map = Map::new();
+        match self.options.tagging {
+            EnumTagging::External => match content {
+                Some(content) => {
+                    map.insert(variant, content);
+                }
+                None => return Ok(Value::String(variant.to_string())),
+            },
+            EnumTagging::Internal { tag } => {
+                match content {
+                    Some(Value::Map(fields)) => map = fields,
+                    Some(Value::None) | None => {}
+                    Some(_) => {
+                        return Err(ser::Error::custom(format_args!(
+                            "cannot serialize internally tagged variant {} holding a non-map",
+                            variant
+                        )))
+                    }
+                }
+                map.insert(tag, variant);
+            }
+            EnumTagging::Adjacent {
+                tag,
+                content: content_key,
+            } => {
+                map.insert(tag, variant);
+                if let Some(content) = content {
+                    map.insert(content_key, content);
+                }
+            }
+        }
+        Ok(Value::Map(map))
+    }
+}
 
 impl ser::Serializer for Serializer {
     type Ok = Value;
@@ -155,7 +293,10 @@ impl ser::Serializer for Serializer {
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Bytes(v.to_vec()))
+        match self.options.bytes {
+            ByteRepr::List => Ok(Value::List(v.iter().map(|&b| Value::from(b)).collect())),
+            ByteRepr::Preserve | ByteRepr::Bytes => Ok(Value::Bytes(v.to_vec())),
+        }
     }
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
@@ -166,7 +307,7 @@ impl ser::Serializer for Serializer {
     where
         T: ser::Serialize,
     {
-        value.serialize(Serializer)
+        value.serialize(self)
     }
 
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
@@ -183,7 +324,7 @@ impl ser::Serializer for Serializer {
         _variant_index: u32,
         variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::String(variant.to_string()))
+        self.variant(variant, None)
     }
 
     fn serialize_newtype_struct<T: ?Sized>(
@@ -194,7 +335,7 @@ impl ser::Serializer for Serializer {
     where
         T: ser::Serialize,
     {
-        value.serialize(Serializer)
+        value.serialize(self)
     }
 
     fn serialize_newtype_variant<T: ?Sized>(
@@ -207,19 +348,16 @@ impl ser::Serializer for Serializer {
     where
         T: ser::Serialize,
     {
-        value.serialize(Serializer).map(|v| {
-            let mut map = BTreeMap::new();
-            map.insert(variant.to_string(), v);
-            Value::Map(map.into())
-        })
+        let content = value.serialize(self)?;
+        self.variant(variant, Some(content))
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        Ok(SerializeSeq(vec![]))
+        Ok(SerializeSeq(self, vec![]))
     }
 
     fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
-        Ok(SerializeTuple(vec![]))
+        Ok(SerializeTuple(self, vec![]))
     }
 
     fn serialize_tuple_struct(
@@ -227,7 +365,7 @@ impl ser::Serializer for Serializer {
         _name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleStruct, Self::Error> {
-        Ok(SerializeTupleStruct(vec![]))
+        Ok(SerializeTupleStruct(self, vec![]))
     }
 
     fn serialize_tuple_variant(
@@ -237,15 +375,23 @@ impl ser::Serializer for Serializer {
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        if let EnumTagging::Internal { .. } = self.options.tagging {
+            return Err(ser::Error::custom(format_args!(
+                "cannot serialize tuple variant {} internally tagged",
+                variant
+            )));
+        }
         Ok(SerializeTupleVariant(
-            variant.to_string(),
+            self,
+            variant,
             Vec::with_capacity(len),
         ))
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
         Ok(SerializeMap {
-            map: BTreeMap::new(),
+            ser: self,
+            map: MapBuilder::new(),
             key: None,
         })
     }
@@ -255,7 +401,7 @@ impl ser::Serializer for Serializer {
         _name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        Ok(SerializeStruct(BTreeMap::new()))
+        Ok(SerializeStruct(self, BTreeMap::new()))
     }
 
     fn serialize_struct_variant(
@@ -265,11 +411,15 @@ impl ser::Serializer for Serializer {
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        Ok(SerializeStructVariant(variant.to_string(), BTreeMap::new()))
+        Ok(SerializeStructVariant(self, variant, BTreeMap::new()))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.options.human_readable
     }
 }
 
-struct SerializeSeq(Vec<Value>);
+pub struct SerializeSeq(Serializer, Vec<Value>);
 
 impl ser::SerializeSeq for SerializeSeq {
     type Ok = Value;
@@ -279,17 +429,30 @@ impl ser::SerializeSeq for SerializeSeq {
     where
         T: ser::Serialize,
     {
-        let value = value.serialize(Serializer)?;
-        self.0.push(value);
+        let value = value.serialize(self.0)?;
+        self.1.push(value);
         Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::List(self.0))
+        let is_bytes = |list: &[Value]| {
+            !list.is_empty()
+                && list
+                    .iter()
+                    .all(|v| matches!(v, Value::Number(Number::U8(_))))
+        };
+        if self.0.options.bytes == ByteRepr::Bytes && is_bytes(&self.1) {
+            let bytes = self.1.iter().map(|v| match v {
+                Value::Number(Number::U8(b)) => *b,
+                _ => unreachable!(),
+            });
+            return Ok(Value::Bytes(bytes.collect()));
+        }
+        Ok(Value::List(self.1))
     }
 }
 
-struct SerializeTuple(Vec<Value>);
+pub struct SerializeTuple(Serializer, Vec<Value>);
 
 impl ser::SerializeTuple for SerializeTuple {
     type Ok = Value;
@@ -299,17 +462,17 @@ impl ser::SerializeTuple for SerializeTuple {
     where
         T: ser::Serialize,
     {
-        let value = value.serialize(Serializer)?;
-        self.0.push(value);
+        let value = value.serialize(self.0)?;
+        self.1.push(value);
         Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::List(self.0))
+        Ok(Value::List(self.1))
     }
 }
 
-struct SerializeTupleStruct(Vec<Value>);
+pub struct SerializeTupleStruct(Serializer, Vec<Value>);
 
 impl ser::SerializeTupleStruct for SerializeTupleStruct {
     type Ok = Value;
@@ -319,17 +482,17 @@ impl ser::SerializeTupleStruct for SerializeTupleStruct {
     where
         T: ser::Serialize,
     {
-        let value = value.serialize(Serializer)?;
-        self.0.push(value);
+        let value = value.serialize(self.0)?;
+        self.1.push(value);
         Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::List(self.0))
+        Ok(Value::List(self.1))
     }
 }
 
-struct SerializeTupleVariant(String, Vec<Value>);
+pub struct SerializeTupleVariant(Serializer, &'static str, Vec<Value>);
 
 impl ser::SerializeTupleVariant for SerializeTupleVariant {
     type Ok = Value;
@@ -339,21 +502,20 @@ impl ser::SerializeTupleVariant for SerializeTupleVariant {
     where
         T: ser::Serialize,
     {
-        let value = value.serialize(Serializer)?;
-        self.1.push(value);
+        let value = value.serialize(self.0)?;
+        self.2.push(value);
         Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        let mut map = BTreeMap::new();
-        map.insert(self.0, Value::List(self.1));
-        Ok(Value::Map(map.into()))
+        self.0.variant(self.1, Some(Value::List(self.2)))
     }
 }
 
-struct SerializeMap {
-    map: BTreeMap<String, Value>,
-    key: Option<String>,
+pub struct SerializeMap {
+    ser: Serializer,
+    map: MapBuilder,
+    key: Option<Value>,
 }
 
 impl ser::SerializeMap for SerializeMap {
@@ -364,9 +526,7 @@ impl ser::SerializeMap for SerializeMap {
     where
         T: ser::Serialize,
     {
-        let key = key.serialize(Serializer)?;
-        // FIXME: Dont unwrap
-        self.key = Some(key.into_string().unwrap());
+        self.key = Some(key.serialize(self.ser)?);
         Ok(())
     }
 
@@ -374,17 +534,26 @@ impl ser::SerializeMap for SerializeMap {
     where
         T: ser::Serialize,
     {
-        let value = value.serialize(Serializer)?;
-        self.map.insert(self.key.take().unwrap(), value);
+        let key = match self.key.take() {
+            Some(key) => key,
+            None => {
+                return Err(ser::Error::custom(
+                    "serialize_value called before serialize_key",
+                ))
+            }
+        };
+        let value = value.serialize(self.ser)?;
+        self.map.insert(key, value);
         Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Map(self.map.into()))
+        // Keep string keyed maps as `Map` and only fall back to `Dict` when needed
+        Ok(self.map.into_value())
     }
 }
 
-struct SerializeStruct(BTreeMap<String, Value>);
+pub struct SerializeStruct(Serializer, BTreeMap<String, Value>);
 
 impl ser::SerializeStruct for SerializeStruct {
     type Ok = Value;
@@ -398,18 +567,19 @@ impl ser::SerializeStruct for SerializeStruct {
     where
         T: ser::Serialize,
     {
-        let key = key.to_string();
-        let value = value.serialize(Serializer)?;
-        self.0.insert(key, value);
+        let value = value.serialize(self.0)?;
+        if !(self.0.options.skip_none_fields && value.is_none()) {
+            self.1.insert(key.to_string(), value);
+        }
         Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(Value::Map(self.0.into()))
+        Ok(Value::Map(self.1.into()))
     }
 }
 
-struct SerializeStructVariant(String, BTreeMap<String, Value>);
+pub struct SerializeStructVariant(Serializer, &'static str, BTreeMap<String, Value>);
 
 impl ser::SerializeStructVariant for SerializeStructVariant {
     type Ok = Value;
@@ -423,16 +593,429 @@ impl ser::SerializeStructVariant for SerializeStructVariant {
     where
         T: ser::Serialize,
     {
-        let key = key.to_string();
-        let value = value.serialize(Serializer)?;
-        self.1.insert(key, value);
+        let value = value.serialize(self.0)?;
+        if !(self.0.options.skip_none_fields && value.is_none()) {
+            self.2.insert(key.to_string(), value);
+        }
         Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        let mut map = BTreeMap::new();
-        map.insert(self.0, Value::Map(self.1.into()));
-        Ok(Value::Map(map.into()))
+        self.0.variant(self.1, Some(Value::Map(self.2.into())))
+    }
+}
+
+/// Serialize `value` over `target`. When `value` is a struct or a map and
+/// `target` already holds a [`Map`], the produced keys are written into it
+/// directly and every other key is kept. Otherwise `target` is replaced,
+/// including by a map with keys that aren't strings.
+pub fn to_value_into<T: ser::Serialize + ?Sized>(
+    value: &T,
+    target: &mut Value,
+) -> Result<(), SerializerError> {
+    value.serialize(IntoSerializer {
+        ser: Serializer::default(),
+        target: Target::Value(target),
+    })
+}
+
+/// Serialize the fields of the struct or map `value` into `map`,
+/// overwriting only the keys it produces. Any other type is an error and
+/// leaves `map` as it was. A map key that isn't a string is an error too,
+/// and like an error in a field may leave `map` partially updated.
+pub fn merge_serialize<T: ser::Serialize + ?Sized>(
+    value: &T,
+    map: &mut Map,
+) -> Result<(), SerializerError> {
+    value.serialize(IntoSerializer {
+        ser: Serializer::default(),
+        target: Target::Map(map),
+    })
+}
+
+enum Target<'a> {
+    Value(&'a mut Value),
+    Map(&'a mut Map),
+}
+
+/// Writes into an existing value instead of returning a new one.
+struct IntoSerializer<'a> {
+    ser: Serializer,
+    target: Target<'a>,
+}
+
+impl<'a> IntoSerializer<'a> {
+    /// The value to replace, for anything that can't be merged.
+    fn replace(self) -> Result<&'a mut Value, SerializerError> {
+        match self.target {
+            Target::Value(value) => Ok(value),
+            Target::Map(_) => Err(ser::Error::custom("can only merge a struct or a map")),
+        }
+    }
+
+    /// The map to merge into, if there is one.
+    fn merge(self) -> Result<&'a mut Map, &'a mut Value> {
+        match self.target {
+            Target::Map(map) | Target::Value(Value::Map(map)) => Ok(map),
+            Target::Value(value) => Err(value),
+        }
+    }
+}
+
+macro_rules! replace {
+    ($($method: ident($($arg: ident: $ty: ty),*);)*) => {
+        $(
+            fn $method(self, $($arg: $ty),*) -> Result<(), SerializerError> {
+                let ser = self.ser;
+                let target = self.replace()?;
+                *target = ser::Serializer::$method(ser, $($arg),*)?;
+                Ok(())
+            }
+        )*
+    };
+}
+
+impl<'a> ser::Serializer for IntoSerializer<'a> {
+    type Ok = ();
+    type Error = SerializerError;
+    type SerializeSeq = Assign<'a, SerializeSeq>;
+    type SerializeTuple = Assign<'a, SerializeTuple>;
+    type SerializeTupleStruct = Assign<'a, SerializeTupleStruct>;
+    type SerializeTupleVariant = Assign<'a, SerializeTupleVariant>;
+    type SerializeMap = MergeMap<'a>;
+    type SerializeStruct = MergeStruct<'a>;
+    type SerializeStructVariant = Assign<'a, SerializeStructVariant>;
+
+    replace! {
+        serialize_bool(v: bool);
+        serialize_i8(v: i8);
+        serialize_i16(v: i16);
+        serialize_i32(v: i32);
+        serialize_i64(v: i64);
+        serialize_u8(v: u8);
+        serialize_u16(v: u16);
+        serialize_u32(v: u32);
+        serialize_u64(v: u64);
+        serialize_f32(v: f32);
+        serialize_f64(v: f64);
+        serialize_char(v: char);
+        serialize_str(v: &str);
+        serialize_bytes(v: &[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(name: &'static str);
+        serialize_unit_variant(name: &'static str, index: u32, variant: &'static str);
+    }
+
+    fn serialize_some<T: ?Sized + ser::Serialize>(self, value: &T) -> Result<(), Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + ser::Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + ser::Serialize>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        let ser = self.ser;
+        let target = self.replace()?;
+        *target = ser::Serializer::serialize_newtype_variant(ser, name, index, variant, value)?;
+        Ok(())
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        let ser = self.ser;
+        Assign::new(self.replace()?, ser::Serializer::serialize_seq(ser, len))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        let ser = self.ser;
+        Assign::new(self.replace()?, ser::Serializer::serialize_tuple(ser, len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        let ser = self.ser;
+        Assign::new(
+            self.replace()?,
+            ser::Serializer::serialize_tuple_struct(ser, name, len),
+        )
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        let ser = self.ser;
+        Assign::new(
+            self.replace()?,
+            ser::Serializer::serialize_tuple_variant(ser, name, index, variant, len),
+        )
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        let state = match &self.target {
+            Target::Map(_) | Target::Value(Value::Map(_)) => MapState::Merge {
+                key: None,
+                written: Vec::new(),
+            },
+            Target::Value(_) => MapState::Build(ser::Serializer::serialize_map(self.ser, len)?),
+        };
+        Ok(MergeMap {
+            ser: self.ser,
+            target: self.target,
+            state,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        let ser = self.ser;
+        match self.merge() {
+            Ok(map) => Ok(MergeStruct::Merge { ser, map }),
+            Err(target) => Assign::new(target, ser::Serializer::serialize_struct(ser, name, len))
+                .map(MergeStruct::Assign),
+        }
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        let ser = self.ser;
+        Assign::new(
+            self.replace()?,
+            ser::Serializer::serialize_struct_variant(ser, name, index, variant, len),
+        )
+    }
+
+    fn is_human_readable(&self) -> bool {
+        ser::Serializer::is_human_readable(&self.ser)
+    }
+}
+
+/// Builds a value with the regular serializer and stores it in `target`.
+struct Assign<'a, S> {
+    target: &'a mut Value,
+    inner: S,
+}
+
+impl<'a, S> Assign<'a, S> {
+    fn new(
+        target: &'a mut Value,
+        inner: Result<S, SerializerError>,
+    ) -> Result<Self, SerializerError> {
+        Ok(Assign {
+            target,
+            inner: inner?,
+        })
+    }
+}
+
+macro_rules! assign_impl {
+    ($($trait: ident::$method: ident),*) => {
+        $(
+            impl<S: ser::$trait<Ok = Value, Error = SerializerError>> ser::$trait for Assign<'_, S> {
+                type Ok = ();
+                type Error = SerializerError;
+
+                fn $method<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+                    self.inner.$method(value)
+                }
+
+                fn end(self) -> Result<(), Self::Error> {
+                    let value = self.inner.end();
+                    *self.target = value?;
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+assign_impl!(
+    SerializeSeq::serialize_element,
+    SerializeTuple::serialize_element,
+    SerializeTupleStruct::serialize_field,
+    SerializeTupleVariant::serialize_field
+);
+
+impl ser::SerializeStructVariant for Assign<'_, SerializeStructVariant> {
+    type Ok = ();
+    type Error = SerializerError;
+
+    fn serialize_field<T: ?Sized + ser::Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.inner.serialize_field(key, value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        let value = ser::SerializeStructVariant::end(self.inner);
+        *self.target = value?;
+        Ok(())
+    }
+}
+
+struct MergeMap<'a> {
+    ser: Serializer,
+    target: Target<'a>,
+    state: MapState,
+}
+
+enum MapState {
+    /// Writing into the target map. `written` are the keys produced so far,
+    /// tracked for a `Value` target in case a later key isn't a string.
+    Merge {
+        key: Option<String>,
+        written: Vec<String>,
+    },
+    /// Building a new value to replace the target with.
+    Build(SerializeMap),
+}
+
+impl MergeMap<'_> {
+    fn map(&mut self) -> &mut Map {
+        match &mut self.target {
+            Target::Map(map) => map,
+            Target::Value(value) => match &mut **value {
+                Value::Map(map) => map,
+                _ => unreachable!("only maps are merged into"),
+            },
+        }
+    }
+
+    /// Switch from merging to replacing the target, moving the entries
+    /// produced so far out of the map.
+    fn build(&mut self, key: Value) -> Result<(), SerializerError> {
+        let written = match &mut self.state {
+            MapState::Merge { written, .. } if matches!(self.target, Target::Value(_)) => {
+                core::mem::take(written)
+            }
+            _ => return Err(ser::Error::custom("can only merge maps with string keys")),
+        };
+        let mut map = MapBuilder::new();
+        for k in written {
+            if let Some(v) = self.map().remove(&k) {
+                map.insert(Value::String(k), v);
+            }
+        }
+        self.state = MapState::Build(SerializeMap {
+            ser: self.ser,
+            map,
+            key: Some(key),
+        });
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for MergeMap<'_> {
+    type Ok = ();
+    type Error = SerializerError;
+
+    fn serialize_key<T: ?Sized + ser::Serialize>(&mut self, key: &T) -> Result<(), Self::Error> {
+        let track = matches!(self.target, Target::Value(_));
+        match &mut self.state {
+            MapState::Merge { key: slot, written } => match key.serialize(self.ser)? {
+                Value::String(key) => {
+                    if track {
+                        written.push(key.clone());
+                    }
+                    *slot = Some(key);
+                    Ok(())
+                }
+                key => self.build(key),
+            },
+            MapState::Build(inner) => inner.serialize_key(key),
+        }
+    }
+
+    fn serialize_value<T: ?Sized + ser::Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        let key = match &mut self.state {
+            MapState::Merge { key, .. } => match key.take() {
+                Some(key) => key,
+                None => {
+                    return Err(ser::Error::custom(
+                        "serialize_value called before serialize_key",
+                    ))
+                }
+            },
+            MapState::Build(inner) => return inner.serialize_value(value),
+        };
+        let value = value.serialize(self.ser)?;
+        self.map().inner.insert(key, value);
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        match (self.state, self.target) {
+            (MapState::Build(inner), Target::Value(target)) => {
+                *target = ser::SerializeMap::end(inner)?;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+enum MergeStruct<'a> {
+    Merge { ser: Serializer, map: &'a mut Map },
+    Assign(Assign<'a, SerializeStruct>),
+}
+
+impl ser::SerializeStruct for MergeStruct<'_> {
+    type Ok = ();
+    type Error = SerializerError;
+
+    fn serialize_field<T: ?Sized + ser::Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        match self {
+            MergeStruct::Merge { ser, map } => {
+                map.inner.insert(key.to_string(), value.serialize(*ser)?);
+                Ok(())
+            }
+            MergeStruct::Assign(assign) => assign.inner.serialize_field(key, value),
+        }
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        match self {
+            MergeStruct::Merge { .. } => Ok(()),
+            MergeStruct::Assign(assign) => {
+                let value = ser::SerializeStruct::end(assign.inner);
+                *assign.target = value?;
+                Ok(())
+            }
+        }
     }
 }
 
@@ -449,3 +1032,165 @@ impl ser::Serialize for Map {
         map.end()
     }
 }
+
+impl ser::Serialize for Dict {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        use ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (k, v) in self.iter() {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+    use serde::Serialize;
+
+    use super::*;
+
+    #[derive(Serialize)]
+    enum Shape {
+        Empty,
+        Circle { radius: u8 },
+        Named(Map),
+        Pair(u8, u8),
+    }
+
+    #[derive(Serialize)]
+    struct Record {
+        name: &'static str,
+        note: Option<&'static str>,
+        data: Vec<u8>,
+    }
+
+    #[test]
+    fn tagging() {
+        let internal =
+            SerializerOptions::default().with_tagging(EnumTagging::Internal { tag: "type" });
+        let value = to_value_with(&Shape::Circle { radius: 2 }, internal).unwrap();
+        assert_eq!(value["type"], Value::from("Circle"));
+        assert_eq!(value["radius"], Value::from(2u8));
+        let mut named = Map::new();
+        named.insert("n", 1u8);
+        let value = to_value_with(&Shape::Named(named), internal).unwrap();
+        assert_eq!(value["type"], Value::from("Named"));
+        assert_eq!(value["n"], Value::from(1u8));
+        assert_eq!(
+            to_value_with(&Shape::Empty, internal).unwrap()["type"],
+            Value::from("Empty")
+        );
+        assert!(to_value_with(&Shape::Pair(1, 2), internal).is_err());
+
+        let adjacent = SerializerOptions::default().with_tagging(EnumTagging::Adjacent {
+            tag: "t",
+            content: "c",
+        });
+        let value = to_value_with(&Shape::Pair(1, 2), adjacent).unwrap();
+        assert_eq!(value["t"], Value::from("Pair"));
+        assert_eq!(value["c"], Value::List(vec![1u8.into(), 2u8.into()]));
+        let value = to_value_with(&Shape::Empty, adjacent).unwrap();
+        assert!(value.as_map().unwrap().get("c").is_none());
+
+        let value = to_value(Shape::Empty).unwrap();
+        assert_eq!(value, Value::from("Empty"));
+    }
+
+    #[test]
+    fn fields_and_bytes() {
+        let record = Record {
+            name: "a",
+            note: None,
+            data: vec![1, 2],
+        };
+        let value = to_value(&record).unwrap();
+        assert!(value["note"].is_none());
+        assert!(value.as_map().unwrap().contains_key("note"));
+        assert_eq!(value["data"], Value::List(vec![1u8.into(), 2u8.into()]));
+
+        let options = SerializerOptions::default()
+            .with_skip_none_fields(true)
+            .with_bytes(ByteRepr::Bytes);
+        let value = to_value_with(&record, options).unwrap();
+        assert!(!value.as_map().unwrap().contains_key("note"));
+        assert_eq!(value["data"], Value::Bytes(vec![1, 2]));
+
+        let list = SerializerOptions::default().with_bytes(ByteRepr::List);
+        let value = to_value_with(&Value::Bytes(vec![3]), list).unwrap();
+        assert_eq!(value, Value::List(vec![3u8.into()]));
+    }
+
+    #[test]
+    fn human_readable() {
+        struct Probe;
+
+        impl Serialize for Probe {
+            fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let human_readable = serializer.is_human_readable();
+                serializer.serialize_bool(human_readable)
+            }
+        }
+
+        assert_eq!(to_value(Probe).unwrap(), Value::from(true));
+        let compact = SerializerOptions::default().with_human_readable(false);
+        assert_eq!(to_value_with(&Probe, compact).unwrap(), Value::from(false));
+    }
+
+    #[test]
+    fn into_existing() {
+        #[derive(Serialize)]
+        struct Patch {
+            name: &'static str,
+            port: u16,
+        }
+
+        let mut doc = Map::new();
+        doc.insert("name", "old");
+        doc.insert("other", true);
+        let mut value = Value::Map(doc.clone());
+        let patch = Patch {
+            name: "new",
+            port: 80,
+        };
+
+        to_value_into(&patch, &mut value).unwrap();
+        assert_eq!(value["name"], Value::from("new"));
+        assert_eq!(value["port"], Value::from(80u16));
+        assert_eq!(value["other"], Value::from(true));
+
+        merge_serialize(&patch, &mut doc).unwrap();
+        assert_eq!(Value::Map(doc.clone()), value);
+        assert!(merge_serialize(&1u8, &mut doc).is_err());
+        assert_eq!(Value::Map(doc), value);
+
+        to_value_into(&Shape::Pair(1, 2), &mut value).unwrap();
+        assert_eq!(value, to_value(Shape::Pair(1, 2)).unwrap());
+        to_value_into(&patch, &mut value).unwrap();
+        assert_eq!(value["Pair"], Value::List(vec![1u8.into(), 2u8.into()]));
+        assert_eq!(value["port"], Value::from(80u16));
+
+        let mut value = Value::from(true);
+        to_value_into(&patch, &mut value).unwrap();
+        assert_eq!(value, to_value(&patch).unwrap());
+
+        let numbered = BTreeMap::from([(1u8, "one"), (2u8, "two")]);
+        let mixed = vec![
+            (Value::from("port"), Value::from(1u8)),
+            (Value::from(2u8), Value::from("two")),
+        ];
+        for keys in [
+            to_value(&numbered).unwrap(),
+            Value::from(mixed.into_iter().collect::<Dict>()),
+        ] {
+            to_value_into(&keys, &mut value).unwrap();
+            assert_eq!(value, keys);
+            assert!(merge_serialize(&keys, &mut Map::new()).is_err());
+        }
+    }
+}
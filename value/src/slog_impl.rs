@@ -0,0 +1,230 @@
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+use core::fmt;
+use slog::{Key, Record, SerdeValue, Serializer, KV};
+#[cfg(feature = "std")]
+use std::boxed::Box;
+
+use crate::{
+    redact::{RedactedValue, Redactor},
+    Map, Number, Value,
+};
+
+impl slog::Value for Number {
+    fn serialize(
+        &self,
+        _record: &Record<'_>,
+        key: Key,
+        serializer: &mut dyn Serializer,
+    ) -> slog::Result {
+        match *self {
+            Number::U8(v) => serializer.emit_u8(key, v),
+            Number::U16(v) => serializer.emit_u16(key, v),
+            Number::U32(v) => serializer.emit_u32(key, v),
+            Number::U64(v) => serializer.emit_u64(key, v),
+            Number::I8(v) => serializer.emit_i8(key, v),
+            Number::I16(v) => serializer.emit_i16(key, v),
+            Number::I32(v) => serializer.emit_i32(key, v),
+            Number::I64(v) => serializer.emit_i64(key, v),
+            #[cfg(feature = "ordered_float")]
+            Number::F32(v) => serializer.emit_f32(key, *v),
+            #[cfg(feature = "ordered_float")]
+            Number::F64(v) => serializer.emit_f64(key, *v),
+            #[cfg(not(feature = "ordered_float"))]
+            Number::F32(v) => serializer.emit_f32(key, v),
+            #[cfg(not(feature = "ordered_float"))]
+            Number::F64(v) => serializer.emit_f64(key, v),
+        }
+    }
+}
+
+impl slog::Value for Value {
+    fn serialize(
+        &self,
+        record: &Record<'_>,
+        key: Key,
+        serializer: &mut dyn Serializer,
+    ) -> slog::Result {
+        match self {
+            Value::Bool(v) => serializer.emit_bool(key, *v),
+            Value::Number(n) => slog::Value::serialize(n, record, key, serializer),
+            Value::Char(v) => serializer.emit_char(key, *v),
+            Value::String(v) => serializer.emit_str(key, v),
+            Value::None => serializer.emit_none(key),
+            _ => serializer.emit_serde(key, self),
+        }
+    }
+}
+
+impl SerdeValue for Value {
+    fn serialize_fallback(&self, key: Key, serializer: &mut dyn Serializer) -> slog::Result {
+        serializer.emit_arguments(key, &format_args!("{:?}", self))
+    }
+
+    fn as_serde(&self) -> &dyn erased_serde::Serialize {
+        self
+    }
+
+    fn to_sendable(&self) -> Box<dyn SerdeValue + Send + 'static> {
+        Box::new(self.clone())
+    }
+}
+
+impl slog::Value for Map {
+    fn serialize(
+        &self,
+        _record: &Record<'_>,
+        key: Key,
+        serializer: &mut dyn Serializer,
+    ) -> slog::Result {
+        serializer.emit_serde(key, self)
+    }
+}
+
+impl SerdeValue for Map {
+    fn serialize_fallback(&self, key: Key, serializer: &mut dyn Serializer) -> slog::Result {
+        serializer.emit_arguments(key, &format_args!("{:?}", self))
+    }
+
+    fn as_serde(&self) -> &dyn erased_serde::Serialize {
+        self
+    }
+
+    fn to_sendable(&self) -> Box<dyn SerdeValue + Send + 'static> {
+        Box::new(self.clone())
+    }
+}
+
+/// Every entry of the map is emitted as its own key on the record.
+impl KV for Map {
+    fn serialize(&self, record: &Record<'_>, serializer: &mut dyn Serializer) -> slog::Result {
+        for (k, v) in self.iter() {
+            slog::Value::serialize(v, record, Key::from(k.clone()), serializer)?;
+        }
+        Ok(())
+    }
+}
+
+impl Redactor {
+    /// Wrap `value` so it is redacted when logged as a slog value.
+    pub fn slog<'a>(&'a self, value: &'a Value) -> RedactedValue<'a> {
+        self.display(value)
+    }
+
+    /// Wrap `map` so it is redacted when logged, either as a single slog
+    /// value or with every entry as its own key like `Map`'s `KV` impl.
+    pub fn slog_kv<'a>(&'a self, map: &'a Map) -> RedactedMap<'a> {
+        RedactedMap {
+            redactor: self,
+            map,
+        }
+    }
+}
+
+/// A borrowed `Map` redacted by a [`Redactor`] when logged, see
+/// [`Redactor::slog_kv`].
+#[derive(Clone, Copy)]
+pub struct RedactedMap<'a> {
+    redactor: &'a Redactor,
+    map: &'a Map,
+}
+
+impl RedactedMap<'_> {
+    pub fn to_map(&self) -> Map {
+        self.redactor.redact_map(self.map)
+    }
+}
+
+impl fmt::Debug for RedactedMap<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_map(), f)
+    }
+}
+
+impl slog::Value for RedactedMap<'_> {
+    fn serialize(
+        &self,
+        record: &Record<'_>,
+        key: Key,
+        serializer: &mut dyn Serializer,
+    ) -> slog::Result {
+        slog::Value::serialize(&self.to_map(), record, key, serializer)
+    }
+}
+
+impl KV for RedactedMap<'_> {
+    fn serialize(&self, record: &Record<'_>, serializer: &mut dyn Serializer) -> slog::Result {
+        KV::serialize(&self.to_map(), record, serializer)
+    }
+}
+
+impl<'a> slog::Value for RedactedValue<'a> {
+    fn serialize(
+        &self,
+        record: &Record<'_>,
+        key: Key,
+        serializer: &mut dyn Serializer,
+    ) -> slog::Result {
+        slog::Value::serialize(&self.to_value(), record, key, serializer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::REDACTED;
+
+    struct Collect(Vec<(String, String)>);
+
+    impl Serializer for Collect {
+        fn emit_arguments(&mut self, key: Key, val: &fmt::Arguments) -> slog::Result {
+            self.0.push((key.to_string(), val.to_string()));
+            Ok(())
+        }
+    }
+
+    /// The key value pairs emitted by `f`.
+    fn log<F>(f: F) -> Vec<(String, String)>
+    where
+        F: FnOnce(&Record<'_>, &mut dyn Serializer) -> slog::Result,
+    {
+        let static_record = slog::record_static!(slog::Level::Info, "");
+        let mut collect = Collect(Vec::new());
+        f(
+            &Record::new(&static_record, &format_args!(""), slog::b!()),
+            &mut collect,
+        )
+        .unwrap();
+        collect.0
+    }
+
+    #[test]
+    fn redacts_nested_keys() {
+        let mut user = Map::default();
+        user.insert("name", "Rasmus");
+        user.insert("Password", "secret");
+
+        let mut args = Map::default();
+        args.insert("token", "abc");
+        args.insert("users", Value::List(vec![Value::Map(user)]));
+
+        let redactor = Redactor::new().keys(["password", "token"]);
+        let redacted = redactor.slog_kv(&args).to_map();
+
+        assert_eq!(redacted["token"], Value::from(REDACTED));
+        let user = redacted["users"].as_list().unwrap()[0].as_map().unwrap();
+        assert_eq!(user["Password"], Value::from(REDACTED));
+        assert_eq!(user["name"], Value::from("Rasmus"));
+
+        let logged =
+            log(|record, serializer| KV::serialize(&redactor.slog_kv(&args), record, serializer));
+        assert!(logged.contains(&("token".into(), REDACTED.into())));
+        assert!(logged.iter().all(|(_, v)| !v.contains("secret")));
+
+        let token = Value::from("abc");
+        let logged = log(|record, serializer| {
+            slog::Value::serialize(&redactor.slog(&token), record, "token".into(), serializer)
+        });
+        assert_eq!(logged, [("token".into(), "abc".into())]);
+    }
+}
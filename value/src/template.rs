@@ -0,0 +1,399 @@
+//! `${path}` placeholders in string values, filled in from a context value.
+//!
+//! A placeholder names a [`Path`] into the context. When a string consists of
+//! a single placeholder the referenced value is used as is, keeping its type;
+//! otherwise the referenced scalars are formatted into the string.
+//! `${path:-default}` falls back to `default` when the path is missing or
+//! `None`, and `$$` is a literal `$`, so `$${x}` renders as `${x}`.
+#[cfg(not(feature = "std"))]
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, fmt::Write, mem};
+
+use crate::{Dict, Path, PathError, Segment, Value};
+
+#[derive(Debug)]
+pub enum TemplateError {
+    /// A placeholder is malformed. `position` is the byte offset in the string.
+    Syntax {
+        location: Path,
+        position: usize,
+        message: &'static str,
+    },
+    /// The placeholder's path does not exist in the context and has no default.
+    Unresolved { location: Path, placeholder: String },
+    /// The placeholder is part of a larger string but refers to a list, map
+    /// or bytes value.
+    NotScalar { location: Path, placeholder: String },
+}
+
+impl TemplateError {
+    /// Path of the templated string within the rendered value.
+    pub fn location(&self) -> &Path {
+        match self {
+            TemplateError::Syntax { location, .. }
+            | TemplateError::Unresolved { location, .. }
+            | TemplateError::NotScalar { location, .. } => location,
+        }
+    }
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::Syntax {
+                location,
+                position,
+                message,
+            } => write!(
+                f,
+                "{}: invalid placeholder at {}: {}",
+                location, position, message
+            ),
+            TemplateError::Unresolved {
+                location,
+                placeholder,
+            } => write!(
+                f,
+                "{}: unresolved placeholder ${{{}}}",
+                location, placeholder
+            ),
+            TemplateError::NotScalar {
+                location,
+                placeholder,
+            } => write!(
+                f,
+                "{}: placeholder ${{{}}} cannot be formatted into a string",
+                location, placeholder
+            ),
+        }
+    }
+}
+
+/// Every placeholder that could not be rendered.
+#[derive(Debug)]
+pub struct RenderError {
+    errors: Vec<TemplateError>,
+}
+
+impl RenderError {
+    pub fn errors(&self) -> &[TemplateError] {
+        &self.errors
+    }
+
+    pub fn into_errors(self) -> Vec<TemplateError> {
+        self.errors
+    }
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, error) in self.errors.iter().enumerate() {
+            if idx > 0 {
+                f.write_str("; ")?;
+            }
+            error.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RenderError {}
+
+impl Value {
+    /// Substitute the `${path}` placeholders in every string of this value
+    /// with values from `context`, see the [module docs](crate::template).
+    ///
+    /// Map keys are left untouched. All failing placeholders are collected
+    /// before an error is returned.
+    pub fn render(&self, context: &Value) -> Result<Value, RenderError> {
+        let mut renderer = Renderer {
+            context,
+            location: Vec::new(),
+            errors: Vec::new(),
+        };
+        let value = renderer.render(self);
+        if renderer.errors.is_empty() {
+            Ok(value)
+        } else {
+            Err(RenderError {
+                errors: renderer.errors,
+            })
+        }
+    }
+}
+
+enum Part<'a> {
+    Text(String),
+    Placeholder {
+        path: &'a str,
+        /// The byte offset of `path` in the template.
+        start: usize,
+        default: Option<&'a str>,
+    },
+}
+
+struct Renderer<'a> {
+    context: &'a Value,
+    location: Vec<Segment>,
+    errors: Vec<TemplateError>,
+}
+
+impl<'a> Renderer<'a> {
+    fn render(&mut self, value: &Value) -> Value {
+        match value {
+            Value::String(s) => self.render_str(s),
+            Value::List(list) => Value::List(
+                list.iter()
+                    .enumerate()
+                    .map(|(idx, item)| self.nested(Segment::Index(idx), item))
+                    .collect(),
+            ),
+            Value::Map(map) => Value::Map(
+                map.iter()
+                    .map(|(k, v)| (k.clone(), self.nested(Segment::Key(k.clone()), v)))
+                    .collect(),
+            ),
+            Value::Dict(dict) => Value::from(
+                dict.iter()
+                    .map(|(k, v)| {
+                        let segment = match k {
+                            Value::String(s) => Segment::Key(s.clone()),
+                            k => Segment::Key(format!("{:?}", k)),
+                        };
+                        (k.clone(), self.nested(segment, v))
+                    })
+                    .collect::<Dict>(),
+            ),
+            value => value.clone(),
+        }
+    }
+
+    fn nested(&mut self, segment: Segment, value: &Value) -> Value {
+        self.location.push(segment);
+        let value = self.render(value);
+        self.location.pop();
+        value
+    }
+
+    fn location(&self) -> Path {
+        self.location.iter().cloned().collect()
+    }
+
+    fn render_str(&mut self, template: &str) -> Value {
+        let parts = match parse(template) {
+            Ok(parts) => parts,
+            Err((position, message)) => {
+                self.errors.push(TemplateError::Syntax {
+                    location: self.location(),
+                    position,
+                    message,
+                });
+                return Value::String(template.to_string());
+            }
+        };
+
+        if let [Part::Placeholder {
+            path,
+            start,
+            default,
+        }] = parts.as_slice()
+        {
+            return match self.lookup(path, *start, *default) {
+                Some(Ok(value)) => value.clone(),
+                Some(Err(default)) => Value::String(default.to_string()),
+                None => Value::None,
+            };
+        }
+
+        let mut out = String::with_capacity(template.len());
+        for part in parts {
+            let (path, start, default) = match part {
+                Part::Text(text) => {
+                    out.push_str(&text);
+                    continue;
+                }
+                Part::Placeholder {
+                    path,
+                    start,
+                    default,
+                } => (path, start, default),
+            };
+            let value = match self.lookup(path, start, default) {
+                Some(Ok(value)) => value,
+                Some(Err(default)) => {
+                    out.push_str(default);
+                    continue;
+                }
+                None => continue,
+            };
+            match value {
+                Value::String(s) => out.push_str(s),
+                Value::Char(c) => out.push(*c),
+                Value::Bool(b) => write!(out, "{}", b).unwrap(),
+                Value::Number(n) => write!(out, "{}", n).unwrap(),
+                #[cfg(feature = "datetime")]
+                Value::Date(date) => write!(out, "{}", date).unwrap(),
+                #[cfg(feature = "datetime")]
+                Value::DateTime(datetime) => write!(out, "{}", datetime).unwrap(),
+                Value::None => {}
+                _ => self.errors.push(TemplateError::NotScalar {
+                    location: self.location(),
+                    placeholder: path.to_string(),
+                }),
+            }
+        }
+        Value::String(out)
+    }
+
+    /// Resolve a placeholder to a context value (`Ok`) or its default (`Err`).
+    /// Errors are recorded and yield `None`. `start` is the offset of `path`
+    /// in the template, so syntax errors point into the template.
+    fn lookup<'b>(
+        &mut self,
+        path: &str,
+        start: usize,
+        default: Option<&'b str>,
+    ) -> Option<Result<&'a Value, &'b str>> {
+        let found = match Path::parse(path) {
+            Ok(parsed) => parsed.get(self.context).ok(),
+            Err(PathError::Syntax { position, message }) => {
+                self.errors.push(TemplateError::Syntax {
+                    location: self.location(),
+                    position: start + position,
+                    message,
+                });
+                return None;
+            }
+            Err(_) => None,
+        };
+
+        match (found, default) {
+            (Some(value), Some(default)) if value.is_none() => Some(Err(default)),
+            (Some(value), _) => Some(Ok(value)),
+            (None, Some(default)) => Some(Err(default)),
+            (None, None) => {
+                self.errors.push(TemplateError::Unresolved {
+                    location: self.location(),
+                    placeholder: path.to_string(),
+                });
+                None
+            }
+        }
+    }
+}
+
+/// Split a template into literal text and placeholders, resolving `$$`.
+fn parse(template: &str) -> Result<Vec<Part<'_>>, (usize, &'static str)> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut idx = 0;
+
+    while let Some(offset) = template[idx..].find('$') {
+        text.push_str(&template[idx..idx + offset]);
+        idx += offset;
+        match template.as_bytes().get(idx + 1) {
+            Some(b'$') => {
+                text.push('$');
+                idx += 2;
+            }
+            Some(b'{') => {
+                let inner = &template[idx + 2..];
+                let end = inner.find('}').ok_or((idx, "unterminated placeholder"))?;
+                let inner = &inner[..end];
+                let (path, default) = match inner.find(":-") {
+                    Some(split) => (&inner[..split], Some(&inner[split + 2..])),
+                    None => (inner, None),
+                };
+                if !text.is_empty() {
+                    parts.push(Part::Text(mem::take(&mut text)));
+                }
+                parts.push(Part::Placeholder {
+                    path,
+                    start: idx + 2,
+                    default,
+                });
+                idx += end + 3;
+            }
+            _ => {
+                text.push('$');
+                idx += 1;
+            }
+        }
+    }
+
+    text.push_str(&template[idx..]);
+    if !text.is_empty() || parts.is_empty() {
+        parts.push(Part::Text(text));
+    }
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    use super::*;
+    use crate::Map;
+
+    fn context() -> Value {
+        let mut user = Map::new();
+        user.insert("email", "jane@example.com");
+        let mut ctx = Map::new();
+        ctx.insert("host", "localhost");
+        ctx.insert("port", 8080u16);
+        ctx.insert("user", user);
+        ctx.insert("tags", Value::List(vec!["a".into(), "b".into()]));
+        Value::Map(ctx)
+    }
+
+    #[test]
+    fn render() {
+        let mut template = Map::new();
+        template.insert("url", "https://${host}:${port}/api");
+        template.insert("port", "${port}");
+        template.insert("to", "${user.email}");
+        template.insert("tag", "${tags[1]}");
+        template.insert("mode", "${mode:-dev}");
+        template.insert("escaped", "$${host} costs $5");
+
+        let out = Value::Map(template).render(&context()).unwrap();
+        assert_eq!(out["url"], Value::from("https://localhost:8080/api"));
+        assert_eq!(out["port"], Value::from(8080u16));
+        assert_eq!(out["to"], Value::from("jane@example.com"));
+        assert_eq!(out["tag"], Value::from("b"));
+        assert_eq!(out["mode"], Value::from("dev"));
+        assert_eq!(out["escaped"], Value::from("${host} costs $5"));
+    }
+
+    #[test]
+    fn errors() {
+        let template = Value::List(vec![
+            "${missing}".into(),
+            "tags: ${tags}".into(),
+            "${host".into(),
+            "port ${user..email}".into(),
+        ]);
+        let errors = template.render(&context()).unwrap_err().into_errors();
+        assert_eq!(errors.len(), 4);
+        assert!(matches!(
+            &errors[0],
+            TemplateError::Unresolved { placeholder, .. } if placeholder == "missing"
+        ));
+        assert_eq!(errors[0].location().to_string(), "[0]");
+        assert!(matches!(&errors[1], TemplateError::NotScalar { .. }));
+        assert!(matches!(
+            &errors[2],
+            TemplateError::Syntax { position: 0, .. }
+        ));
+        assert!(matches!(
+            &errors[3],
+            TemplateError::Syntax { position: 12, .. }
+        ));
+    }
+}
@@ -0,0 +1,318 @@
+//! TOML documents as values.
+//!
+//! With the `datetime` feature, TOML local dates become [`Value::Date`] and
+//! local or offset date-times become [`Value::DateTime`], offsets converted
+//! to UTC. Local times, and every datetime without the feature, are kept as
+//! strings.
+//!
+//! TOML is stricter than [`Value`]: documents must be tables, there is no
+//! null, no bytes and integers are 64 bit signed. Writing a value that
+//! doesn't fit reports where in a [`TomlError`].
+use std::fmt;
+
+use ::toml::value::{Array, Datetime, Table};
+
+use crate::{Map, Number, Path, Value};
+
+#[derive(Debug)]
+pub enum TomlError {
+    Parse(::toml::de::Error),
+    Serialize(::toml::ser::Error),
+    /// The top level value isn't a map.
+    NotATable,
+    /// A value with no TOML representation, like `None`, bytes or a date
+    /// outside the years 0000 to 9999.
+    Unsupported {
+        path: Path,
+        kind: &'static str,
+    },
+    /// A dict key that isn't a string.
+    NonStringKey {
+        path: Path,
+    },
+    /// An unsigned integer above `i64::MAX`.
+    OutOfRange {
+        path: Path,
+    },
+}
+
+impl fmt::Display for TomlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TomlError::Parse(err) => write!(f, "toml error: {}", err),
+            TomlError::Serialize(err) => write!(f, "toml error: {}", err),
+            TomlError::NotATable => write!(f, "toml documents must be tables"),
+            TomlError::Unsupported { path, kind } => {
+                write!(f, "{}: {} can't be represented in toml", path, kind)
+            }
+            TomlError::NonStringKey { path } => write!(f, "{}: toml keys must be strings", path),
+            TomlError::OutOfRange { path } => {
+                write!(f, "{}: integer out of range for toml", path)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TomlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TomlError::Parse(err) => Some(err),
+            TomlError::Serialize(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<::toml::de::Error> for TomlError {
+    fn from(err: ::toml::de::Error) -> Self {
+        TomlError::Parse(err)
+    }
+}
+
+impl From<::toml::ser::Error> for TomlError {
+    fn from(err: ::toml::ser::Error) -> Self {
+        TomlError::Serialize(err)
+    }
+}
+
+pub fn from_toml_str(input: &str) -> Result<Value, TomlError> {
+    let table = input.parse::<Table>()?;
+    Ok(from_table(table))
+}
+
+pub fn to_toml_string(value: &Value) -> Result<String, TomlError> {
+    let table = match value {
+        Value::Map(_) | Value::Dict(_) => match to_toml(value, &Path::new())? {
+            ::toml::Value::Table(table) => table,
+            _ => unreachable!(),
+        },
+        _ => return Err(TomlError::NotATable),
+    };
+    Ok(::toml::to_string(&table)?)
+}
+
+fn from_table(table: Table) -> Value {
+    Value::Map(
+        table
+            .into_iter()
+            .map(|(k, v)| (k, from_toml(v)))
+            .collect::<Map>(),
+    )
+}
+
+fn from_toml(value: ::toml::Value) -> Value {
+    match value {
+        ::toml::Value::String(s) => Value::String(s),
+        ::toml::Value::Integer(i) => Value::Number(Number::I64(i)),
+        ::toml::Value::Float(f) => Value::Number(Number::from(f)),
+        ::toml::Value::Boolean(b) => Value::Bool(b),
+        ::toml::Value::Datetime(datetime) => from_datetime(datetime),
+        ::toml::Value::Array(array) => Value::List(array.into_iter().map(from_toml).collect()),
+        ::toml::Value::Table(table) => from_table(table),
+    }
+}
+
+#[cfg(feature = "datetime")]
+fn from_datetime(datetime: Datetime) -> Value {
+    use ::toml::value::Offset;
+    use chrono::{Duration, NaiveDate, NaiveTime};
+
+    let Some(date) = datetime.date.and_then(|date| {
+        NaiveDate::from_ymd_opt(date.year.into(), date.month.into(), date.day.into())
+    }) else {
+        return Value::String(datetime.to_string());
+    };
+    let Some(time) = datetime.time else {
+        return Value::Date(date);
+    };
+    let Some(time) = NaiveTime::from_hms_nano_opt(
+        time.hour.into(),
+        time.minute.into(),
+        time.second.into(),
+        time.nanosecond,
+    ) else {
+        return Value::String(datetime.to_string());
+    };
+    let local = date.and_time(time);
+    Value::DateTime(match datetime.offset {
+        None | Some(Offset::Z) => local,
+        Some(Offset::Custom { minutes }) => local - Duration::minutes(minutes.into()),
+    })
+}
+
+#[cfg(not(feature = "datetime"))]
+fn from_datetime(datetime: Datetime) -> Value {
+    Value::String(datetime.to_string())
+}
+
+fn to_toml(value: &Value, path: &Path) -> Result<::toml::Value, TomlError> {
+    let value = match value {
+        Value::Bool(b) => ::toml::Value::Boolean(*b),
+        Value::Number(n) if n.is_float() => ::toml::Value::Float(n.as_f64()),
+        Value::Number(Number::U64(n)) => match i64::try_from(*n) {
+            Ok(n) => ::toml::Value::Integer(n),
+            Err(_) => return Err(TomlError::OutOfRange { path: path.clone() }),
+        },
+        Value::Number(n) => ::toml::Value::Integer(n.as_i64()),
+        Value::Char(c) => ::toml::Value::String(c.to_string()),
+        Value::String(s) => ::toml::Value::String(s.clone()),
+        Value::List(list) => ::toml::Value::Array(
+            list.iter()
+                .enumerate()
+                .map(|(idx, v)| to_toml(v, &path.clone().index(idx)))
+                .collect::<Result<Array, _>>()?,
+        ),
+        Value::Map(map) => ::toml::Value::Table(
+            map.iter()
+                .map(|(k, v)| {
+                    Ok::<_, TomlError>((k.clone(), to_toml(v, &path.clone().key(k.as_str()))?))
+                })
+                .collect::<Result<Table, _>>()?,
+        ),
+        Value::Dict(dict) => ::toml::Value::Table(
+            dict.iter()
+                .map(|(k, v)| match k {
+                    Value::String(k) => Ok((k.clone(), to_toml(v, &path.clone().key(k.as_str()))?)),
+                    _ => Err(TomlError::NonStringKey { path: path.clone() }),
+                })
+                .collect::<Result<Table, _>>()?,
+        ),
+        Value::Bytes(_) => return Err(unsupported(path, "bytes")),
+        #[cfg(feature = "datetime")]
+        Value::Date(date) => ::toml::Value::Datetime(Datetime {
+            date: Some(to_date(date).ok_or_else(|| unsupported(path, "date"))?),
+            time: None,
+            offset: None,
+        }),
+        #[cfg(feature = "datetime")]
+        Value::DateTime(datetime) => ::toml::Value::Datetime(Datetime {
+            date: Some(to_date(&datetime.date()).ok_or_else(|| unsupported(path, "datetime"))?),
+            time: Some(to_time(&datetime.time())),
+            offset: None,
+        }),
+        Value::None => return Err(unsupported(path, "none")),
+    };
+    Ok(value)
+}
+
+fn unsupported(path: &Path, kind: &'static str) -> TomlError {
+    TomlError::Unsupported {
+        path: path.clone(),
+        kind,
+    }
+}
+
+/// The TOML date, if the year has four digits.
+#[cfg(feature = "datetime")]
+fn to_date(date: &chrono::NaiveDate) -> Option<::toml::value::Date> {
+    use chrono::Datelike;
+
+    if !(0..=9999).contains(&date.year()) {
+        return None;
+    }
+    Some(::toml::value::Date {
+        year: date.year() as u16,
+        month: date.month() as u8,
+        day: date.day() as u8,
+    })
+}
+
+#[cfg(feature = "datetime")]
+fn to_time(time: &chrono::NaiveTime) -> ::toml::value::Time {
+    use chrono::Timelike;
+
+    ::toml::value::Time {
+        hour: time.hour() as u8,
+        minute: time.minute() as u8,
+        second: time.second() as u8,
+        nanosecond: time.nanosecond(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let input = "name = \"app\"\nport = 8080\n\n[server]\nhosts = [\"a\", \"b\"]\n";
+        let value = from_toml_str(input).unwrap();
+        assert_eq!(value["server"]["hosts"][1], Value::from("b"));
+        assert_eq!(value["port"], Value::from(8080i64));
+        assert_eq!(
+            from_toml_str(&to_toml_string(&value).unwrap()).unwrap(),
+            value
+        );
+    }
+
+    #[cfg(feature = "datetime")]
+    #[test]
+    fn datetimes() {
+        let value = from_toml_str(
+            "day = 2024-02-29\nlocal = 2024-02-29T10:30:00\noffset = 2024-02-29T10:30:00+02:00\ntime = 10:30:00\n",
+        )
+        .unwrap();
+        let day = chrono::NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        assert_eq!(value["day"], Value::Date(day));
+        assert_eq!(
+            value["local"],
+            Value::DateTime(day.and_hms_opt(10, 30, 0).unwrap())
+        );
+        assert_eq!(
+            value["offset"],
+            Value::DateTime(day.and_hms_opt(8, 30, 0).unwrap())
+        );
+        assert_eq!(value["time"], Value::from("10:30:00"));
+
+        let output = to_toml_string(&value).unwrap();
+        assert!(output.contains("day = 2024-02-29\n"), "{}", output);
+        assert!(
+            output.contains("local = 2024-02-29T10:30:00\n"),
+            "{}",
+            output
+        );
+
+        for year in [10000, -5] {
+            let date = chrono::NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
+            let mut map = Map::new();
+            map.insert("d", Value::Date(date));
+            assert!(matches!(
+                to_toml_string(&Value::Map(map)),
+                Err(TomlError::Unsupported { path, kind: "date" }) if path.to_string() == "d"
+            ));
+
+            let mut map = Map::new();
+            map.insert("dt", Value::DateTime(date.and_hms_opt(0, 0, 0).unwrap()));
+            assert!(matches!(
+                to_toml_string(&Value::Map(map)),
+                Err(TomlError::Unsupported {
+                    kind: "datetime",
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            to_toml_string(&Value::from("x")),
+            Err(TomlError::NotATable)
+        ));
+
+        let mut map = Map::new();
+        map.insert("list", Value::List(vec![Value::None]));
+        assert!(matches!(
+            to_toml_string(&Value::Map(map)),
+            Err(TomlError::Unsupported { path, kind: "none" }) if path.to_string() == "list[0]"
+        ));
+
+        let mut map = Map::new();
+        map.insert("big", u64::MAX);
+        assert!(matches!(
+            to_toml_string(&Value::Map(map)),
+            Err(TomlError::OutOfRange { .. })
+        ));
+        assert!(matches!(from_toml_str("a = "), Err(TomlError::Parse(_))));
+    }
+}
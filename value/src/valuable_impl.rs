@@ -0,0 +1,337 @@
+#[cfg(all(feature = "datetime", not(feature = "std")))]
+use alloc::string::ToString;
+use valuable::{Mappable, Valuable, Visit};
+
+use crate::{Dict, Map, Number, Value};
+
+impl Valuable for Number {
+    fn as_value(&self) -> valuable::Value<'_> {
+        match *self {
+            Number::U8(v) => valuable::Value::U8(v),
+            Number::U16(v) => valuable::Value::U16(v),
+            Number::U32(v) => valuable::Value::U32(v),
+            Number::U64(v) => valuable::Value::U64(v),
+            Number::I8(v) => valuable::Value::I8(v),
+            Number::I16(v) => valuable::Value::I16(v),
+            Number::I32(v) => valuable::Value::I32(v),
+            Number::I64(v) => valuable::Value::I64(v),
+            #[cfg(feature = "ordered_float")]
+            Number::F32(v) => valuable::Value::F32(*v),
+            #[cfg(feature = "ordered_float")]
+            Number::F64(v) => valuable::Value::F64(*v),
+            #[cfg(not(feature = "ordered_float"))]
+            Number::F32(v) => valuable::Value::F32(v),
+            #[cfg(not(feature = "ordered_float"))]
+            Number::F64(v) => valuable::Value::F64(v),
+        }
+    }
+
+    fn visit(&self, visit: &mut dyn Visit) {
+        visit.visit_value(self.as_value())
+    }
+}
+
+impl Valuable for Value {
+    fn as_value(&self) -> valuable::Value<'_> {
+        match self {
+            Value::Bool(v) => valuable::Value::Bool(*v),
+            Value::Number(n) => n.as_value(),
+            Value::Char(v) => valuable::Value::Char(*v),
+            Value::String(v) => valuable::Value::String(v),
+            Value::List(v) => valuable::Value::Listable(v),
+            Value::Map(v) => valuable::Value::Mappable(v),
+            Value::Dict(v) => valuable::Value::Mappable(v),
+            Value::Bytes(v) => valuable::Value::Listable(v),
+            #[cfg(feature = "datetime")]
+            Value::Date(_) | Value::DateTime(_) => valuable::Value::Tuplable(self),
+            Value::None => valuable::Value::Unit,
+        }
+    }
+
+    fn visit(&self, visit: &mut dyn Visit) {
+        match self {
+            Value::List(v) => v.visit(visit),
+            Value::Map(v) => v.visit(visit),
+            Value::Dict(v) => v.visit(visit),
+            Value::Bytes(v) => v.visit(visit),
+            #[cfg(feature = "datetime")]
+            Value::Date(v) => {
+                let s = v.to_string();
+                visit.visit_unnamed_fields(&[valuable::Value::String(&s)])
+            }
+            #[cfg(feature = "datetime")]
+            Value::DateTime(v) => {
+                let s = v.to_string();
+                visit.visit_unnamed_fields(&[valuable::Value::String(&s)])
+            }
+            _ => visit.visit_value(self.as_value()),
+        }
+    }
+}
+
+// Dates have no borrowed representation in valuable, so they are exposed as a
+// single element tuple holding the formatted date.
+#[cfg(feature = "datetime")]
+impl valuable::Tuplable for Value {
+    fn definition(&self) -> valuable::TupleDef {
+        valuable::TupleDef::new_static(1)
+    }
+}
+
+impl Valuable for Map {
+    fn as_value(&self) -> valuable::Value<'_> {
+        valuable::Value::Mappable(self)
+    }
+
+    fn visit(&self, visit: &mut dyn Visit) {
+        for (k, v) in self.iter() {
+            visit.visit_entry(k.as_value(), v.as_value());
+        }
+    }
+}
+
+impl Mappable for Map {
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), Some(self.len()))
+    }
+}
+
+impl Valuable for Dict {
+    fn as_value(&self) -> valuable::Value<'_> {
+        valuable::Value::Mappable(self)
+    }
+
+    fn visit(&self, visit: &mut dyn Visit) {
+        for (k, v) in self.iter() {
+            visit.visit_entry(k.as_value(), v.as_value());
+        }
+    }
+}
+
+impl Mappable for Dict {
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), Some(self.len()))
+    }
+}
+
+/// Record `value` into the field `name` of `span`. Scalars are recorded as
+/// primitives while lists and maps are recorded through `valuable` when
+/// tracing is built with `tracing_unstable`, and with their `Debug` output otherwise.
+/// Fields not declared on the span are ignored.
+#[cfg(feature = "tracing")]
+pub fn record_field(span: &tracing::Span, name: &str, value: &Value) {
+    if !span.has_field(name) {
+        return;
+    }
+
+    match value {
+        Value::Bool(v) => span.record(name, *v),
+        Value::Number(n) => match *n {
+            Number::U8(_) | Number::U16(_) | Number::U32(_) | Number::U64(_) => {
+                span.record(name, n.as_u64())
+            }
+            Number::F32(_) | Number::F64(_) => span.record(name, n.as_f64()),
+            _ => span.record(name, n.as_i64()),
+        },
+        Value::String(v) => span.record(name, v.as_str()),
+        Value::None => span,
+        #[cfg(tracing_unstable)]
+        v => span.record(name, tracing::field::valuable(v)),
+        #[cfg(not(tracing_unstable))]
+        v => span.record(name, tracing::field::debug(v)),
+    };
+}
+
+/// Record every entry of `fields` into the span field of the same name.
+#[cfg(feature = "tracing")]
+pub fn record_fields(span: &tracing::Span, fields: &Map) {
+    for (k, v) in fields.iter() {
+        record_field(span, k, v);
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl crate::redact::Redactor {
+    /// Redact `value` as the entry `name` of a map and record it into the
+    /// span field of that name, see [`record_field`].
+    pub fn record_field(&self, span: &tracing::Span, name: &str, value: &Value) {
+        let mut fields = Map::new();
+        fields.insert(name, value.clone());
+        self.record_fields(span, &fields);
+    }
+
+    /// Redact `fields` and record every entry into the span field of the same
+    /// name, see [`record_fields`].
+    pub fn record_fields(&self, span: &tracing::Span, fields: &Map) {
+        record_fields(span, &self.redact_map(fields));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "std"))]
+    use alloc::{string::String, vec};
+    use core::fmt::Write;
+
+    use super::*;
+
+    /// Writes what a value reports to a visitor as compact text.
+    #[derive(Default)]
+    struct Text(String);
+
+    impl Visit for Text {
+        fn visit_value(&mut self, value: valuable::Value<'_>) {
+            match value {
+                valuable::Value::Listable(list) => {
+                    self.0.push('[');
+                    list.visit(self);
+                    self.0.push(']');
+                }
+                valuable::Value::Mappable(map) => {
+                    self.0.push('{');
+                    map.visit(self);
+                    self.0.push('}');
+                }
+                valuable::Value::Tuplable(tuple) => {
+                    self.0.push('(');
+                    tuple.visit(self);
+                    self.0.push(')');
+                }
+                value => {
+                    let _ = write!(self.0, "{:?} ", value);
+                }
+            }
+        }
+
+        fn visit_entry(&mut self, key: valuable::Value<'_>, value: valuable::Value<'_>) {
+            self.visit_value(key);
+            self.0.push_str(": ");
+            self.visit_value(value);
+        }
+
+        fn visit_unnamed_fields(&mut self, values: &[valuable::Value<'_>]) {
+            for value in values {
+                self.visit_value(*value);
+            }
+        }
+    }
+
+    fn text(value: &Value) -> String {
+        let mut text = Text::default();
+        valuable::visit(value, &mut text);
+        text.0
+    }
+
+    #[test]
+    fn visit_nested() {
+        let mut inner = Map::new();
+        inner.insert("n", 1u8);
+        inner.insert("tags", Value::List(vec!["a".into(), Value::None]));
+        let mut outer = Map::new();
+        outer.insert("inner", inner);
+        outer.insert("bytes", Value::Bytes(vec![7]));
+        let dict = Value::from(
+            vec![(Value::from(2i32), Value::from(true))]
+                .into_iter()
+                .collect::<Dict>(),
+        );
+        outer.insert("dict", dict);
+
+        assert_eq!(
+            text(&Value::Map(outer)),
+            "{\"bytes\" : [7 ]\"dict\" : {2 : true }\"inner\" : {\"n\" : 1 \"tags\" : [\"a\" () ]}}"
+        );
+        assert!(matches!(
+            Value::from(1u8).as_value(),
+            valuable::Value::U8(1)
+        ));
+        assert!(matches!(
+            Value::from(-2i64).as_value(),
+            valuable::Value::I64(-2)
+        ));
+    }
+
+    #[cfg(feature = "datetime")]
+    #[test]
+    fn visit_dates() {
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        let value = Value::Date(date);
+        assert!(matches!(value.as_value(), valuable::Value::Tuplable(_)));
+        assert_eq!(text(&value), "(\"2024-02-29\" )");
+        let value = Value::DateTime(date.and_hms_opt(10, 30, 0).unwrap());
+        assert_eq!(text(&value), "(\"2024-02-29 10:30:00\" )");
+    }
+
+    #[cfg(all(feature = "tracing", feature = "std"))]
+    #[test]
+    fn record_redacted_fields() {
+        use std::sync::{Arc, Mutex};
+        use tracing::{field, span, Metadata};
+
+        type Recorded = Arc<Mutex<Vec<(String, String)>>>;
+
+        struct Subscriber(Recorded);
+
+        impl tracing::Subscriber for Subscriber {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+                span::Id::from_u64(1)
+            }
+
+            fn record(&self, _: &span::Id, values: &span::Record<'_>) {
+                values.record(&mut Fields(&self.0));
+            }
+
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+            fn event(&self, _: &tracing::Event<'_>) {}
+
+            fn enter(&self, _: &span::Id) {}
+
+            fn exit(&self, _: &span::Id) {}
+        }
+
+        struct Fields<'a>(&'a Recorded);
+
+        impl field::Visit for Fields<'_> {
+            fn record_debug(&mut self, field: &field::Field, value: &dyn core::fmt::Debug) {
+                let value = format!("{:?}", value);
+                self.0.lock().unwrap().push((field.name().into(), value));
+            }
+
+            fn record_str(&mut self, field: &field::Field, value: &str) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push((field.name().into(), value.into()));
+            }
+        }
+
+        let recorded = Recorded::default();
+        let mut user = Map::new();
+        user.insert("password", "hunter2");
+        user.insert("name", "ann");
+        let mut fields = Map::new();
+        fields.insert("token", "abc");
+        fields.insert("user", user);
+        fields.insert("undeclared", 1u8);
+
+        let redactor = crate::Redactor::new().keys(["token", "password"]);
+        tracing::subscriber::with_default(Subscriber(recorded.clone()), || {
+            let span = tracing::info_span!("call", token = field::Empty, user = field::Empty);
+            redactor.record_fields(&span, &fields);
+            redactor.record_field(&span, "token", &Value::from("abc"));
+        });
+
+        let recorded = recorded.lock().unwrap();
+        let names = recorded.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["token", "user", "token"]);
+        assert_eq!(recorded[0].1, crate::REDACTED);
+        assert!(recorded[1].1.contains("ann"), "{}", recorded[1].1);
+        assert!(!recorded[1].1.contains("hunter2"), "{}", recorded[1].1);
+        assert_eq!(recorded[2].1, crate::REDACTED);
+    }
+}
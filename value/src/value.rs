@@ -3,13 +3,14 @@ use alloc::{
     // borrow::ToOwned,
     // collections::BTreeMap,
     // format,
+    boxed::Box,
     string::String,
     vec::Vec,
 };
 #[cfg(feature = "std")]
 use std::string::String;
 
-use crate::{number::Number, Map};
+use crate::{number::Number, Dict, Map};
 
 #[cfg(feature = "serde")]
 use super::de::DeserializerError;
@@ -121,18 +122,45 @@ macro_rules! as_method {
     feature = "ordered_float",
     derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)
 )]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(
+        derive(Debug),
+        serialize_bounds(
+            __S: rkyv::ser::Writer + rkyv::ser::Allocator,
+            __S::Error: rkyv::rancor::Source,
+        ),
+        deserialize_bounds(__D::Error: rkyv::rancor::Source),
+        bytecheck(bounds(
+            __C: rkyv::validation::ArchiveContext,
+            __C::Error: rkyv::rancor::Source,
+        )),
+    )
+)]
 pub enum Value {
     Bool(bool),
     Number(Number),
     Char(char),
     String(String),
-    List(Vec<Value>),
-    Map(Map),
+    List(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Vec<Value>),
+    Map(#[cfg_attr(feature = "rkyv", rkyv(omit_bounds))] Map),
+    /// A map with non-string keys, see [`Dict`]. Boxed to keep `Value` small.
+    Dict(
+        #[cfg_attr(
+            feature = "rkyv",
+            rkyv(omit_bounds, with = crate::rkyv_impl::DictEntries)
+        )]
+        Box<Dict>,
+    ),
     Bytes(Vec<u8>),
     #[cfg(feature = "datetime")]
-    Date(chrono::NaiveDate),
+    Date(#[cfg_attr(feature = "rkyv", rkyv(with = crate::rkyv_impl::Date))] chrono::NaiveDate),
     #[cfg(feature = "datetime")]
-    DateTime(chrono::NaiveDateTime),
+    DateTime(
+        #[cfg_attr(feature = "rkyv", rkyv(with = crate::rkyv_impl::DateTime))]
+        chrono::NaiveDateTime,
+    ),
     None,
 }
 
@@ -182,6 +210,7 @@ impl Value {
     is_method!(is_bool, Bool);
     is_method!(is_list, List);
     is_method!(is_map, Map);
+    is_method!(is_dict, Dict);
     is_method!(is_char, Char);
 
     pub fn is_none(&self) -> bool {
@@ -203,6 +232,7 @@ impl Value {
     as_method!(as_bool, as_bool_mut, Bool, bool);
     as_method!(as_list, as_list_mut, List, Vec<Value>);
     as_method!(as_map, as_map_mut, Map, Map);
+    as_method!(as_dict, as_dict_mut, Dict, Dict);
     as_method!(as_char, as_char_as, Char, char);
 
     #[cfg(feature = "datetime")]
@@ -220,6 +250,13 @@ impl Value {
     into_method!(into_bool, Bool, bool);
     into_method!(into_list, List, Vec<Value>);
     into_method!(into_map, Map, Map);
+    pub fn into_dict(self) -> Result<Dict, Value> {
+        match self {
+            Value::Dict(v) => Ok(*v),
+            _ => Err(self),
+        }
+    }
+
     into_method!(into_char, Char, char);
     into_method!(into_number, Number, Number);
 
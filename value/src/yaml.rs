@@ -0,0 +1,508 @@
+//! YAML documents as values.
+//!
+//! Mappings with only string keys become [`Value::Map`], any other mapping a
+//! [`Value::Dict`]. Scalars tagged `!!binary` are base64 decoded to
+//! [`Value::Bytes`] and bytes are written back the same way; unknown tags are
+//! ignored. Anchors and aliases are expanded, and only the first document of
+//! a stream is read. Expanded aliases count towards [`Limits`], so a small
+//! document can't alias its way into an exponentially large value.
+use std::{collections::BTreeMap, fmt, fmt::Write};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use yaml_rust2::{
+    parser::{Event, MarkedEventReceiver, Parser, Tag},
+    scanner::{Marker, TScalarStyle},
+    ScanError, Yaml,
+};
+
+use crate::{dict::MapBuilder, LimitError, Limits, Number, Value};
+
+const CORE_SCHEMA: &str = "tag:yaml.org,2002:";
+
+#[derive(Debug)]
+pub enum YamlError {
+    Scan(ScanError),
+    /// A scalar that doesn't match its tag, like `!!int abc` or a
+    /// `!!binary` scalar that isn't base64.
+    InvalidScalar {
+        line: usize,
+        column: usize,
+        tag: String,
+    },
+    /// The document, with its aliases expanded, exceeds the [`Limits`].
+    Limit {
+        line: usize,
+        error: LimitError,
+    },
+}
+
+impl YamlError {
+    /// The 1-based line the error occurred on.
+    pub fn line(&self) -> usize {
+        match self {
+            YamlError::Scan(err) => err.marker().line(),
+            YamlError::InvalidScalar { line, .. } | YamlError::Limit { line, .. } => *line,
+        }
+    }
+}
+
+impl fmt::Display for YamlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            YamlError::Scan(err) => write!(f, "yaml error: {}", err),
+            YamlError::InvalidScalar { line, column, tag } => {
+                write!(
+                    f,
+                    "invalid !!{} scalar at line {} column {}",
+                    tag, line, column
+                )
+            }
+            YamlError::Limit { line, error } => write!(f, "{} at line {}", error, line),
+        }
+    }
+}
+
+impl std::error::Error for YamlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            YamlError::Scan(err) => Some(err),
+            YamlError::InvalidScalar { .. } => None,
+            YamlError::Limit { error, .. } => Some(error),
+        }
+    }
+}
+
+impl From<ScanError> for YamlError {
+    fn from(err: ScanError) -> Self {
+        YamlError::Scan(err)
+    }
+}
+
+/// Parse the first document of `input` within the default [`Limits`]. An
+/// empty stream is [`Value::None`].
+pub fn from_yaml_str(input: &str) -> Result<Value, YamlError> {
+    from_yaml_str_with_limits(input, Limits::default())
+}
+
+/// Parse the first document of `input`, failing once the value would exceed
+/// `limits`.
+pub fn from_yaml_str_with_limits(input: &str, limits: Limits) -> Result<Value, YamlError> {
+    let mut loader = Loader {
+        stack: Vec::new(),
+        anchors: BTreeMap::new(),
+        root: None,
+        error: None,
+        limits,
+        nodes: 0,
+    };
+    Parser::new_from_str(input).load(&mut loader, false)?;
+    if let Some(err) = loader.error {
+        return Err(err);
+    }
+    Ok(loader.root.unwrap_or(Value::None))
+}
+
+/// Write `value` as a block style YAML document.
+pub fn to_yaml_string(value: &Value) -> String {
+    let mut out = String::new();
+    emit(&mut out, value, 0);
+    out.push('\n');
+    out
+}
+
+/// An open collection with its anchor and the node count when it started.
+enum Frame {
+    List(Vec<Value>, usize, usize),
+    Map(MapBuilder, Option<Value>, usize, usize),
+}
+
+struct Loader {
+    stack: Vec<Frame>,
+    /// Anchored values with their node counts.
+    anchors: BTreeMap<usize, (Value, usize)>,
+    root: Option<Value>,
+    error: Option<YamlError>,
+    limits: Limits,
+    nodes: usize,
+}
+
+impl Loader {
+    /// Account for `nodes` more values, recording the error on overflow.
+    fn count(&mut self, nodes: usize, mark: Marker) -> bool {
+        self.nodes = self.nodes.saturating_add(nodes);
+        let ret = self
+            .limits
+            .check_nodes(self.nodes)
+            .and_then(|_| self.limits.check_depth(self.stack.len()));
+        match ret {
+            Ok(()) => true,
+            Err(error) => {
+                self.error = Some(YamlError::Limit {
+                    line: mark.line(),
+                    error,
+                });
+                false
+            }
+        }
+    }
+
+    fn push(&mut self, value: Value, anchor: usize, nodes: usize) {
+        if anchor > 0 {
+            self.anchors.insert(anchor, (value.clone(), nodes));
+        }
+        match self.stack.last_mut() {
+            Some(Frame::List(list, ..)) => list.push(value),
+            Some(Frame::Map(map, key, ..)) => match key.take() {
+                Some(key) => map.insert(key, value),
+                None => *key = Some(value),
+            },
+            None => {
+                self.root.get_or_insert(value);
+            }
+        }
+    }
+}
+
+impl MarkedEventReceiver for Loader {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        if self.error.is_some() {
+            return;
+        }
+        match event {
+            Event::SequenceStart(anchor, _) => {
+                let start = self.nodes;
+                self.stack.push(Frame::List(Vec::new(), anchor, start));
+                self.count(1, mark);
+            }
+            Event::MappingStart(anchor, _) => {
+                let start = self.nodes;
+                self.stack
+                    .push(Frame::Map(MapBuilder::new(), None, anchor, start));
+                self.count(1, mark);
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                let (value, anchor, start) = match self.stack.pop() {
+                    Some(Frame::List(list, anchor, start)) => (Value::List(list), anchor, start),
+                    Some(Frame::Map(map, _, anchor, start)) => (map.into_value(), anchor, start),
+                    None => return,
+                };
+                let nodes = self.nodes - start;
+                self.push(value, anchor, nodes);
+            }
+            Event::Scalar(value, style, anchor, tag) => match scalar(value, style, tag) {
+                Ok(value) => {
+                    if self.count(1, mark) {
+                        self.push(value, anchor, 1);
+                    }
+                }
+                Err(tag) => {
+                    self.error = Some(YamlError::InvalidScalar {
+                        line: mark.line(),
+                        column: mark.col() + 1,
+                        tag,
+                    })
+                }
+            },
+            Event::Alias(anchor) => {
+                let nodes = self.anchors.get(&anchor).map_or(1, |(_, nodes)| *nodes);
+                // Check before cloning, the expansion may be huge.
+                if self.count(nodes, mark) {
+                    let value = self
+                        .anchors
+                        .get(&anchor)
+                        .map_or(Value::None, |(value, _)| value.clone());
+                    self.push(value, 0, nodes);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Resolve a scalar with the core schema, returning the tag on mismatch.
+fn scalar(value: String, style: TScalarStyle, tag: Option<Tag>) -> Result<Value, String> {
+    let tag = match tag {
+        Some(tag) if tag.handle == CORE_SCHEMA => tag.suffix,
+        _ if style != TScalarStyle::Plain => return Ok(Value::String(value)),
+        _ => return Ok(plain(&value)),
+    };
+    let resolved = match tag.as_str() {
+        "str" => Some(Value::String(value)),
+        "binary" => {
+            let data = value.split_whitespace().collect::<String>();
+            STANDARD.decode(data).ok().map(Value::Bytes)
+        }
+        "null" => Some(plain(&value)).filter(Value::is_none),
+        "bool" => Some(plain(&value)).filter(Value::is_bool),
+        "int" => Some(plain(&value)).filter(|v| matches!(v, Value::Number(n) if !n.is_float())),
+        "float" => match plain(&value) {
+            Value::Number(n) => Some(Value::Number(Number::from(n.as_f64()))),
+            _ => None,
+        },
+        _ => Some(Value::String(value)),
+    };
+    resolved.ok_or(tag)
+}
+
+fn plain(value: &str) -> Value {
+    if let Ok(n) = value.parse::<u64>() {
+        return Value::Number(Number::U64(n));
+    }
+    match Yaml::from_str(value) {
+        Yaml::Null => Value::None,
+        Yaml::Boolean(b) => Value::Bool(b),
+        Yaml::Integer(i) => Value::Number(Number::I64(i)),
+        Yaml::Real(real) => match Yaml::Real(real).as_f64() {
+            Some(f) => Value::Number(Number::from(f)),
+            None => Value::String(value.to_string()),
+        },
+        _ => Value::String(value.to_string()),
+    }
+}
+
+/// Scalars and empty collections, which are written on the same line.
+fn is_inline(value: &Value) -> bool {
+    match value {
+        Value::List(list) => list.is_empty(),
+        Value::Map(map) => map.is_empty(),
+        Value::Dict(dict) => dict.is_empty(),
+        _ => true,
+    }
+}
+
+/// Write `value` starting at the cursor, continuing lines at `indent`.
+fn emit(out: &mut String, value: &Value, indent: usize) {
+    let entry = |out: &mut String, idx: usize| {
+        if idx > 0 {
+            out.push('\n');
+            out.extend(core::iter::repeat_n(' ', indent));
+        }
+    };
+    let value_after = |out: &mut String, value: &Value| {
+        if is_inline(value) {
+            out.push(' ');
+            emit_flow(out, value);
+        } else {
+            out.push('\n');
+            out.extend(core::iter::repeat_n(' ', indent + 2));
+            emit(out, value, indent + 2);
+        }
+    };
+    match value {
+        Value::List(list) if !list.is_empty() => {
+            for (idx, item) in list.iter().enumerate() {
+                entry(out, idx);
+                out.push_str("- ");
+                emit(out, item, indent + 2);
+            }
+        }
+        Value::Map(map) if !map.is_empty() => {
+            for (idx, (key, value)) in map.iter().enumerate() {
+                entry(out, idx);
+                emit_str(out, key);
+                out.push(':');
+                value_after(out, value);
+            }
+        }
+        Value::Dict(dict) if !dict.is_empty() => {
+            for (idx, (key, value)) in dict.iter().enumerate() {
+                entry(out, idx);
+                emit_flow(out, key);
+                out.push(':');
+                value_after(out, value);
+            }
+        }
+        value => emit_flow(out, value),
+    }
+}
+
+fn emit_flow(out: &mut String, value: &Value) {
+    match value {
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) if n.is_float() => {
+            let f = n.as_f64();
+            if f.is_nan() {
+                out.push_str(".nan");
+            } else if f.is_infinite() {
+                out.push_str(if f > 0.0 { ".inf" } else { "-.inf" });
+            } else if f.fract() == 0.0 && f.abs() < 1e16 {
+                let _ = write!(out, "{:.1}", f);
+            } else if f.fract() == 0.0 {
+                // `{}` would print all the digits without a `.`, reading back
+                // as an integer.
+                let _ = write!(out, "{:e}", f);
+            } else {
+                let _ = write!(out, "{}", f);
+            }
+        }
+        Value::Number(n) => {
+            let _ = write!(out, "{}", n);
+        }
+        Value::Char(c) => emit_str(out, c.encode_utf8(&mut [0; 4])),
+        Value::String(s) => emit_str(out, s),
+        Value::List(list) => {
+            out.push('[');
+            for (idx, item) in list.iter().enumerate() {
+                if idx > 0 {
+                    out.push_str(", ");
+                }
+                emit_flow(out, item);
+            }
+            out.push(']');
+        }
+        Value::Map(map) => {
+            out.push('{');
+            for (idx, (key, value)) in map.iter().enumerate() {
+                if idx > 0 {
+                    out.push_str(", ");
+                }
+                emit_str(out, key);
+                out.push_str(": ");
+                emit_flow(out, value);
+            }
+            out.push('}');
+        }
+        Value::Dict(dict) => {
+            out.push('{');
+            for (idx, (key, value)) in dict.iter().enumerate() {
+                if idx > 0 {
+                    out.push_str(", ");
+                }
+                emit_flow(out, key);
+                out.push_str(": ");
+                emit_flow(out, value);
+            }
+            out.push('}');
+        }
+        Value::Bytes(bytes) => {
+            out.push_str("!!binary ");
+            out.push_str(&STANDARD.encode(bytes));
+        }
+        #[cfg(feature = "datetime")]
+        Value::Date(date) => {
+            let _ = write!(out, "{}", date);
+        }
+        #[cfg(feature = "datetime")]
+        Value::DateTime(datetime) => {
+            let _ = write!(out, "{}T{}", datetime.date(), datetime.time());
+        }
+        Value::None => out.push_str("null"),
+    }
+}
+
+/// Write a string plain when it reads back as the same string, otherwise
+/// double quoted.
+fn emit_str(out: &mut String, s: &str) {
+    let plain = s
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '/')
+        && !s.ends_with(' ')
+        && s.chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | ' '))
+        && matches!(Yaml::from_str(s), Yaml::String(_))
+        && !matches!(
+            s.to_ascii_lowercase().as_str(),
+            "y" | "n" | "yes" | "no" | "on" | "off"
+        );
+    if plain {
+        out.push_str(s);
+        return;
+    }
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Map;
+
+    #[test]
+    fn round_trip() {
+        let value = from_yaml_str(
+            "name: app\nport: 8080\nratio: -0.5\nlogo: !!binary aGVsbG8=\n1: one\nhosts:\n  - a\n  - {x: [1, 2], y: \"true\"}\nempty: []\nbase: &base {v: 1}\ncopy: *base\n",
+        )
+        .unwrap();
+        let Value::Dict(dict) = &value else {
+            panic!("expected dict, got {:?}", value);
+        };
+        assert_eq!(
+            dict.get(&Value::from("logo")),
+            Some(&Value::Bytes(b"hello".to_vec()))
+        );
+        assert_eq!(dict.get(&Value::from(1u64)), Some(&Value::from("one")));
+        assert_eq!(
+            dict.get(&Value::from("copy")),
+            dict.get(&Value::from("base"))
+        );
+
+        let yaml = to_yaml_string(&value);
+        assert!(yaml.contains("logo: !!binary aGVsbG8=\n"), "{}", yaml);
+        assert_eq!(from_yaml_str(&yaml).unwrap(), value);
+
+        let mut map = Map::new();
+        map.insert("port", 8080u64);
+        map.insert("text", "line\n\"quoted\": yes");
+        let value = Value::Map(map);
+        assert_eq!(from_yaml_str(&to_yaml_string(&value)).unwrap(), value);
+        assert_eq!(from_yaml_str("").unwrap(), Value::None);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            from_yaml_str("a: 1\ndata: !!binary '***'"),
+            Err(YamlError::InvalidScalar { line: 2, ref tag, .. }) if tag == "binary"
+        ));
+        assert!(matches!(
+            from_yaml_str("n: !!int abc"),
+            Err(YamlError::InvalidScalar { .. })
+        ));
+        assert!(matches!(from_yaml_str("a: [1"), Err(YamlError::Scan(_))));
+
+        let mut laughs = String::from("a: &a [x, x, x, x, x, x, x, x, x, x]\n");
+        for i in 1..9u8 {
+            let (prev, name) = ((b'a' + i - 1) as char, (b'a' + i) as char);
+            let _ = writeln!(
+                laughs,
+                "{0}: &{0} [*{1}, *{1}, *{1}, *{1}, *{1}, *{1}, *{1}, *{1}, *{1}, *{1}]",
+                name, prev
+            );
+        }
+        assert!(matches!(
+            from_yaml_str(&laughs),
+            Err(YamlError::Limit {
+                error: LimitError::Nodes(_),
+                ..
+            })
+        ));
+        let limits = Limits::default().with_max_nodes(30);
+        assert!(from_yaml_str_with_limits("a: &a [1, 2]\nb: [*a, *a]", limits).is_ok());
+        assert!(
+            from_yaml_str_with_limits("a: &a [1, 2]\nb: [*a, *a]", limits.with_max_nodes(8))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn large_floats() {
+        for f in [1e16, -1e17, 1e300, 1.5e20] {
+            let value = Value::from(f);
+            assert_eq!(from_yaml_str(&to_yaml_string(&value)).unwrap(), value);
+        }
+    }
+}